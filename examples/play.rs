@@ -1,10 +1,11 @@
 use coat::{
     app::App,
     state::Mutable,
+    text::format::IntegerFormatter,
     ui::Ui,
     widgets::{
         flex::{CrossAxisAlignment, Flex, MainAxisAlignment},
-        Button, Label, Padding, SizedBox, TextBox,
+        Button, Label, Padding, SizedBox, TextBox, ValueTextBox,
     },
 };
 
@@ -31,6 +32,21 @@ fn app(ui: &mut Ui) {
 
                 SizedBox::new().height(10.0).empty(ui);
 
+                Mutable::with(|| 1000).use_in(ui, |ui, number: &mut i64| {
+                    let formatter = IntegerFormatter::new()
+                        .min(0)
+                        .max(1_000_000)
+                        .thousands_separator(',');
+                    let action = ValueTextBox::new(number, formatter)
+                        .validate_while_editing(true)
+                        .build(ui);
+                    if let Some(number) = action.committed {
+                        println!("Committed {}", number);
+                    }
+                });
+
+                SizedBox::new().height(10.0).empty(ui);
+
                 fn btn(name: &str, count: usize) -> String {
                     format!("{} button clicked {} times", name, count)
                 }
@@ -2,6 +2,8 @@ use crate::{
     context::{ContextState, EventCtx, LayoutCtx, PaintCtx},
     id::{ChildCounter, ChildId},
     kurbo::Point,
+    piet::{FontFamily, RenderContext},
+    text::{FontError, Fonts},
     tree::{Child, Children},
     ui::Ui,
 };
@@ -9,17 +11,31 @@ use druid::{ExtEventSink, WindowDesc};
 
 pub struct App {
     name: String,
+    fonts: Fonts,
 }
 
 impl App {
     pub fn new(name: impl Into<String>) -> Self {
-        App { name: name.into() }
+        App {
+            name: name.into(),
+            fonts: Fonts::default(),
+        }
+    }
+
+    /// Register the data of a font that is shipped with the app, such as an
+    /// icon font, returning the family to use it with.
+    ///
+    /// The font is loaded before the app first lays out text. If the platform
+    /// can't load it, text in its family is shown in the default font.
+    pub fn register_font(&mut self, data: impl Into<Vec<u8>>) -> Result<FontFamily, FontError> {
+        self.fonts.register(data.into())
     }
 
     pub fn run(self, app: impl FnMut(&mut Ui) + 'static) -> Result<(), druid::PlatformError> {
         simple_logger::SimpleLogger::new().init().unwrap();
 
-        let window = WindowDesc::new(|| AppWidget::new(app)).title(self.name);
+        let fonts = self.fonts;
+        let window = WindowDesc::new(|| AppWidget::new(app, fonts)).title(self.name);
         druid::AppLauncher::with_window(window).launch(())
     }
 }
@@ -31,10 +47,11 @@ struct AppWidget {
     focus_widget: Option<ChildId>,
     ext_event_sink: Option<ExtEventSink>,
     mouse_pos: Option<Point>,
+    fonts: Fonts,
 }
 
 impl AppWidget {
-    pub fn new(app: impl FnMut(&mut Ui) + 'static) -> Self {
+    pub fn new(app: impl FnMut(&mut Ui) + 'static, fonts: Fonts) -> Self {
         AppWidget {
             app: Box::new(app),
             root: Children::new(),
@@ -42,6 +59,7 @@ impl AppWidget {
             focus_widget: None,
             ext_event_sink: None,
             mouse_pos: None,
+            fonts,
         }
     }
 
@@ -80,10 +98,11 @@ impl druid::Widget<AppWidgetData> for AppWidget {
             window_id: ctx.window_id(),
             window: &ctx.window().clone(),
             text: ctx.text().clone(),
+            fonts: &self.fonts,
             focus_widget,
         };
 
-        let root = self.root();
+        let root = &mut self.root.renders[0];
         let mut event_ctx = EventCtx {
             state: &mut context_state,
             child_state: &mut root.state,
@@ -104,12 +123,14 @@ impl druid::Widget<AppWidgetData> for AppWidget {
                 crate::tree::FocusChange::Previous => {}
             }
         }
-        if self.focus_widget != old_focus_widget {
+        let focus_changed = self.focus_widget != old_focus_widget;
+        if focus_changed {
             let new_focus_widget = self.focus_widget;
             self.root().update_focus(new_focus_widget);
         }
 
-        let mut needs_update = self.root().needs_update();
+        // widgets may be built differently while they are focused
+        let mut needs_update = self.root().needs_update() || focus_changed;
         while needs_update {
             needs_update = self.root().needs_update();
 
@@ -119,7 +140,8 @@ impl druid::Widget<AppWidgetData> for AppWidget {
                 window_id: ctx.window_id(),
                 window: &ctx.window().clone(),
                 text: ctx.text().clone(),
-                focus_widget,
+                fonts: &self.fonts,
+                focus_widget: self.focus_widget,
             };
             let mut cx = Ui::new(&mut self.root, &mut context_state, &mut self.child_counter);
             (self.app)(&mut cx);
@@ -142,6 +164,7 @@ impl druid::Widget<AppWidgetData> for AppWidget {
                 window_id: ctx.window_id(),
                 window: &ctx.window().clone(),
                 text: ctx.text().clone(),
+                fonts: &self.fonts,
                 focus_widget: self.focus_widget,
             };
             let mut cx = Ui::new(&mut self.root, &mut context_state, &mut self.child_counter);
@@ -169,6 +192,8 @@ impl druid::Widget<AppWidgetData> for AppWidget {
         _data: &AppWidgetData,
         env: &druid::Env,
     ) -> druid::Size {
+        // fonts are loaded before any text is laid out
+        self.fonts.load(ctx.text());
         let ext_handle = ctx.get_external_handle();
         let mouse_pos = self.mouse_pos;
 
@@ -177,10 +202,11 @@ impl druid::Widget<AppWidgetData> for AppWidget {
             window_id: ctx.window_id(),
             window: &ctx.window().clone(),
             text: ctx.text().clone(),
+            fonts: &self.fonts,
             focus_widget: self.focus_widget,
         };
 
-        let root = self.root();
+        let root = &mut self.root.renders[0];
         let mut layout_ctx = LayoutCtx {
             state: &mut context_state,
             child_state: &mut root.state,
@@ -200,10 +226,11 @@ impl druid::Widget<AppWidgetData> for AppWidget {
             window_id: ctx.window_id(),
             window: &ctx.window().clone(),
             text: ctx.text().clone(),
+            fonts: &self.fonts,
             focus_widget: self.focus_widget,
         };
 
-        let root = self.root();
+        let root = &mut self.root.renders[0];
         let mut paint_ctx = PaintCtx {
             state: &mut context_state,
             child_state: &mut root.state,
@@ -215,5 +242,19 @@ impl druid::Widget<AppWidgetData> for AppWidget {
         };
 
         root.object.paint(&mut paint_ctx, &mut root.children);
+
+        // paint operations with a z-index are run after the whole tree, such
+        // as popups that are painted above their siblings
+        let mut z_ops = std::mem::take(&mut paint_ctx.z_ops);
+        z_ops.sort_by_key(|op| op.z_index);
+        let region = paint_ctx.region.clone();
+        for z_op in z_ops {
+            paint_ctx.with_child_ctx(region.clone(), |ctx| {
+                ctx.with_save(|ctx| {
+                    ctx.render_ctx.transform(z_op.transform);
+                    (z_op.paint_func)(ctx);
+                });
+            });
+        }
     }
 }
@@ -2,6 +2,7 @@ use crate::{
     id::ChildId,
     kurbo::{Affine, Insets, Point, Rect, Size},
     piet::{Piet, PietText, RenderContext},
+    text::Fonts,
     tree::{ChildState, CursorChange, FocusChange},
 };
 use druid::{Cursor, ExtEventSink, Region, TimerToken, WindowHandle, WindowId};
@@ -30,6 +31,8 @@ pub(crate) struct ContextState<'a> {
     pub(crate) window_id: WindowId,
     pub(crate) window: &'a WindowHandle,
     pub(crate) text: PietText,
+    /// The fonts that are shipped with the app.
+    pub(crate) fonts: &'a Fonts,
     /// The id of the widget that currently has focus.
     pub(crate) focus_widget: Option<ChildId>,
 }
@@ -85,6 +88,13 @@ impl_context_method!(LayoutCtx<'_, '_>, PaintCtx<'_, '_, '_>, {
     }
 });
 
+impl<'b> LayoutCtx<'_, 'b> {
+    /// The fonts that are shipped with the app, to build text layouts with.
+    pub fn fonts(&self) -> &'b Fonts {
+        self.state.fonts
+    }
+}
+
 // methods on everyone
 impl_context_method!(
     EventCtx<'_, '_>,
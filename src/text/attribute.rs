@@ -16,11 +16,13 @@
 
 use std::ops::Range;
 
+use super::font_descriptor::{self, FontDescriptor};
+use super::{ArcStr, Fonts};
 use crate::piet::{Color, FontFamily, FontStyle, FontWeight, TextAttribute as PietAttr};
-use druid::{Env, FontDescriptor, KeyOrValue};
+use druid::{Env, KeyOrValue};
 
 /// A collection of spans of attributes of various kinds.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct AttributeSpans {
     family: SpanSet<FontFamily>,
     size: SpanSet<KeyOrValue<f64>>,
@@ -29,12 +31,29 @@ pub struct AttributeSpans {
     style: SpanSet<FontStyle>,
     underline: SpanSet<bool>,
     font_descriptor: SpanSet<KeyOrValue<FontDescriptor>>,
+    link: SpanSet<ArcStr>,
+    background: SpanSet<KeyOrValue<Color>>,
+    strikethrough: SpanSet<bool>,
+    letter_spacing: SpanSet<f64>,
+    baseline_shift: SpanSet<f64>,
+}
+
+/// The attributes that piet can't apply to a text layout, resolved against
+/// an [`Env`]. These are drawn by [`TextLayout::draw`] instead.
+///
+/// [`Env`]: ../struct.Env.html
+/// [`TextLayout::draw`]: struct.TextLayout.html#method.draw
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Decorations {
+    pub background: Vec<(Range<usize>, Color)>,
+    pub letter_spacing: Vec<(Range<usize>, f64)>,
+    pub baseline_shift: Vec<(Range<usize>, f64)>,
 }
 
 /// A set of spans for a given attribute.
 ///
 /// Invariant: the spans are sorted and non-overlapping.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 struct SpanSet<T> {
     spans: Vec<Span<T>>,
 }
@@ -91,6 +110,32 @@ pub enum Attribute {
     Underline(bool),
     /// A [`FontDescriptor`](struct.FontDescriptor.html).
     Descriptor(KeyOrValue<FontDescriptor>),
+    /// A link, with a payload that identifies its target.
+    ///
+    /// Links are not styled on their own; widgets that display rich text
+    /// may report clicks on them, and underline them while hovered.
+    Link(ArcStr),
+    /// The background color of the text, drawn behind the span.
+    BackgroundColor(KeyOrValue<Color>),
+    /// Strikethrough.
+    Strikethrough(bool),
+    /// Extra space, in points, added after each grapheme cluster.
+    ///
+    /// This does not affect line breaking, but is included in the size of
+    /// the text and when finding the text at a point.
+    LetterSpacing(f64),
+    /// The distance, in points, that the text is raised above the baseline;
+    /// negative values lower it, as for subscripts.
+    BaselineShift(f64),
+}
+
+impl Decorations {
+    /// Add the decorations of `other`, which are drawn after these.
+    pub fn extend(&mut self, other: Decorations) {
+        self.background.extend(other.background);
+        self.letter_spacing.extend(other.letter_spacing);
+        self.baseline_shift.extend(other.baseline_shift);
+    }
 }
 
 impl AttributeSpans {
@@ -109,24 +154,85 @@ impl AttributeSpans {
             Attribute::Style(attr) => self.style.add(Span::new(range, attr)),
             Attribute::Underline(attr) => self.underline.add(Span::new(range, attr)),
             Attribute::Descriptor(attr) => self.font_descriptor.add(Span::new(range, attr)),
+            Attribute::Link(attr) => self.link.add(Span::new(range, attr)),
+            Attribute::BackgroundColor(attr) => self.background.add(Span::new(range, attr)),
+            Attribute::Strikethrough(attr) => self.strikethrough.add(Span::new(range, attr)),
+            Attribute::LetterSpacing(attr) => self.letter_spacing.add(Span::new(range, attr)),
+            Attribute::BaselineShift(attr) => self.baseline_shift.add(Span::new(range, attr)),
         }
     }
 
-    pub(crate) fn to_piet_attrs(&self, env: &Env) -> Vec<(Range<usize>, PietAttr)> {
+    pub(crate) fn to_decorations(&self, env: &Env) -> Decorations {
+        Decorations {
+            background: self
+                .background
+                .iter()
+                .map(|s| (s.range.clone(), s.attr.resolve(env)))
+                .collect(),
+            letter_spacing: self
+                .letter_spacing
+                .iter()
+                .filter(|s| s.attr != 0.0)
+                .map(|s| (s.range.clone(), s.attr))
+                .collect(),
+            baseline_shift: self
+                .baseline_shift
+                .iter()
+                .filter(|s| s.attr != 0.0)
+                .map(|s| (s.range.clone(), s.attr))
+                .collect(),
+        }
+    }
+
+    /// Update the spans for the `changed` range of the text being replaced
+    /// with text of `new_len` bytes, which has no attributes.
+    pub(crate) fn edit(&mut self, changed: Range<usize>, new_len: usize) {
+        self.family.edit(changed.clone(), new_len);
+        self.size.edit(changed.clone(), new_len);
+        self.weight.edit(changed.clone(), new_len);
+        self.fg_color.edit(changed.clone(), new_len);
+        self.style.edit(changed.clone(), new_len);
+        self.underline.edit(changed.clone(), new_len);
+        self.font_descriptor.edit(changed.clone(), new_len);
+        self.link.edit(changed.clone(), new_len);
+        self.background.edit(changed.clone(), new_len);
+        self.strikethrough.edit(changed.clone(), new_len);
+        self.letter_spacing.edit(changed.clone(), new_len);
+        self.baseline_shift.edit(changed, new_len);
+    }
+
+    /// Returns the range and payload of the link at `offset`, if there is one.
+    pub fn link_at(&self, offset: usize) -> Option<(Range<usize>, &ArcStr)> {
+        self.link
+            .iter()
+            .find(|span| span.range.contains(&offset))
+            .map(|span| (span.range.clone(), &span.attr))
+    }
+
+    pub(crate) fn to_piet_attrs(
+        &self,
+        text: &str,
+        fonts: &Fonts,
+        env: &Env,
+    ) -> Vec<(Range<usize>, PietAttr)> {
         let mut items = Vec::new();
         for Span { range, attr } in self.font_descriptor.iter() {
-            let font = attr.resolve(env);
-            items.push((range.clone(), PietAttr::FontFamily(font.family)));
+            let font = font_descriptor::resolve(attr, env);
+            let families = fonts.family_runs(text, range.clone(), &font.family, &font.fallback);
+            for (range, family) in families {
+                items.push((range, PietAttr::FontFamily(family)));
+            }
             items.push((range.clone(), PietAttr::FontSize(font.size)));
             items.push((range.clone(), PietAttr::Weight(font.weight)));
             items.push((range.clone(), PietAttr::Style(font.style)));
         }
 
-        items.extend(
-            self.family
-                .iter()
-                .map(|s| (s.range.clone(), PietAttr::FontFamily(s.attr.clone()))),
-        );
+        items.extend(self.family.iter().map(|s| {
+            (
+                s.range.clone(),
+                PietAttr::FontFamily(fonts.resolve(&s.attr)),
+            )
+        }));
         items.extend(
             self.size
                 .iter()
@@ -152,6 +258,11 @@ impl AttributeSpans {
                 .iter()
                 .map(|s| (s.range.clone(), PietAttr::Underline(s.attr))),
         );
+        items.extend(
+            self.strikethrough
+                .iter()
+                .map(|s| (s.range.clone(), PietAttr::Strikethrough(s.attr))),
+        );
 
         // sort by ascending start order; this is a stable sort
         // so items that come from FontDescriptor will stay at the front
@@ -218,9 +329,6 @@ impl<T: Clone> SpanSet<T> {
     /// `new_len` is the length of the inserted text.
     //TODO: we could be smarter here about just extending the existing spans
     //as requred for insertions in the interior of a span.
-    //TODO: this isn't currently used; it should be used if we use spans with
-    //some editable type.
-    #[allow(dead_code)]
     fn edit(&mut self, changed: Range<usize>, new_len: usize) {
         let old_len = changed.len();
         let mut to_insert = None;
@@ -313,6 +421,31 @@ impl Attribute {
     pub fn font_descriptor(font: impl Into<KeyOrValue<FontDescriptor>>) -> Self {
         Attribute::Descriptor(font.into())
     }
+
+    /// Create a new background color attribute.
+    pub fn background_color(color: impl Into<KeyOrValue<Color>>) -> Self {
+        Attribute::BackgroundColor(color.into())
+    }
+
+    /// Create a new strikethrough attribute.
+    pub fn strikethrough(strikethrough: bool) -> Self {
+        Attribute::Strikethrough(strikethrough)
+    }
+
+    /// Create a new letter spacing attribute.
+    pub fn letter_spacing(spacing: f64) -> Self {
+        Attribute::LetterSpacing(spacing)
+    }
+
+    /// Create a new baseline shift attribute.
+    pub fn baseline_shift(shift: f64) -> Self {
+        Attribute::BaselineShift(shift)
+    }
+
+    /// Create a new link attribute.
+    pub fn link(payload: impl Into<ArcStr>) -> Self {
+        Attribute::Link(payload.into())
+    }
 }
 
 impl<T> Default for SpanSet<T> {
@@ -341,6 +474,17 @@ mod tests {
         assert_eq!(&spans.spans, &vec![Span::new(0..5, 3), Span::new(5..20, 4)]);
     }
 
+    #[test]
+    fn link_spans() {
+        let mut attrs = AttributeSpans::new();
+        attrs.add(2..6, Attribute::link("help"));
+        attrs.add(8..10, Attribute::link("error"));
+        assert_eq!(attrs.link_at(0), None);
+        assert_eq!(attrs.link_at(2), Some((2..6, &"help".into())));
+        assert_eq!(attrs.link_at(6), None);
+        assert_eq!(attrs.link_at(9), Some((8..10, &"error".into())));
+    }
+
     #[test]
     fn edit_spans() {
         let mut spans = SpanSet::<u32>::default();
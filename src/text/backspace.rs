@@ -14,7 +14,7 @@
 
 //! Calc start of a backspace delete interval
 
-use crate::text::{EditableText, EditableTextCursor, Selection};
+use crate::text::{EditableText, Selection};
 
 use xi_unicode::*;
 
@@ -41,12 +41,23 @@ fn backspace_offset(text: &impl EditableText, start: usize) -> usize {
 
     let mut delete_code_point_count = 0;
     let mut last_seen_vs_code_point_count = 0;
-    let mut cursor = text
-        .cursor(start)
-        .expect("Backspace must begin at a valid codepoint boundary.");
+    assert!(
+        text.is_char_boundary(start),
+        "Backspace must begin at a valid codepoint boundary."
+    );
+    // the codepoints are read through the text rather than a cursor, so
+    // that text such as a rope isn't built into one string
+    let mut pos = start;
 
-    while state != State::Finished && cursor.pos() > 0 {
-        let code_point = cursor.prev_codepoint().unwrap_or('0');
+    while state != State::Finished && pos > 0 {
+        let code_point = match text.prev_codepoint_offset(pos) {
+            Some(prev) => {
+                let code_point = text.slice(prev..pos).and_then(|c| c.chars().next());
+                pos = prev;
+                code_point.unwrap_or('0')
+            }
+            None => '0',
+        };
 
         match state {
             State::Start => {
@@ -185,11 +196,11 @@ fn backspace_offset(text: &impl EditableText, start: usize) -> usize {
         }
     }
 
-    cursor.set(start);
+    let mut pos = start;
     for _ in 0..delete_code_point_count {
-        let _ = cursor.prev_codepoint();
+        pos = text.prev_codepoint_offset(pos).unwrap_or(pos);
     }
-    cursor.pos()
+    pos
 }
 
 /// Calculate resulting offset for a backwards delete.
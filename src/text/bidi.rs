@@ -0,0 +1,279 @@
+//! Support for bidirectional text.
+//!
+//! Text in scripts such as Arabic and Hebrew is written from right to left,
+//! and may be mixed with left-to-right text such as numbers or words in
+//! other scripts. The order that characters are shown in then differs from
+//! their order in the text, which is resolved with the
+//! [Unicode Bidirectional Algorithm].
+//!
+//! [Unicode Bidirectional Algorithm]: https://www.unicode.org/reports/tr9/
+
+use std::ops::Range;
+
+use crate::piet::TextAlignment;
+use crate::text::Affinity;
+use unicode_bidi::{BidiClass, BidiInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The direction of a paragraph of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The paragraph is written from left to right.
+    LeftToRight,
+    /// The paragraph is written from right to left.
+    RightToLeft,
+}
+
+/// A grapheme cluster of a line, in the order that the line is shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VisualCell {
+    /// The range of the cluster in the text.
+    pub range: Range<usize>,
+    /// Whether the cluster is in a right-to-left run.
+    pub rtl: bool,
+}
+
+impl Direction {
+    /// The direction of the paragraph of `text` that contains `offset`.
+    ///
+    /// This is the direction of the first character in the paragraph that
+    /// has a strong direction, or left-to-right if there is none.
+    pub fn of_paragraph(text: &str, offset: usize) -> Self {
+        match unicode_bidi::get_base_direction(&text[paragraph(text, offset)]) {
+            unicode_bidi::Direction::Rtl => Direction::RightToLeft,
+            _ => Direction::LeftToRight,
+        }
+    }
+
+    /// The direction shared by every paragraph of `text`, or `None` if the
+    /// paragraphs differ.
+    pub(crate) fn of_text(text: &str) -> Option<Self> {
+        let first = Direction::of_paragraph(text, 0);
+        let mut starts = text.match_indices('\n').map(|(i, _)| i + 1);
+        starts
+            .all(|start| Direction::of_paragraph(text, start) == first)
+            .then_some(first)
+    }
+
+    /// Returns `true` if this is [`Direction::RightToLeft`].
+    ///
+    /// [`Direction::RightToLeft`]: Direction::RightToLeft
+    pub fn is_rtl(self) -> bool {
+        self == Direction::RightToLeft
+    }
+
+    /// Resolve an alignment that is relative to the start of paragraphs in
+    /// this direction, so that [`TextAlignment::Start`] is the right edge
+    /// of right-to-left paragraphs.
+    ///
+    /// [`TextAlignment::Start`]: crate::piet::TextAlignment::Start
+    pub fn resolve_alignment(self, alignment: TextAlignment) -> TextAlignment {
+        match (self, alignment) {
+            (Direction::RightToLeft, TextAlignment::Start) => TextAlignment::End,
+            (Direction::RightToLeft, TextAlignment::End) => TextAlignment::Start,
+            _ => alignment,
+        }
+    }
+}
+
+/// The range of the paragraph of `text` that contains `offset`, without
+/// its line break.
+pub(crate) fn paragraph(text: &str, offset: usize) -> Range<usize> {
+    let offset = offset.min(text.len());
+    let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let end = text[offset..].find('\n').map_or(text.len(), |i| offset + i);
+    start..end
+}
+
+/// Returns `true` if any of `text` may be shown from right to left.
+pub(crate) fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            unicode_bidi::bidi_class(c),
+            BidiClass::R
+                | BidiClass::AL
+                | BidiClass::AN
+                | BidiClass::RLE
+                | BidiClass::RLO
+                | BidiClass::RLI
+        )
+    })
+}
+
+/// The grapheme clusters of the `line` range of `text`, from left to right.
+///
+/// The line should not include its line break. Only the paragraph that
+/// contains the line is analyzed.
+pub(crate) fn visual_cells(text: &str, line: Range<usize>) -> Vec<VisualCell> {
+    if line.is_empty() {
+        return Vec::new();
+    }
+    let range = paragraph(text, line.start);
+    let paragraph_text = &text[range.clone()];
+    if !has_rtl(paragraph_text) {
+        return graphemes(text, line)
+            .map(|range| VisualCell { range, rtl: false })
+            .collect();
+    }
+    let bidi = BidiInfo::new(paragraph_text, None);
+    let paragraph = match bidi.paragraphs.first() {
+        Some(paragraph) => paragraph,
+        None => return Vec::new(),
+    };
+    let line = line.start - range.start..line.end.min(range.end) - range.start;
+    let (levels, runs) = bidi.visual_runs(paragraph, line);
+    let mut cells = Vec::new();
+    for run in runs {
+        let rtl = levels[run.start].is_rtl();
+        let run = range.start + run.start..range.start + run.end;
+        let start = cells.len();
+        cells.extend(graphemes(text, run).map(|range| VisualCell { range, rtl }));
+        if rtl {
+            cells[start..].reverse();
+        }
+    }
+    cells
+}
+
+fn graphemes(text: &str, range: Range<usize>) -> impl Iterator<Item = Range<usize>> + '_ {
+    text[range.clone()]
+        .grapheme_indices(true)
+        .map(move |(i, g)| range.start + i..range.start + i + g.len())
+}
+
+/// The index of the boundary between `cells` where a caret at `offset` is
+/// shown, counting from the left edge of the line.
+///
+/// An offset between runs in different directions is at two boundaries;
+/// the `affinity` picks the one next to the text before or after it.
+pub(crate) fn caret_slot(cells: &[VisualCell], offset: usize, affinity: Affinity) -> usize {
+    // the leading edge of the cluster after the offset, which is its right
+    // edge if it is right-to-left
+    let after = cells
+        .iter()
+        .position(|cell| cell.range.start == offset)
+        .map(|i| if cells[i].rtl { i + 1 } else { i });
+    // the trailing edge of the cluster before the offset
+    let before = cells
+        .iter()
+        .position(|cell| cell.range.end == offset)
+        .map(|i| if cells[i].rtl { i } else { i + 1 });
+    let slot = match affinity {
+        Affinity::Downstream => after.or(before),
+        Affinity::Upstream => before.or(after),
+    };
+    slot.unwrap_or(0)
+}
+
+/// Move a caret one cluster to the left or right on screen, returning its
+/// new offset and affinity, or `None` at the edge of the line.
+pub(crate) fn move_visually(
+    cells: &[VisualCell],
+    offset: usize,
+    affinity: Affinity,
+    right: bool,
+) -> Option<(usize, Affinity)> {
+    let slot = caret_slot(cells, offset, affinity);
+    let cell = if right {
+        cells.get(slot)?
+    } else {
+        cells.get(slot.checked_sub(1)?)?
+    };
+    // crossing a cluster in its own direction ends up after it
+    Some(if right != cell.rtl {
+        (cell.range.end, Affinity::Upstream)
+    } else {
+        (cell.range.start, Affinity::Downstream)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "ab אב cd", where the Hebrew letters are two bytes each
+    const MIXED: &str = "ab \u{5d0}\u{5d1} cd";
+
+    #[test]
+    fn directions() {
+        assert_eq!(Direction::of_paragraph("hello", 0), Direction::LeftToRight);
+        assert_eq!(
+            Direction::of_paragraph("12 \u{5e9}\u{5dc}\u{5d5}\u{5dd}", 0),
+            Direction::RightToLeft
+        );
+        assert_eq!(Direction::of_paragraph("123", 0), Direction::LeftToRight);
+        let text = "abc\n\u{627}\u{644}";
+        assert_eq!(Direction::of_paragraph(text, 2), Direction::LeftToRight);
+        assert_eq!(Direction::of_paragraph(text, 6), Direction::RightToLeft);
+        assert_eq!(Direction::of_text(text), None);
+        assert_eq!(
+            Direction::of_text("\u{627}\n\u{644}"),
+            Some(Direction::RightToLeft)
+        );
+        assert_eq!(
+            Direction::RightToLeft.resolve_alignment(TextAlignment::Start),
+            TextAlignment::End
+        );
+        assert_eq!(
+            Direction::LeftToRight.resolve_alignment(TextAlignment::Start),
+            TextAlignment::Start
+        );
+    }
+
+    #[test]
+    fn visual_order() {
+        let cells = visual_cells(MIXED, 0..MIXED.len());
+        let ranges: Vec<_> = cells.iter().map(|cell| cell.range.clone()).collect();
+        assert_eq!(
+            ranges,
+            vec![0..1, 1..2, 2..3, 5..7, 3..5, 7..8, 8..9, 9..10]
+        );
+        assert!(cells[3].rtl && cells[4].rtl && !cells[5].rtl);
+
+        let cells = visual_cells("abc", 0..3);
+        assert!(cells.iter().all(|cell| !cell.rtl));
+        assert!(visual_cells("abc", 3..3).is_empty());
+
+        // only the line's own paragraph is analyzed
+        let text = format!("\u{5d0}\n{}", MIXED);
+        let cells = visual_cells(&text, 3..text.len());
+        let ranges: Vec<_> = cells.iter().map(|cell| cell.range.clone()).collect();
+        assert_eq!(
+            ranges,
+            vec![3..4, 4..5, 5..6, 8..10, 6..8, 10..11, 11..12, 12..13]
+        );
+        let cells = visual_cells("ab\ncd", 3..5);
+        assert!(cells.iter().all(|cell| !cell.rtl));
+    }
+
+    #[test]
+    fn visual_movement() {
+        let cells = visual_cells(MIXED, 0..MIXED.len());
+        let mut caret = (0, Affinity::Downstream);
+        let mut offsets = Vec::new();
+        while let Some(moved) = move_visually(&cells, caret.0, caret.1, true) {
+            caret = moved;
+            offsets.push(caret.0);
+        }
+        assert_eq!(offsets, vec![1, 2, 3, 5, 3, 8, 9, 10]);
+
+        offsets.clear();
+        while let Some(moved) = move_visually(&cells, caret.0, caret.1, false) {
+            caret = moved;
+            offsets.push(caret.0);
+        }
+        assert_eq!(offsets, vec![9, 8, 7, 5, 7, 2, 1, 0]);
+    }
+
+    #[test]
+    fn split_carets() {
+        let cells = visual_cells(MIXED, 0..MIXED.len());
+        // between the space and the first Hebrew letter, which is shown
+        // on the right of the second one
+        assert_eq!(caret_slot(&cells, 3, Affinity::Upstream), 3);
+        assert_eq!(caret_slot(&cells, 3, Affinity::Downstream), 5);
+        assert_eq!(caret_slot(&cells, 5, Affinity::Downstream), 4);
+        assert_eq!(caret_slot(&cells, 5, Affinity::Upstream), 4);
+        assert_eq!(caret_slot(&cells, 10, Affinity::Downstream), 8);
+    }
+}
@@ -0,0 +1,92 @@
+//! Suggesting completions for the word being typed.
+
+use std::ops::Range;
+
+/// A completion offered by a [`CompletionSource`].
+///
+/// [`CompletionSource`]: trait.CompletionSource.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// The text shown in the list of suggestions.
+    pub label: String,
+    /// The text that replaces the current word when the suggestion is
+    /// accepted.
+    pub replacement: String,
+    /// Additional text shown next to the label, such as a description.
+    pub detail: Option<String>,
+}
+
+/// A source of completions for a text box.
+///
+/// This is implemented for closures taking the text and the cursor
+/// position.
+pub trait CompletionSource {
+    /// Returns the suggestions for `text` with the cursor at `cursor`, with
+    /// the most relevant first.
+    fn suggestions(&self, text: &str, cursor: usize) -> Vec<Suggestion>;
+
+    /// Returns the range of `text` that an accepted suggestion replaces.
+    ///
+    /// By default this is the word around `cursor`, where a word is a run
+    /// of alphanumeric characters and underscores.
+    fn word_range(&self, text: &str, cursor: usize) -> Range<usize> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let start = text[..cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word_char(*c))
+            .last()
+            .map_or(cursor, |(i, _)| i);
+        let end = text[cursor..]
+            .char_indices()
+            .find(|(_, c)| !is_word_char(*c))
+            .map_or(text.len(), |(i, _)| cursor + i);
+        start..end
+    }
+}
+
+impl Suggestion {
+    /// Create a new `Suggestion` that shows and inserts `text`.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        Suggestion {
+            label: text.clone(),
+            replacement: text,
+            detail: None,
+        }
+    }
+
+    /// Builder-style method to insert `replacement` rather than the label.
+    pub fn with_replacement(mut self, replacement: impl Into<String>) -> Self {
+        self.replacement = replacement.into();
+        self
+    }
+
+    /// Builder-style method to show `detail` next to the label.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl<F: Fn(&str, usize) -> Vec<Suggestion>> CompletionSource for F {
+    fn suggestions(&self, text: &str, cursor: usize) -> Vec<Suggestion> {
+        (self)(text, cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_range() {
+        let source = |_: &str, _: usize| Vec::new();
+        let text = "git check_out main";
+        assert_eq!(source.word_range(text, 9), 4..13);
+        assert_eq!(source.word_range(text, 4), 4..13);
+        assert_eq!(source.word_range(text, 13), 4..13);
+        assert_eq!(source.word_range(text, 18), 14..18);
+        assert_eq!(source.word_range("a  b", 2), 2..2);
+    }
+}
@@ -0,0 +1,72 @@
+//! Composition of text by an input method.
+
+use std::ops::Range;
+
+use druid::Selector;
+
+/// Sent to the focused [`TextBox`] or [`TextArea`] by a platform input
+/// handler, to compose text in it.
+///
+/// [`TextBox`]: ../widgets/textbox/struct.TextBox.html
+/// [`TextArea`]: ../widgets/textarea/struct.TextArea.html
+pub const COMPOSE: Selector<CompositionEvent> = Selector::new("coat.text.compose");
+
+/// A step of composing text, sent with [`COMPOSE`].
+///
+/// [`COMPOSE`]: constant.COMPOSE.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompositionEvent {
+    /// Replace the preedit text, as with [`Composition::set_preedit`].
+    ///
+    /// [`Composition::set_preedit`]: trait.Composition.html#tymethod.set_preedit
+    Preedit(String),
+    /// Keep the preedit text as entered text.
+    Commit,
+    /// Remove the preedit text.
+    Cancel,
+}
+
+/// An interface through which an input method composes text.
+///
+/// Input methods for languages such as Chinese or Japanese, as well as
+/// dead keys, build up text in several steps before it is entered. While
+/// text is being composed it is shown inline as *preedit* text, which is
+/// replaced on every step until it is either committed or cancelled.
+///
+/// This is implemented by [`Editor`], so that a platform input handler, or
+/// a test double, can drive composition in any widget that owns one.
+///
+/// The type parameter `T` is the text data being edited.
+///
+/// [`Editor`]: struct.Editor.html
+pub trait Composition<T> {
+    /// Set the preedit text, replacing the current preedit text if there is
+    /// any, and otherwise replacing the selection.
+    fn set_preedit(&mut self, text: &str, data: &mut T);
+
+    /// End composition, keeping the preedit text as entered text.
+    fn commit_preedit(&mut self, data: &mut T);
+
+    /// End composition, removing the preedit text and restoring the text
+    /// and selection from before composition started.
+    fn cancel_preedit(&mut self, data: &mut T);
+
+    /// The range of the preedit text, or `None` if nothing is being composed.
+    fn preedit_range(&self) -> Option<Range<usize>>;
+
+    /// Returns `true` if text is being composed.
+    fn is_composing(&self) -> bool {
+        self.preedit_range().is_some()
+    }
+
+    /// Apply a step of composition from a [`CompositionEvent`].
+    ///
+    /// [`CompositionEvent`]: enum.CompositionEvent.html
+    fn compose(&mut self, event: &CompositionEvent, data: &mut T) {
+        match event {
+            CompositionEvent::Preedit(text) => self.set_preedit(text, data),
+            CompositionEvent::Commit => self.commit_preedit(data),
+            CompositionEvent::Cancel => self.cancel_preedit(data),
+        }
+    }
+}
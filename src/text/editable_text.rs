@@ -20,6 +20,8 @@ use std::sync::Arc;
 
 use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
 
+use super::SearchQuery;
+
 /// An EditableText trait.
 pub trait EditableText: Sized {
     // TODO: would be nice to have something like
@@ -68,8 +70,40 @@ pub trait EditableText: Sized {
     /// Returns `true` if this text has 0 length.
     fn is_empty(&self) -> bool;
 
+    /// Returns `true` if `offset` is a codepoint boundary.
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        self.cursor(offset).is_some()
+    }
+
     /// Construct an instance of this type from a `&str`.
     fn from_str(s: &str) -> Self;
+
+    /// Returns the range of the first match of `query` that starts at or
+    /// after `from`.
+    fn find_next(&self, query: &SearchQuery, from: usize) -> Option<Range<usize>> {
+        let text = self.slice(0..self.len())?;
+        let found = query.find_iter(&text).find(|found| found.start >= from);
+        found
+    }
+
+    /// Returns the range of the last match of `query` that starts before
+    /// `from`.
+    fn find_prev(&self, query: &SearchQuery, from: usize) -> Option<Range<usize>> {
+        let text = self.slice(0..self.len())?;
+        let found = query
+            .find_iter(&text)
+            .take_while(|found| found.start < from)
+            .last();
+        found
+    }
+
+    /// Returns the ranges of all matches of `query`, in order.
+    fn find_all(&self, query: &SearchQuery) -> Vec<Range<usize>> {
+        match self.slice(0..self.len()) {
+            Some(text) => query.find_iter(&text).collect(),
+            None => Vec::new(),
+        }
+    }
 }
 
 impl EditableText for String {
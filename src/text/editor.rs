@@ -14,14 +14,64 @@
 
 //! A component for building text editing widgets
 
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::filter::Filtered;
+use super::format::ValidationError;
+use super::highlight::Highlighting;
+use super::history::{Change, History};
+use super::kill_ring::KillRing;
+use super::spell::SpellCheck;
 use super::{
-    movement, offset_for_delete_backwards, EditAction, EditableText, MouseAction, Movement,
-    Selection, TextLayout, TextStorage,
+    movement, offset_for_delete_backwards, Composition, EditAction, EditableText, Fonts,
+    Highlighter, InputFilter, LayoutMetrics, MouseAction, Movement, Selection, SpellChecker,
+    TextLayout, TextStorage,
 };
 use crate::context::PaintCtx;
-use crate::kurbo::Line;
-use crate::piet::PietText;
-use druid::{Application, Env, MouseEvent, Point, Rect, UpdateCtx};
+use crate::kurbo::{BezPath, Line};
+use crate::piet::{PietText, RenderContext};
+use druid::{theme, Application, Color, Env, MouseEvent, Point, Rect, UpdateCtx};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The character drawn in place of each grapheme cluster in masked mode.
+const MASK_CHAR: char = '\u{2022}';
+/// The color of the underline of misspelled words.
+const MISSPELLED_COLOR: Color = Color::rgb8(0xe0, 0x30, 0x30);
+
+/// The unit of text selected by clicking and dragging.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Granularity {
+    Grapheme,
+    Word,
+    Paragraph,
+}
+
+/// Text that is being composed by an input method.
+#[derive(Debug, Clone)]
+struct Preedit {
+    /// The range of the preedit text.
+    range: Range<usize>,
+    /// The text that the preedit text replaced.
+    replaced: String,
+    /// The selection before composition started.
+    selection: Selection,
+}
+
+/// How the previous edit can be continued by the next one.
+#[derive(Debug, Clone, PartialEq)]
+enum Continuation {
+    None,
+    /// Consecutive kills are joined into a single kill ring entry.
+    Kill,
+    /// A yank can be replaced by an older kill.
+    Yank {
+        /// The range of the yanked text.
+        range: Range<usize>,
+        /// The position of the yanked text in the kill ring.
+        index: usize,
+    },
+}
 
 /// A component for widgets that offer text editing.
 ///
@@ -34,7 +84,32 @@ use druid::{Application, Env, MouseEvent, Point, Rect, UpdateCtx};
 #[derive(Debug, Clone)]
 pub struct Editor<T> {
     layout: TextLayout<T>,
+    /// The layout that is drawn instead of `layout` when the text is masked.
+    masked_layout: Option<TextLayout<String>>,
+    /// The primary selection, which is the one that is scrolled to and that
+    /// edits such as copying and killing apply to.
     selection: Selection,
+    /// The other selections, in no particular order.
+    secondary: Vec<Selection>,
+    history: History,
+    kill_ring: KillRing,
+    continuation: Continuation,
+    /// The unit by which the selection is extended while dragging.
+    drag_granularity: Granularity,
+    /// The word or paragraph that was selected by the click starting a drag.
+    drag_anchor: Range<usize>,
+    preedit: Option<Preedit>,
+    highlighting: Option<Highlighting>,
+    spell_check: Option<SpellCheck>,
+    /// The data as the last edit left it, which the highlighting and spell
+    /// check already follow.
+    edited: Option<T>,
+    /// The ranges that are highlighted as search results.
+    search_matches: Vec<Range<usize>>,
+    input_filter: InputFilter,
+    /// The error from the input filter's formatter for the last edit, if it
+    /// was rejected.
+    rejection: Option<ValidationError>,
     multiline: bool,
     fixed_width: f64,
 }
@@ -44,7 +119,21 @@ impl<T> Editor<T> {
     pub fn new() -> Self {
         Editor {
             layout: TextLayout::new(),
+            masked_layout: None,
             selection: Selection::caret(0),
+            secondary: Vec::new(),
+            history: History::new(),
+            kill_ring: KillRing::new(),
+            continuation: Continuation::None,
+            drag_granularity: Granularity::Grapheme,
+            drag_anchor: 0..0,
+            preedit: None,
+            highlighting: None,
+            spell_check: None,
+            edited: None,
+            search_matches: Vec::new(),
+            input_filter: InputFilter::new(),
+            rejection: None,
             multiline: false,
             fixed_width: f64::INFINITY,
         }
@@ -55,7 +144,21 @@ impl<T> Editor<T> {
     pub fn from_text(text: impl Into<T>) -> Self {
         Editor {
             layout: TextLayout::from_text(text),
+            masked_layout: None,
             selection: Selection::caret(0),
+            secondary: Vec::new(),
+            history: History::new(),
+            kill_ring: KillRing::new(),
+            continuation: Continuation::None,
+            drag_granularity: Granularity::Grapheme,
+            drag_anchor: 0..0,
+            preedit: None,
+            highlighting: None,
+            spell_check: None,
+            edited: None,
+            search_matches: Vec::new(),
+            input_filter: InputFilter::new(),
+            rejection: None,
             multiline: false,
             fixed_width: f64::INFINITY,
         }
@@ -99,6 +202,79 @@ impl<T> Editor<T> {
     pub fn layout_mut(&mut self) -> &mut TextLayout<T> {
         &mut self.layout
     }
+
+    /// Set the maximum number of steps that can be undone.
+    ///
+    /// If there are more steps in the history, the oldest ones are dropped.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history.set_limit(limit);
+    }
+
+    /// Forget all undo and redo steps.
+    ///
+    /// This should be called when the text is replaced from outside the
+    /// editor, as the recorded steps no longer apply to it.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Returns `true` if there is an edit that can be undone.
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    /// Returns `true` if there is an undone edit that can be redone.
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Forget the text being composed by an input method, keeping it in
+    /// the text.
+    ///
+    /// Like [`clear_history`], this should be called when the text is
+    /// replaced from outside the editor.
+    ///
+    /// [`clear_history`]: #method.clear_history
+    pub fn discard_preedit(&mut self) {
+        self.preedit = None;
+    }
+
+    /// Set the [`InputFilter`] that restricts the text that can be typed or
+    /// pasted.
+    ///
+    /// The filter only applies to [`EditAction::Insert`] and
+    /// [`EditAction::Paste`]; text set with [`set_text`] is not filtered.
+    ///
+    /// [`InputFilter`]: struct.InputFilter.html
+    /// [`EditAction::Insert`]: enum.EditAction.html#variant.Insert
+    /// [`EditAction::Paste`]: enum.EditAction.html#variant.Paste
+    /// [`set_text`]: #method.set_text
+    pub fn set_input_filter(&mut self, filter: InputFilter) {
+        self.input_filter = filter;
+    }
+
+    /// Returns the [`InputFilter`] that restricts the text that can be typed
+    /// or pasted.
+    ///
+    /// [`InputFilter`]: struct.InputFilter.html
+    pub fn input_filter(&self) -> &InputFilter {
+        &self.input_filter
+    }
+
+    /// Returns the error from the formatter of the [`InputFilter`] if it
+    /// rejected the last edit.
+    ///
+    /// The error is cleared by the next edit.
+    ///
+    /// [`InputFilter`]: struct.InputFilter.html
+    pub fn rejection(&self) -> Option<&ValidationError> {
+        self.rejection.as_ref()
+    }
+
+    /// Returns whether the text is masked.
+    pub fn is_masked(&self) -> bool {
+        self.masked_layout.is_some()
+    }
 }
 
 impl<T: TextStorage + EditableText> Editor<T> {
@@ -109,57 +285,276 @@ impl<T: TextStorage + EditableText> Editor<T> {
     /// [`WidgetAdded`]: ../enum.LifeCycle.html#variant.WidgetAdded
     pub fn set_text(&mut self, text: T) {
         self.selection = self.selection.constrained(&text);
+        for selection in &mut self.secondary {
+            *selection = selection.constrained(&text);
+        }
         self.layout.set_text(text);
+        self.update_masked_text();
+        self.update_decorations();
+    }
+
+    /// Set the [`Highlighter`] used to style the text, or `None` to draw
+    /// the text without highlighting.
+    ///
+    /// After each edit, only the lines that were changed are highlighted
+    /// again.
+    ///
+    /// [`Highlighter`]: trait.Highlighter.html
+    pub fn set_highlighter(&mut self, highlighter: Option<Arc<dyn Highlighter>>) {
+        let text = self.layout.text().map(|t| t.as_str()).unwrap_or_default();
+        self.highlighting = highlighter.map(|highlighter| Highlighting::new(highlighter, text));
+        if self.highlighting.is_none() {
+            self.layout.set_attributes(None);
+        }
+        self.apply_highlighting();
+    }
+
+    /// Returns the [`Highlighter`] used to style the text, if any.
+    ///
+    /// [`Highlighter`]: trait.Highlighter.html
+    pub fn highlighter(&self) -> Option<&Arc<dyn Highlighter>> {
+        self.highlighting
+            .as_ref()
+            .map(|highlighting| highlighting.highlighter())
+    }
+
+    /// Set the [`SpellChecker`] used to underline misspelled words, or
+    /// `None` to stop checking the spelling.
+    ///
+    /// After each edit, only the paragraphs that were changed are checked
+    /// again. Masked text is never checked.
+    ///
+    /// [`SpellChecker`]: trait.SpellChecker.html
+    pub fn set_spell_checker(&mut self, checker: Option<Arc<dyn SpellChecker>>) {
+        let text = self.layout.text().map(|t| t.as_str()).unwrap_or_default();
+        self.spell_check = checker.map(|checker| SpellCheck::new(checker, text));
+    }
+
+    /// Returns the [`SpellChecker`] used to underline misspelled words, if
+    /// any.
+    ///
+    /// [`SpellChecker`]: trait.SpellChecker.html
+    pub fn spell_checker(&self) -> Option<&Arc<dyn SpellChecker>> {
+        self.spell_check.as_ref().map(|check| check.checker())
+    }
+
+    /// Returns the ranges of the misspelled words.
+    pub fn misspelled_words(&self) -> Vec<Range<usize>> {
+        self.misspelled_words_in(0..usize::MAX)
+    }
+
+    /// Returns the ranges of the misspelled words in the paragraphs that
+    /// have any of `range`.
+    pub fn misspelled_words_in(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        match &self.spell_check {
+            Some(check) if !self.is_masked() => check.misspelled(range),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the range of the misspelled word at `offset` and the
+    /// suggested replacements for it, if there is a misspelled word there.
+    pub fn spelling_suggestions(&self, offset: usize) -> Option<(Range<usize>, Vec<String>)> {
+        let check = self.spell_check.as_ref()?;
+        let range = self
+            .misspelled_words_in(offset..offset)
+            .into_iter()
+            .find(|range| range.start <= offset && offset <= range.end)?;
+        let word = self.layout.text()?.slice(range.clone())?;
+        let suggestions = check.checker().suggestions(&word);
+        Some((range, suggestions))
+    }
+
+    /// Set the ranges to highlight as search results, such as those
+    /// returned by [`EditableText::find_all`].
+    ///
+    /// Ranges that don't fit the text are not drawn. Masked text never shows
+    /// its search results.
+    ///
+    /// [`EditableText::find_all`]: trait.EditableText.html#method.find_all
+    pub fn set_search_matches(&mut self, matches: Vec<Range<usize>>) {
+        self.search_matches = matches;
+    }
+
+    /// Returns the ranges that are highlighted as search results.
+    pub fn search_matches(&self) -> &[Range<usize>] {
+        &self.search_matches
+    }
+
+    /// Set whether the text should be masked, as for password entry.
+    ///
+    /// A masked editor draws a bullet for each grapheme cluster in place of
+    /// the actual text. Selection, cursor placement and hit-testing operate
+    /// on the bullets, while edits are still applied to the real text.
+    /// Copying and cutting are disabled while the text is masked.
+    pub fn set_masked(&mut self, masked: bool) {
+        if masked != self.is_masked() {
+            self.masked_layout = if masked {
+                Some(TextLayout::new())
+            } else {
+                None
+            };
+            self.update_masked_text();
+        }
+    }
+
+    /// Return the [`LayoutMetrics`] of the text as it is drawn.
+    ///
+    /// If the text is masked, these are the metrics of the masked text.
+    ///
+    /// [`LayoutMetrics`]: struct.LayoutMetrics.html
+    pub fn layout_metrics(&self) -> LayoutMetrics {
+        match &self.masked_layout {
+            Some(masked) => masked.layout_metrics(),
+            None => self.layout.layout_metrics(),
+        }
     }
 
-    /// Return the current selection.
+    /// Return the current primary selection.
     pub fn selection(&self) -> &Selection {
         &self.selection
     }
 
-    /// Set the current selection.
+    /// Return the selections other than the primary selection.
+    pub fn secondary_selections(&self) -> &[Selection] {
+        &self.secondary
+    }
+
+    /// Set the current selection, removing any other selections.
     ///
     /// The selection will be constrained to the current text.
     pub fn set_selection(&mut self, selection: Selection) {
-        let selection = self
-            .layout
-            .text()
-            .map(|t| selection.constrained(t))
-            .unwrap_or_else(|| Selection::caret(0));
+        self.selection = self.constrained(selection);
+        self.secondary.clear();
+        self.continuation = Continuation::None;
+    }
+
+    /// Add a selection, which becomes the primary selection.
+    ///
+    /// The selection will be constrained to the current text, and merged
+    /// with any selection it overlaps.
+    pub fn add_selection(&mut self, selection: Selection) {
+        let selection = self.constrained(selection);
+        self.secondary.push(self.selection);
         self.selection = selection;
+        self.merge_selections();
     }
 
-    /// Returns the `Rect`s representing the  current selection.
+    /// Returns the `Rect`s representing the current selections.
     pub fn selection_rects(&self) -> Vec<Rect> {
-        self.layout.rects_for_range(self.selection.range())
+        let selections = std::iter::once(&self.selection).chain(&self.secondary);
+        match &self.masked_layout {
+            Some(masked) => selections
+                .flat_map(|selection| {
+                    let range = selection.range();
+                    let start = self.masked_offset(range.start);
+                    let end = self.masked_offset(range.end);
+                    masked.rects_for_range(start..end)
+                })
+                .collect(),
+            None => selections
+                .flat_map(|selection| self.layout.rects_for_range(selection.range()))
+                .collect(),
+        }
     }
 
     /// Returns the `Line` to draw for the current cursor position.
     pub fn cursor_line(&self) -> Line {
-        self.layout
-            .cursor_line_for_text_position(self.selection.end)
+        match &self.masked_layout {
+            Some(masked) => {
+                masked.cursor_line_for_text_position(self.masked_offset(self.selection.end))
+            }
+            None => {
+                self.layout
+                    .cursor_lines_for_text_position(self.selection.end, self.selection.affinity)
+                    .0
+            }
+        }
+    }
+
+    /// Returns the `Line` to draw for the other half of a split cursor.
+    ///
+    /// Where the cursor is between runs of text in different directions, it
+    /// is at two places on screen: [`cursor_line`] is next to the text that
+    /// the cursor is attached to, and this is next to the other text.
+    ///
+    /// [`cursor_line`]: #method.cursor_line
+    pub fn split_cursor_line(&self) -> Option<Line> {
+        match &self.masked_layout {
+            Some(_) => None,
+            None => {
+                self.layout
+                    .cursor_lines_for_text_position(self.selection.end, self.selection.affinity)
+                    .1
+            }
+        }
+    }
+
+    /// Returns the `Line`s to draw for the cursors of the secondary
+    /// selections.
+    pub fn secondary_cursor_lines(&self) -> Vec<Line> {
+        self.secondary
+            .iter()
+            .map(|selection| match &self.masked_layout {
+                Some(masked) => {
+                    masked.cursor_line_for_text_position(self.masked_offset(selection.end))
+                }
+                None => {
+                    self.layout
+                        .cursor_lines_for_text_position(selection.end, selection.affinity)
+                        .0
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the `Rect` to draw for a block cursor, which covers the
+    /// grapheme after the cursor position.
+    ///
+    /// At the end of a line, the block is half as wide as it is high.
+    pub fn cursor_block(&self) -> Rect {
+        let line = self.cursor_line();
+        let next = self
+            .layout
+            .text()
+            .and_then(|text| text.next_grapheme_offset(self.selection.end));
+        let next_line = next.map(|next| match &self.masked_layout {
+            Some(masked) => masked.cursor_line_for_text_position(self.masked_offset(next)),
+            None => self.layout.cursor_line_for_text_position(next),
+        });
+        let width = match next_line {
+            Some(next) if next.p0.y == line.p0.y && next.p0.x > line.p0.x => next.p0.x - line.p0.x,
+            _ => (line.p1.y - line.p0.y) / 2.0,
+        };
+        Rect::new(line.p0.x, line.p0.y, line.p0.x + width, line.p1.y)
     }
 
     /// Handle a mouse click
     pub fn click(&mut self, mouse: &MouseEvent, data: &mut T) {
+        self.continuation = Continuation::None;
         self.do_edit(EditAction::Click(self.mouse_action_for_event(mouse)), data);
     }
 
     /// Handle a mouse drag
     pub fn drag(&mut self, mouse: &MouseEvent, data: &mut T) {
+        self.continuation = Continuation::None;
         self.do_edit(EditAction::Drag(self.mouse_action_for_event(mouse)), data);
     }
 
     /// Handle a copy command
+    ///
+    /// This does nothing if the text is masked.
     pub fn copy(&self, data: &mut T) {
-        if !self.data_is_stale(data) {
+        if !self.data_is_stale(data) && !self.is_masked() {
             self.set_clipboard()
         }
     }
 
     /// Handle a cut command
+    ///
+    /// This does nothing if the text is masked.
     pub fn cut(&mut self, data: &mut T) {
-        if !self.data_is_stale(data) {
+        if !self.data_is_stale(data) && !self.is_masked() {
             self.set_clipboard();
             self.delete_backward(data);
         }
@@ -173,14 +568,20 @@ impl<T: TextStorage + EditableText> Editor<T> {
     /// Set the selection to the entire buffer.
     pub fn select_all(&mut self, data: &T) {
         self.selection = Selection::new(0, data.len());
+        self.secondary.clear();
+        self.continuation = Continuation::None;
     }
 
     fn mouse_action_for_event(&self, event: &MouseEvent) -> MouseAction {
-        let pos = self.layout.text_position_for_point(event.pos);
+        let pos = match &self.masked_layout {
+            Some(masked) => self.unmasked_offset(masked.text_position_for_point(event.pos)),
+            None => self.layout.text_position_for_point(event.pos),
+        };
         MouseAction {
             row: 0,
             column: pos,
             mods: event.mods,
+            count: event.count,
         }
     }
 
@@ -190,62 +591,442 @@ impl<T: TextStorage + EditableText> Editor<T> {
     /// [`update`] call.
     ///
     /// [`update`]: ../trait.Widget.html#tymethod.update
-    pub fn update(&mut self, ctx: &mut UpdateCtx, new_data: &T, env: &Env) {
+    pub fn update(&mut self, ctx: &mut UpdateCtx, new_data: &T, _env: &Env) {
         if self.data_is_stale(new_data) {
             self.layout.set_text(new_data.clone());
             self.selection = self.selection.constrained(new_data);
+            self.secondary.clear();
+            self.update_masked_text();
+            self.update_decorations();
+            self.history.clear();
+            self.preedit = None;
             ctx.request_layout();
         } else if self.layout.needs_rebuild_after_update(ctx) {
             ctx.request_layout();
         }
-        self.rebuild_if_needed(ctx.text(), env);
     }
 
     /// Must be called in WidgetAdded
-    pub fn rebuild_if_needed(&mut self, factory: &mut PietText, env: &Env) {
-        self.layout.rebuild_if_needed(factory, env);
+    pub fn rebuild_if_needed(&mut self, factory: &mut PietText, fonts: &Fonts, env: &Env) {
+        self.layout.rebuild_if_needed(factory, fonts, env);
+        if let Some(masked) = &mut self.masked_layout {
+            masked.rebuild_if_needed(factory, fonts, env);
+        }
     }
 
     /// Perform an [`EditAction`](enum.EditAction.html).
+    ///
+    /// Insertions, deletions and movements apply to every selection; other
+    /// actions apply to the primary selection only.
     pub fn do_edit(&mut self, edit: EditAction, data: &mut T) {
         if self.data_is_stale(data) {
             log::warn!("editor data changed externally, skipping event {:?}", &edit);
             return;
         }
+        self.rejection = None;
+        // any other edit ends composition
+        self.commit_preedit(data);
+        let continuation = std::mem::replace(&mut self.continuation, Continuation::None);
+        // only consecutive insertions are grouped into a single undo step
+        if !matches!(edit, EditAction::Insert(_)) {
+            self.history.seal();
+        }
+        let each_selection = matches!(
+            edit,
+            EditAction::Insert(_)
+                | EditAction::Paste(_)
+                | EditAction::Backspace
+                | EditAction::Delete
+                | EditAction::JumpDelete(_)
+                | EditAction::JumpBackspace(_)
+                | EditAction::Move(_)
+                | EditAction::ModifySelection(_)
+                | EditAction::CollapseSelection { .. }
+        );
+        if each_selection && !self.secondary.is_empty() {
+            self.for_each_selection(data, |editor, data| {
+                editor.apply_edit(edit.clone(), data, Continuation::None)
+            });
+        } else {
+            self.apply_edit(edit, data, continuation);
+        }
+        self.merge_selections();
+    }
+
+    /// Apply an edit to the primary selection.
+    fn apply_edit(&mut self, edit: EditAction, data: &mut T, continuation: Continuation) {
         match edit {
-            EditAction::Insert(chars) | EditAction::Paste(chars) => self.insert(data, &chars),
+            EditAction::Insert(chars) => {
+                self.insert_filtered(data, &chars, false);
+            }
+            EditAction::Paste(chars) => {
+                self.insert_filtered(data, &chars, true);
+                self.history.seal();
+            }
             EditAction::Backspace => self.delete_backward(data),
             EditAction::Delete => self.delete_forward(data),
             EditAction::JumpDelete(mvmt) | EditAction::JumpBackspace(mvmt) => {
+                let mvmt = self.visible_movement(mvmt);
                 let to_delete = if self.selection.is_caret() {
                     movement(mvmt, self.selection, &self.layout, true)
                 } else {
                     self.selection
                 };
-                data.edit(to_delete.range(), "");
-                self.selection = Selection::caret(to_delete.min());
+                let selection = Selection::caret(to_delete.min());
+                self.replace(data, to_delete.range(), "", selection, false);
             }
             EditAction::Move(mvmt) => {
+                let mvmt = self.visible_movement(mvmt);
                 self.selection = movement(mvmt, self.selection, &self.layout, false)
             }
             EditAction::ModifySelection(mvmt) => {
+                let mvmt = self.visible_movement(mvmt);
                 self.selection = movement(mvmt, self.selection, &self.layout, true)
             }
             EditAction::Click(action) => {
-                if action.mods.shift() {
+                self.drag_granularity = match action.count {
+                    0 | 1 => Granularity::Grapheme,
+                    2 => Granularity::Word,
+                    _ => Granularity::Paragraph,
+                };
+                if !action.mods.alt() {
+                    self.secondary.clear();
+                }
+                if self.drag_granularity != Granularity::Grapheme {
+                    self.drag_anchor = self.unit_range(data, action.column);
+                    self.selection = Selection::new(self.drag_anchor.start, self.drag_anchor.end);
+                } else if action.mods.shift() {
                     self.selection.end = action.column;
+                } else if action.mods.alt() {
+                    // alt+click adds a cursor
+                    self.secondary.push(self.selection);
+                    self.selection = Selection::caret(action.column);
                 } else {
                     self.selection = Selection::caret(action.column);
                 }
             }
-            EditAction::Drag(action) => self.selection.end = action.column,
-            EditAction::SelectAll => self.selection = Selection::new(0, data.len()),
+            EditAction::Drag(action) => {
+                if self.drag_granularity == Granularity::Grapheme {
+                    self.selection.end = action.column;
+                } else {
+                    // extend the selection by whole units, keeping the anchor selected
+                    let unit = self.unit_range(data, action.column);
+                    self.selection = if unit.start < self.drag_anchor.start {
+                        Selection::new(self.drag_anchor.end, unit.start)
+                    } else {
+                        Selection::new(self.drag_anchor.start, unit.end.max(self.drag_anchor.end))
+                    };
+                }
+            }
+            EditAction::SelectAll => self.select_all(data),
+            EditAction::Undo => self.undo(data),
+            EditAction::Redo => self.redo(data),
+            EditAction::Copy => {
+                if !self.is_masked() {
+                    self.set_clipboard();
+                }
+            }
+            EditAction::CollapseSelection { to_start } => {
+                let offset = if to_start {
+                    self.selection.min()
+                } else {
+                    self.selection.end
+                };
+                self.selection = Selection::caret(offset);
+            }
+            EditAction::Kill(mvmt) => {
+                let mvmt = self.visible_movement(mvmt);
+                self.secondary.clear();
+                let caret = Selection::caret(self.selection.end);
+                let mut to_kill = movement(mvmt, caret, &self.layout, true);
+                // as in emacs, killing to the end of a line at its end
+                // kills the line break
+                if to_kill.is_caret() && mvmt == Movement::NextLineBreak {
+                    to_kill = Selection::new(
+                        caret.end,
+                        data.next_grapheme_offset(caret.end).unwrap_or(caret.end),
+                    );
+                }
+                let backwards = to_kill.end < to_kill.start;
+                self.kill(
+                    data,
+                    to_kill.range(),
+                    continuation == Continuation::Kill,
+                    backwards,
+                );
+            }
+            EditAction::KillSelection => {
+                self.secondary.clear();
+                let range = self.selection.range();
+                self.kill(data, range, continuation == Continuation::Kill, false);
+            }
+            EditAction::CopyToKillRing => {
+                if !self.is_masked() {
+                    if let Some(text) = data.slice(self.selection.range()) {
+                        self.kill_ring.push(text.into_owned());
+                    }
+                }
+            }
+            EditAction::Yank => {
+                self.secondary.clear();
+                if let Some(text) = self.kill_ring.get(0).map(str::to_owned) {
+                    let inserted = self.insert_filtered(data, &text, true);
+                    self.history.seal();
+                    if let Some(range) = inserted {
+                        self.continuation = Continuation::Yank { range, index: 0 };
+                    }
+                }
+            }
+            EditAction::YankPop => {
+                if let Continuation::Yank { range, index } = continuation {
+                    let index = (index + 1) % self.kill_ring.len();
+                    let text = self.kill_ring.get(index).unwrap_or_default().to_owned();
+                    self.selection = Selection::new(range.start, range.end);
+                    let inserted = self.insert_filtered(data, &text, true);
+                    self.history.seal();
+                    if let Some(range) = inserted {
+                        self.continuation = Continuation::Yank { range, index };
+                    }
+                }
+            }
+            EditAction::AddCursorAbove => self.add_cursor_vertically(Movement::Up),
+            EditAction::AddCursorBelow => self.add_cursor_vertically(Movement::Down),
+            EditAction::AddNextOccurrence => self.add_next_occurrence(data),
+            EditAction::RemoveSecondaryCursors => self.secondary.clear(),
+            EditAction::Replace(replacements) => self.replace_ranges(data, replacements),
         }
     }
 
+    /// Replace each range with its text, recording the replacements as a
+    /// single undo step.
+    fn replace_ranges(&mut self, data: &mut T, mut replacements: Vec<(Range<usize>, String)>) {
+        replacements.sort_by_key(|(range, _)| range.start);
+        self.secondary.clear();
+        let original = self.selection;
+        // the caret after the last replacement, where the ranges before it
+        // have not been replaced yet
+        let mut caret = None;
+        self.history.begin_group();
+        // back to front, so that the ranges before each replacement stay valid
+        for (range, text) in replacements.into_iter().rev() {
+            if data.slice(range.clone()).is_none() {
+                continue;
+            }
+            let text = match self.filtered_replacement(data, range.clone(), &text) {
+                Some(text) => text,
+                None => continue,
+            };
+            let end = match caret {
+                None => range.start + text.len(),
+                Some(end) => end + text.len() - range.len(),
+            };
+            caret = Some(end);
+            // every change restores the original selection when undone
+            self.selection = original;
+            self.replace(data, range, &text, Selection::caret(end), false);
+        }
+        self.history.end_group();
+        self.selection = caret.map(Selection::caret).unwrap_or(original);
+    }
+
+    /// Apply an edit to each selection in turn.
+    ///
+    /// The selections are edited back to front, so that each edit leaves
+    /// the offsets of the selections before it valid. The selections that
+    /// were already edited are moved by the change in length, but not before
+    /// the selection that was just edited, and are then merged where they
+    /// meet.
+    fn for_each_selection(&mut self, data: &mut T, mut edit: impl FnMut(&mut Self, &mut T)) {
+        let primary = self.selection;
+        let mut selections = std::mem::take(&mut self.secondary);
+        selections.push(primary);
+        selections.sort_by_key(|selection| (selection.min(), selection.max()));
+        let primary_index = selections.iter().position(|s| *s == primary).unwrap_or(0);
+
+        let mut edited: Vec<Selection> = Vec::with_capacity(selections.len());
+        self.history.begin_group();
+        for (i, selection) in selections.into_iter().enumerate().rev() {
+            let len = data.len();
+            let recorded = self.history.group_len();
+            self.selection = selection.constrained(data);
+            edit(self, data);
+            if i == primary_index && self.history.group_len() > recorded {
+                self.history.mark_primary();
+            }
+            let delta = data.len() as isize - len as isize;
+            shift_selections(&mut edited, delta, self.selection.min(), data.len());
+            edited.push(self.selection);
+        }
+        self.history.end_group();
+
+        edited.reverse();
+        self.selection = edited.remove(primary_index);
+        self.secondary = edited;
+        self.merge_selections();
+    }
+
+    /// Merge selections that overlap, or carets at the same position.
+    fn merge_selections(&mut self) {
+        if self.secondary.is_empty() {
+            return;
+        }
+        let primary = self.selection;
+        let mut selections = std::mem::take(&mut self.secondary);
+        selections.push(primary);
+        selections.sort_by_key(|selection| (selection.min(), selection.max()));
+
+        let mut merged: Vec<(Selection, bool)> = Vec::with_capacity(selections.len());
+        for selection in selections {
+            let is_primary = selection == primary;
+            match merged.last_mut() {
+                Some((last, last_is_primary))
+                    if selection.min() < last.max() || selection.min() == last.min() =>
+                {
+                    if selection.max() > last.max() {
+                        let (min, max) = (last.min(), selection.max());
+                        *last = if last.end < last.start {
+                            Selection::new(max, min)
+                        } else {
+                            Selection::new(min, max)
+                        };
+                    }
+                    *last_is_primary |= is_primary;
+                }
+                _ => merged.push((selection, is_primary)),
+            }
+        }
+
+        let primary_index = merged.iter().position(|(_, p)| *p).unwrap_or(0);
+        self.selection = merged.remove(primary_index).0;
+        self.secondary = merged.into_iter().map(|(selection, _)| selection).collect();
+    }
+
+    /// Add a caret on the line above or below the primary selection.
+    fn add_cursor_vertically(&mut self, direction: Movement) {
+        let caret = Selection::caret(self.selection.end).with_h_pos(self.selection.h_pos);
+        let moved = movement(direction, caret, &self.layout, false);
+        let line_y = |offset| self.layout.cursor_line_for_text_position(offset).p0.y;
+        // there is no line to add a cursor to at the first or last line
+        if line_y(moved.end) != line_y(caret.end) {
+            self.secondary.push(self.selection);
+            self.selection = moved;
+        }
+    }
+
+    /// Select the next occurrence of the primary selection's text, or the
+    /// word around the primary caret.
+    fn add_next_occurrence(&mut self, data: &T) {
+        // don't give away the contents of masked text
+        if self.is_masked() {
+            return;
+        }
+        let text = data.as_str();
+        if self.selection.is_caret() {
+            let word = word_range(text, self.selection.end);
+            self.selection = Selection::new(word.start, word.end);
+            return;
+        }
+        let needle = &text[self.selection.range()];
+        let from = self.selection.max();
+        let selected: Vec<usize> = std::iter::once(&self.selection)
+            .chain(&self.secondary)
+            .map(|selection| selection.min())
+            .collect();
+        let next = text[from..]
+            .match_indices(needle)
+            .map(|(offset, _)| from + offset)
+            .chain(text[..from].match_indices(needle).map(|(offset, _)| offset))
+            .find(|offset| !selected.contains(offset));
+        if let Some(start) = next {
+            self.secondary.push(self.selection);
+            self.selection = Selection::new(start, start + needle.len());
+        }
+    }
+
+    /// Constrain a selection to the current text.
+    fn constrained(&self, selection: Selection) -> Selection {
+        self.layout
+            .text()
+            .map(|t| selection.constrained(t))
+            .unwrap_or_else(|| Selection::caret(0))
+    }
+
     /// Draw this editor at the provided point.
+    ///
+    /// Search results are highlighted, and text that is being composed by
+    /// an input method is underlined.
     pub fn draw(&self, ctx: &mut PaintCtx, point: impl Into<Point>) {
-        self.layout.draw(ctx, point)
+        let visible = self.layout.size().to_rect();
+        self.draw_visible(ctx, point, visible);
+    }
+
+    /// Draw this editor at the provided point, where only `visible`, a rect
+    /// relative to the text, is shown.
+    ///
+    /// Misspelled words are only underlined in the paragraphs that are
+    /// visible, so that painting doesn't go through all of a long text.
+    pub fn draw_visible(&self, ctx: &mut PaintCtx, point: impl Into<Point>, visible: Rect) {
+        let point = point.into();
+        if !self.is_masked() && !self.search_matches.is_empty() {
+            let color = ctx.env().get(theme::SELECTION_COLOR).with_alpha(0.4);
+            let text = self.layout.text();
+            for range in &self.search_matches {
+                if text.and_then(|text| text.slice(range.clone())).is_none() {
+                    continue;
+                }
+                for rect in self.layout.rects_for_range(range.clone()) {
+                    let rect = rect + point.to_vec2();
+                    ctx.fill(rect.to_rounded_rect(1.0), &color);
+                }
+            }
+        }
+        let preedit_rects = match (&self.masked_layout, self.preedit_range()) {
+            (_, None) => Vec::new(),
+            (Some(masked), Some(range)) => {
+                let start = self.masked_offset(range.start);
+                let end = self.masked_offset(range.end);
+                masked.rects_for_range(start..end)
+            }
+            (None, Some(range)) => self.layout.rects_for_range(range),
+        };
+        match &self.masked_layout {
+            Some(masked) => masked.draw(ctx, point),
+            None => self.layout.draw(ctx, point),
+        }
+        for range in self.misspelled_words_in(self.paragraphs_in(visible)) {
+            for rect in self.layout.rects_for_range(range) {
+                let rect = rect + point.to_vec2();
+                ctx.stroke(
+                    squiggle(rect.x0, rect.x1, rect.y1 - 1.5),
+                    &MISSPELLED_COLOR,
+                    1.0,
+                );
+            }
+        }
+        if !preedit_rects.is_empty() {
+            let color = ctx.env().get(theme::LABEL_COLOR);
+            for rect in preedit_rects {
+                let rect = rect + point.to_vec2();
+                let y = rect.y1 - 0.5;
+                ctx.stroke(Line::new((rect.x0, y), (rect.x1, y)), &color, 1.0);
+            }
+        }
+    }
+
+    /// The range of the paragraphs that have any of `rect`, relative to the
+    /// text.
+    fn paragraphs_in(&self, rect: Rect) -> Range<usize> {
+        let text = match self.layout.text() {
+            Some(text) => text,
+            None => return 0..0,
+        };
+        let start = self
+            .layout
+            .text_position_for_point(Point::new(rect.x0, rect.y0));
+        let end = self
+            .layout
+            .text_position_for_point(Point::new(rect.x1, rect.y1));
+        text.preceding_line_break(start.min(end))..text.next_line_break(start.max(end))
     }
 
     /// Returns `true` if the data passed here has been changed externally,
@@ -255,48 +1036,333 @@ impl<T: TextStorage + EditableText> Editor<T> {
     /// the data before us while handling an event; if this is the case we ignore
     /// the event, and our data will be updated in `update`.
     fn data_is_stale(&self, data: &T) -> bool {
-        self.layout
-            .text()
-            .map(|t| t.as_str() != data.as_str())
-            .unwrap_or(true)
+        self.layout.text().map(|t| !t.text_eq(data)).unwrap_or(true)
     }
 
-    fn insert(&mut self, data: &mut T, text: &str) {
-        // if we aren't multiline, we insert only up to the first newline
-        let text = if self.multiline {
+    /// The movement to make for `mvmt`, which in masked text moves to the
+    /// start or end of the text rather than giving away where its words,
+    /// sentences and paragraphs are.
+    fn visible_movement(&self, mvmt: Movement) -> Movement {
+        if !self.is_masked() {
+            return mvmt;
+        }
+        match mvmt {
+            Movement::LeftWord | Movement::StartOfSentence | Movement::StartOfParagraph => {
+                Movement::StartOfDocument
+            }
+            Movement::RightWord
+            | Movement::RightWordStart
+            | Movement::EndOfSentence
+            | Movement::EndOfParagraph => Movement::EndOfDocument,
+            mvmt => mvmt,
+        }
+    }
+
+    /// The range of the word or paragraph around `offset`, depending on
+    /// the current drag granularity.
+    fn unit_range(&self, data: &T, offset: usize) -> Range<usize> {
+        match self.drag_granularity {
+            Granularity::Grapheme => offset..offset,
+            // don't give away word boundaries in masked text
+            Granularity::Word if self.is_masked() => 0..data.len(),
+            Granularity::Word => word_range(data.as_str(), offset),
+            Granularity::Paragraph => {
+                data.preceding_line_break(offset)..data.next_line_break(offset)
+            }
+        }
+    }
+
+    /// Take the text being composed, dropping it if its range no longer
+    /// fits the data.
+    ///
+    /// Unlike other edits, composition may continue before the owning widget
+    /// has synced the editor with the data, so we can't use [`data_is_stale`].
+    ///
+    /// [`data_is_stale`]: #method.data_is_stale
+    fn take_preedit(&mut self, data: &T) -> Option<Preedit> {
+        let preedit = self.preedit.take()?;
+        if data.slice(preedit.range.clone()).is_some() {
+            Some(preedit)
+        } else {
+            log::warn!("editor data changed externally, discarding preedit");
+            None
+        }
+    }
+
+    /// Replace the masked text with one bullet per grapheme of the real text.
+    fn update_masked_text(&mut self) {
+        if let (Some(masked), Some(text)) = (&mut self.masked_layout, self.layout.text()) {
+            let count = text.as_str().graphemes(true).count();
+            masked.set_text(MASK_CHAR.to_string().repeat(count));
+        }
+    }
+
+    /// Highlight and check the spelling of the layout text after it was
+    /// set.
+    ///
+    /// Edits made through [`edit_data`] are highlighted and checked as they
+    /// are made, so the data they left isn't checked again; any other text
+    /// is highlighted and checked in full here.
+    ///
+    /// [`edit_data`]: #method.edit_data
+    fn update_decorations(&mut self) {
+        let edited = self.edited.take();
+        let text = match self.layout.text() {
+            Some(text) => text,
+            None => return,
+        };
+        if !edited.is_some_and(|edited| edited.same(text)) {
+            if let Some(highlighting) = &mut self.highlighting {
+                highlighting.set_text(text.as_str());
+            }
+            if let Some(check) = &mut self.spell_check {
+                check.set_text(text.as_str());
+            }
+        }
+        self.apply_highlighting();
+    }
+
+    /// Apply the highlighting to the layout.
+    fn apply_highlighting(&mut self) {
+        if let Some(highlighting) = &self.highlighting {
+            self.layout.set_attributes(Some(highlighting.spans()));
+        }
+    }
+
+    /// Replace `range` of `data` with `text`, highlighting and checking the
+    /// spelling of the changed lines again.
+    ///
+    /// All edits made by the editor go through this method, on data that
+    /// matches the layout text or the data left by the previous edit.
+    fn edit_data(&mut self, data: &mut T, range: Range<usize>, text: &str) {
+        data.edit(range.clone(), text);
+        if let Some(highlighting) = &mut self.highlighting {
+            highlighting.edit(data, range.clone(), text.len());
+        }
+        if let Some(check) = &mut self.spell_check {
+            check.edit(data, range, text.len());
+        }
+        self.edited = Some(data.clone());
+    }
+
+    /// Map an offset in the real text to the matching offset in the masked text.
+    fn masked_offset(&self, offset: usize) -> usize {
+        let text = self.layout.text().map(|t| t.as_str()).unwrap_or_default();
+        let count = text.get(..offset).unwrap_or(text).graphemes(true).count();
+        count * MASK_CHAR.len_utf8()
+    }
+
+    /// Map an offset in the masked text to the matching offset in the real text.
+    fn unmasked_offset(&self, masked_offset: usize) -> usize {
+        let text = self.layout.text().map(|t| t.as_str()).unwrap_or_default();
+        let count = masked_offset / MASK_CHAR.len_utf8();
+        text.grapheme_indices(true)
+            .nth(count)
+            .map(|(offset, _)| offset)
+            .unwrap_or_else(|| text.len())
+    }
+
+    /// If we aren't multiline, returns `text` up to the first newline.
+    fn single_line<'a>(&self, text: &'a str) -> &'a str {
+        if self.multiline {
             text
         } else {
             text.split('\n').next().unwrap_or("")
-        };
-        let sel = self.selection.range();
-        data.edit(sel, text);
-        self.selection = Selection::caret(self.selection.min() + text.len());
+        }
+    }
+
+    fn insert(&mut self, data: &mut T, text: &str) {
+        let text = self.single_line(text);
+        let selection = Selection::caret(self.selection.min() + text.len());
+        self.replace(data, self.selection.range(), text, selection, true);
+    }
+
+    /// Insert `text` if the input filter allows it, returning the range of
+    /// the inserted text.
+    ///
+    /// If `truncate` is true, as much of `text` as the filter allows is
+    /// inserted. This returns `None` if the text was rejected, or if the
+    /// filter replaced the whole text instead.
+    fn insert_filtered(
+        &mut self,
+        data: &mut T,
+        text: &str,
+        truncate: bool,
+    ) -> Option<Range<usize>> {
+        let text = self.single_line(text);
+        let start = self.selection.min();
+        if self.input_filter.is_empty() {
+            self.insert(data, text);
+            return Some(start..start + text.len());
+        }
+        let filtered =
+            self.input_filter
+                .apply(data.as_str(), self.selection.range(), text, truncate);
+        match filtered {
+            Filtered::Insert(text, selection) => {
+                self.insert(data, &text);
+                if let Some(selection) = selection {
+                    self.selection = selection.constrained(data);
+                }
+                Some(start..start + text.len())
+            }
+            Filtered::Replace(text, selection) => {
+                let selection = selection.constrained(&text);
+                self.replace(data, 0..data.len(), &text, selection, false);
+                None
+            }
+            Filtered::Rejected(selection, error) => {
+                if let Some(selection) = selection {
+                    self.selection = selection.constrained(data);
+                }
+                self.rejection = error;
+                None
+            }
+        }
+    }
+
+    /// The text to replace `range` with in place of `text`, as allowed by
+    /// the input filter, or `None` if the filter rejects it.
+    ///
+    /// This is for edits that keep their own selection, so a filter that
+    /// would change the selection or the whole text rejects the edit.
+    fn filtered_replacement(
+        &mut self,
+        data: &T,
+        range: Range<usize>,
+        text: &str,
+    ) -> Option<String> {
+        let text = self.single_line(text);
+        if self.input_filter.is_empty() {
+            return Some(text.to_owned());
+        }
+        match self.input_filter.apply(data.as_str(), range, text, false) {
+            Filtered::Insert(text, None) => Some(text),
+            Filtered::Rejected(_, error) => {
+                self.rejection = error;
+                None
+            }
+            _ => None,
+        }
     }
 
     /// Delete backwards, using fancy logic when in caret mode.
     fn delete_backward(&mut self, data: &mut T) {
-        let cursor_pos = if self.selection.is_caret() {
+        let to_delete = if self.selection.is_caret() {
             let del_end = self.selection.end;
             let del_start = offset_for_delete_backwards(&self.selection, data);
-            data.edit(del_start..del_end, "");
-            del_start
+            del_start..del_end
         } else {
-            data.edit(self.selection.range(), "");
-            self.selection.min()
+            self.selection.range()
         };
 
-        self.selection = Selection::caret(cursor_pos);
+        let selection = Selection::caret(to_delete.start);
+        self.replace(data, to_delete, "", selection, false);
     }
 
     fn delete_forward(&mut self, data: &mut T) {
+        // this deletes forwards in the text, rather than to the right
         let to_delete = if self.selection.is_caret() {
-            movement(Movement::Right, self.selection, &self.layout, true)
+            Selection::new(
+                self.selection.end,
+                data.next_grapheme_offset(self.selection.end)
+                    .unwrap_or(self.selection.end),
+            )
         } else {
             self.selection
         };
 
-        data.edit(to_delete.range(), "");
-        self.selection = Selection::caret(self.selection.min());
+        let selection = Selection::caret(self.selection.min());
+        self.replace(data, to_delete.range(), "", selection, false);
+    }
+
+    /// Replace `range` with `text` and set the new selection, recording
+    /// the change so that it can be undone.
+    fn replace(
+        &mut self,
+        data: &mut T,
+        range: Range<usize>,
+        text: &str,
+        selection: Selection,
+        is_insert: bool,
+    ) {
+        let removed = data
+            .slice(range.clone())
+            .map(|removed| removed.into_owned())
+            .unwrap_or_default();
+        if !removed.is_empty() || !text.is_empty() {
+            let change = Change {
+                offset: range.start,
+                removed,
+                inserted: text.to_owned(),
+                selection_before: self.selection,
+                selection_after: selection,
+            };
+            self.history.push(change, is_insert);
+            self.edit_data(data, range, text);
+        }
+        self.selection = selection;
+    }
+
+    /// Delete `range`, adding the deleted text to the kill ring or, if
+    /// `extend` is true, to the most recent kill.
+    fn kill(&mut self, data: &mut T, range: Range<usize>, extend: bool, backwards: bool) {
+        if range.is_empty() {
+            return;
+        }
+        let killed = data
+            .slice(range.clone())
+            .map(|killed| killed.into_owned())
+            .unwrap_or_default();
+        if !self.is_masked() {
+            if extend {
+                self.kill_ring.extend(&killed, backwards);
+            } else {
+                self.kill_ring.push(killed);
+            }
+        }
+        let selection = Selection::caret(range.start);
+        self.replace(data, range, "", selection, false);
+        self.continuation = Continuation::Kill;
+    }
+
+    fn undo(&mut self, data: &mut T) {
+        let step = match self.history.undo() {
+            Some(step) => step,
+            None => return,
+        };
+        // the changes of a step were made back to front, and are undone front
+        // to back, so the offsets of each are valid when it is undone
+        for change in step.changes.iter().rev() {
+            self.edit_data(data, change.inserted_range(), change.removed.as_str());
+        }
+        let mut selections: Vec<Selection> = step
+            .changes
+            .iter()
+            .map(|change| change.selection_before)
+            .collect();
+        self.selection = selections.remove(step.primary);
+        self.secondary = selections;
+    }
+
+    fn redo(&mut self, data: &mut T) {
+        let step = match self.history.redo() {
+            Some(step) => step,
+            None => return,
+        };
+        // as in `for_each_selection`, the selections after the changes that
+        // were already redone are moved by each following change
+        let mut selections: Vec<Selection> = Vec::with_capacity(step.changes.len());
+        for change in &step.changes {
+            self.edit_data(data, change.removed_range(), change.inserted.as_str());
+            let delta = change.inserted.len() as isize - change.removed.len() as isize;
+            let floor = change.selection_after.min();
+            shift_selections(&mut selections, delta, floor, data.len());
+            selections.push(change.selection_after);
+        }
+        self.selection = selections.remove(step.primary);
+        self.secondary = selections;
+        self.merge_selections();
     }
 
     fn set_clipboard(&self) {
@@ -312,6 +1378,113 @@ impl<T: TextStorage + EditableText> Editor<T> {
     }
 }
 
+impl<T: TextStorage + EditableText> Composition<T> for Editor<T> {
+    fn set_preedit(&mut self, text: &str, data: &mut T) {
+        let preedit = match self.take_preedit(data) {
+            Some(preedit) => preedit,
+            None if self.data_is_stale(data) => {
+                log::warn!(
+                    "editor data changed externally, skipping preedit {:?}",
+                    text
+                );
+                return;
+            }
+            None => {
+                self.history.seal();
+                Preedit {
+                    range: self.selection.range(),
+                    replaced: data
+                        .slice(self.selection.range())
+                        .map(|replaced| replaced.into_owned())
+                        .unwrap_or_default(),
+                    selection: self.selection,
+                }
+            }
+        };
+        // preedit text is not recorded in the history until it is committed
+        self.edit_data(data, preedit.range.clone(), text);
+        let start = preedit.range.start;
+        self.selection = Selection::caret(start + text.len());
+        self.preedit = Some(Preedit {
+            range: start..start + text.len(),
+            ..preedit
+        });
+    }
+
+    fn commit_preedit(&mut self, data: &mut T) {
+        if let Some(preedit) = self.take_preedit(data) {
+            // revert to the text before composition, then enter the composed
+            // text as a single edit
+            let composed = data
+                .slice(preedit.range.clone())
+                .map(|composed| composed.into_owned())
+                .unwrap_or_default();
+            self.edit_data(data, preedit.range, &preedit.replaced);
+            self.selection = preedit.selection;
+            self.insert_filtered(data, &composed, false);
+            self.history.seal();
+        }
+    }
+
+    fn cancel_preedit(&mut self, data: &mut T) {
+        if let Some(preedit) = self.take_preedit(data) {
+            self.edit_data(data, preedit.range, &preedit.replaced);
+            self.selection = preedit.selection;
+        }
+    }
+
+    fn preedit_range(&self) -> Option<Range<usize>> {
+        self.preedit.as_ref().map(|preedit| preedit.range.clone())
+    }
+}
+
+/// Move `selections`, which follow an edit, by the change in length `delta`.
+///
+/// An edit may remove text past the selections that follow it, so they are
+/// kept at or after `floor`, the start of the edited selection, and within
+/// the `len` of the text.
+fn shift_selections(selections: &mut [Selection], delta: isize, floor: usize, len: usize) {
+    let shift =
+        |offset: usize| (offset as isize + delta).clamp(floor as isize, len as isize) as usize;
+    for selection in selections {
+        selection.start = shift(selection.start);
+        selection.end = shift(selection.end);
+    }
+}
+
+/// A wavy line from `x0` to `x1`, centered on `y`, for underlining
+/// misspelled words.
+fn squiggle(x0: f64, x1: f64, y: f64) -> BezPath {
+    const STEP: f64 = 2.0;
+    let mut path = BezPath::new();
+    path.move_to((x0, y));
+    let mut x = x0;
+    let mut up = true;
+    while x < x1 {
+        x = (x + STEP).min(x1);
+        path.line_to((x, if up { y - 1.0 } else { y + 1.0 }));
+        up = !up;
+    }
+    path
+}
+
+/// Returns the range of the word, or of the run of whitespace or punctuation,
+/// that contains `offset`, using Unicode word boundaries.
+///
+/// If `offset` is at the end of the text, this is the range of the last
+/// segment, which is punctuation or whitespace if the text ends with it.
+fn word_range(text: &str, offset: usize) -> Range<usize> {
+    let mut last = 0..0;
+    for (start, word) in text.split_word_bound_indices() {
+        let range = start..start + word.len();
+        if range.contains(&offset) {
+            return range;
+        }
+        last = range;
+    }
+    last
+}
+
 impl<T> Default for Editor<T> {
     fn default() -> Self {
         Editor::new()
@@ -321,6 +1494,8 @@ impl<T> Default for Editor<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::text::SearchQuery;
+    use druid::Modifiers;
 
     /// Devanagari codepoints are 3 utf-8 code units each.
     #[test]
@@ -344,6 +1519,635 @@ mod tests {
         assert_eq!(data, String::from(""));
     }
 
+    /// Perform an edit and sync the editor with the result, as an owning
+    /// widget would do in `update`.
+    fn edit(editor: &mut Editor<String>, data: &mut String, action: EditAction) {
+        editor.do_edit(action, data);
+        editor.set_text(data.clone());
+    }
+
+    #[test]
+    fn undo_groups_inserts() {
+        let mut editor = Editor::new();
+        let mut data = "".to_string();
+        editor.set_text(data.clone());
+
+        for c in &["a", "b", "c"] {
+            edit(&mut editor, &mut data, EditAction::Insert(c.to_string()));
+        }
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        assert_eq!(data, "ab");
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "abc");
+        assert_eq!(editor.selection(), &Selection::caret(3));
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "");
+        assert_eq!(editor.selection(), &Selection::caret(0));
+        assert!(!editor.can_undo());
+
+        edit(&mut editor, &mut data, EditAction::Redo);
+        assert_eq!(data, "abc");
+        assert_eq!(editor.selection(), &Selection::caret(3));
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        let mut editor = Editor::new();
+        let mut data = "one two three".to_string();
+        editor.set_text(data.clone());
+
+        editor.set_selection(Selection::new(3, 7));
+        edit(&mut editor, &mut data, EditAction::KillSelection);
+        assert_eq!(data, "one three");
+        // setting the selection ends the kill, so this is a new entry
+        editor.set_selection(Selection::new(3, 9));
+        edit(&mut editor, &mut data, EditAction::KillSelection);
+        assert_eq!(data, "one");
+        edit(&mut editor, &mut data, EditAction::Yank);
+        assert_eq!(data, "one three");
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "one two");
+
+        editor.set_selection(Selection::new(0, 3));
+        edit(&mut editor, &mut data, EditAction::CopyToKillRing);
+        editor.set_selection(Selection::caret(7));
+        edit(&mut editor, &mut data, EditAction::Yank);
+        assert_eq!(data, "one twoone");
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "one two three");
+        assert_eq!(editor.selection(), &Selection::caret(13));
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "one two two");
+
+        // yank-pop does nothing if the last edit wasn't a yank
+        edit(
+            &mut editor,
+            &mut data,
+            EditAction::CollapseSelection { to_start: true },
+        );
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "one two two");
+    }
+
+    #[test]
+    fn selection_changes_end_kills() {
+        let mut editor = Editor::new();
+        let mut data = "one two".to_string();
+        editor.set_text(data.clone());
+
+        editor.set_selection(Selection::new(0, 4));
+        edit(&mut editor, &mut data, EditAction::KillSelection);
+        editor.select_all(&data);
+        edit(&mut editor, &mut data, EditAction::KillSelection);
+        assert_eq!(data, "");
+        edit(&mut editor, &mut data, EditAction::Yank);
+        assert_eq!(data, "two");
+
+        // yank-pop does nothing once the selection has changed
+        editor.select_all(&data);
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "two");
+        editor.set_selection(Selection::caret(3));
+        edit(&mut editor, &mut data, EditAction::Yank);
+        editor.set_selection(Selection::caret(0));
+        edit(&mut editor, &mut data, EditAction::YankPop);
+        assert_eq!(data, "twotwo");
+    }
+
+    fn carets(editor: &Editor<String>) -> Vec<usize> {
+        let mut carets: Vec<_> = std::iter::once(editor.selection())
+            .chain(editor.secondary_selections())
+            .map(|selection| selection.end)
+            .collect();
+        carets.sort_unstable();
+        carets
+    }
+
+    #[test]
+    fn edit_every_occurrence() {
+        let mut editor = Editor::new();
+        let mut data = "foo = 1\nfoo + foo".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::caret(1));
+
+        edit(&mut editor, &mut data, EditAction::AddNextOccurrence);
+        assert_eq!(editor.selection(), &Selection::new(0, 3));
+        for _ in 0..3 {
+            edit(&mut editor, &mut data, EditAction::AddNextOccurrence);
+        }
+        assert_eq!(editor.selection(), &Selection::new(14, 17));
+        assert_eq!(editor.secondary_selections().len(), 2);
+
+        edit(&mut editor, &mut data, EditAction::Insert("bar".into()));
+        assert_eq!(data, "bar = 1\nbar + bar");
+        assert_eq!(carets(&editor), vec![3, 11, 17]);
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        assert_eq!(data, "ba = 1\nba + ba");
+        assert_eq!(carets(&editor), vec![2, 9, 14]);
+
+        // each edit at all cursors is a single undo step
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "bar = 1\nbar + bar");
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "foo = 1\nfoo + foo");
+        assert_eq!(editor.selection(), &Selection::new(14, 17));
+        assert_eq!(editor.secondary_selections().len(), 2);
+        edit(&mut editor, &mut data, EditAction::Redo);
+        assert_eq!(data, "bar = 1\nbar + bar");
+        assert_eq!(carets(&editor), vec![3, 11, 17]);
+        assert_eq!(editor.selection(), &Selection::caret(17));
+    }
+
+    #[test]
+    fn alt_click_adds_cursors() {
+        let mut editor = Editor::new();
+        let mut data = "abc".to_string();
+        editor.set_text(data.clone());
+        let click = |column, mods| {
+            EditAction::Click(MouseAction {
+                row: 0,
+                column,
+                mods,
+                count: 1,
+            })
+        };
+
+        edit(&mut editor, &mut data, click(1, Modifiers::empty()));
+        // a cursor at the same position is merged
+        edit(&mut editor, &mut data, click(1, Modifiers::ALT));
+        assert!(editor.secondary_selections().is_empty());
+        edit(&mut editor, &mut data, click(2, Modifiers::ALT));
+        assert_eq!(carets(&editor), vec![1, 2]);
+        edit(&mut editor, &mut data, EditAction::Insert("x".into()));
+        assert_eq!(data, "axbxc");
+        // deleting the text between the cursors merges them
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        assert_eq!(data, "c");
+        assert_eq!(carets(&editor), vec![0]);
+
+        edit(&mut editor, &mut data, click(1, Modifiers::ALT));
+        edit(&mut editor, &mut data, click(0, Modifiers::empty()));
+        assert!(editor.secondary_selections().is_empty());
+    }
+
+    #[test]
+    fn multiple_cursor_history() {
+        let mut editor = Editor::new();
+        let mut data = "abc".to_string();
+        editor.set_text(data.clone());
+        editor.set_history_limit(2);
+        editor.set_selection(Selection::caret(2));
+        editor.add_selection(Selection::caret(1));
+
+        // typing at every cursor is merged into one step
+        edit(&mut editor, &mut data, EditAction::Insert("x".into()));
+        edit(&mut editor, &mut data, EditAction::Insert("y".into()));
+        assert_eq!(data, "axybxyc");
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        assert_eq!(data, "axbxc");
+
+        // the limit counts steps rather than changes
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "axybxyc");
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "abc");
+        assert!(!editor.can_undo());
+        // the primary cursor is restored, even though it isn't the last
+        assert_eq!(editor.selection(), &Selection::caret(1));
+        assert_eq!(carets(&editor), vec![1, 2]);
+
+        edit(&mut editor, &mut data, EditAction::Redo);
+        assert_eq!(data, "axybxyc");
+        assert_eq!(editor.selection(), &Selection::caret(3));
+        assert_eq!(carets(&editor), vec![3, 6]);
+    }
+
+    #[test]
+    fn deletion_across_a_later_cursor() {
+        let mut editor = Editor::new();
+        let mut data = "aa\nbb\ncc\n".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::caret(2));
+        editor.add_selection(Selection::caret(3));
+
+        // each cursor deletes past the one after it, as `dj` does
+        editor.for_each_selection(&mut data, |editor, data| {
+            let start = editor.selection.min();
+            let end = (start + 4).min(data.len());
+            editor.replace(data, start..end, "", Selection::caret(start), false);
+        });
+        editor.set_text(data.clone());
+        assert_eq!(data, "aa");
+        // the later cursor isn't moved before the earlier one, and they merge
+        assert_eq!(editor.selection(), &Selection::caret(2));
+        assert_eq!(carets(&editor), vec![2]);
+    }
+
+    #[test]
+    fn replace_all_is_one_step() {
+        let mut editor = Editor::new();
+        let mut data = "a = 1; aa = 2".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::caret(1));
+
+        let query = SearchQuery::new("a").whole_word(true);
+        let replacements = data
+            .find_all(&query)
+            .into_iter()
+            .chain(Some(12..13))
+            .map(|range| (range, "bb".to_string()))
+            .collect();
+        edit(&mut editor, &mut data, EditAction::Replace(replacements));
+        assert_eq!(data, "bb = 1; aa = bb");
+        assert_eq!(editor.selection(), &Selection::caret(15));
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "a = 1; aa = 2");
+        assert_eq!(editor.selection(), &Selection::caret(1));
+        assert!(editor.secondary_selections().is_empty());
+        edit(&mut editor, &mut data, EditAction::Redo);
+        assert_eq!(data, "bb = 1; aa = bb");
+        assert_eq!(editor.selection(), &Selection::caret(15));
+        assert!(editor.secondary_selections().is_empty());
+    }
+
+    #[test]
+    fn input_filter() {
+        use crate::text::format::InputMask;
+
+        let mut editor = Editor::new();
+        let mut data = "12".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::caret(2));
+        editor.set_input_filter(
+            InputFilter::new()
+                .max_length(4)
+                .formatter(InputMask::Digits),
+        );
+
+        edit(&mut editor, &mut data, EditAction::Insert("a".into()));
+        assert_eq!(data, "12");
+        assert!(editor.rejection().is_some());
+        edit(&mut editor, &mut data, EditAction::Insert("3".into()));
+        assert_eq!(data, "123");
+        assert!(editor.rejection().is_none());
+        // a paste is truncated rather than rejected
+        edit(&mut editor, &mut data, EditAction::Paste("456".into()));
+        assert_eq!(data, "1234");
+        assert_eq!(editor.selection(), &Selection::caret(4));
+        edit(&mut editor, &mut data, EditAction::Insert("5".into()));
+        assert_eq!(data, "1234");
+
+        editor.set_input_filter(InputFilter::new().formatter(InputMask::Hex));
+        editor.set_selection(Selection::new(0, 2));
+        edit(&mut editor, &mut data, EditAction::Insert("f".into()));
+        assert_eq!(data, "F34");
+        assert_eq!(editor.selection(), &Selection::caret(1));
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "1234");
+    }
+
+    #[test]
+    fn every_insertion_is_filtered() {
+        let mut editor = Editor::new();
+        let mut data = "x9".to_string();
+        editor.set_text(data.clone());
+        editor.select_all(&data);
+        edit(&mut editor, &mut data, EditAction::CopyToKillRing);
+        editor.set_input_filter(InputFilter::new().chars(|c| c.is_ascii_digit()));
+
+        // a yank is truncated, like a paste
+        editor.set_selection(Selection::caret(2));
+        edit(&mut editor, &mut data, EditAction::Yank);
+        assert_eq!(data, "x99");
+
+        // a replacement that isn't allowed is skipped
+        let replacements = vec![(0..1, "y".to_string()), (1..2, "5".to_string())];
+        edit(&mut editor, &mut data, EditAction::Replace(replacements));
+        assert_eq!(data, "x59");
+        assert_eq!(editor.selection(), &Selection::caret(2));
+
+        let mut ime = TestInputMethod::default();
+        ime.key('n', &mut editor, &mut data);
+        ime.choose("你", &mut editor, &mut data);
+        assert_eq!(data, "x59");
+        ime.key('n', &mut editor, &mut data);
+        ime.choose("7", &mut editor, &mut data);
+        assert_eq!(data, "x579");
+    }
+
+    #[test]
+    fn spelling_follows_edits() {
+        use crate::text::WordList;
+
+        let mut editor = Editor::new();
+        let mut data = "helo world".to_string();
+        editor.set_text(data.clone());
+        editor.set_spell_checker(Some(Arc::new(WordList::new(vec!["hello", "world"]))));
+        assert_eq!(editor.misspelled_words(), vec![0..4]);
+        assert_eq!(
+            editor.spelling_suggestions(2),
+            Some((0..4, vec!["hello".to_string()]))
+        );
+        assert_eq!(editor.spelling_suggestions(6), None);
+
+        editor.set_selection(Selection::caret(10));
+        edit(&mut editor, &mut data, EditAction::Insert(" wrld".into()));
+        assert_eq!(editor.misspelled_words(), vec![0..4, 11..15]);
+        editor.set_selection(Selection::caret(0));
+        edit(
+            &mut editor,
+            &mut data,
+            EditAction::Replace(vec![(0..4, "hello".into())]),
+        );
+        assert_eq!(editor.misspelled_words(), vec![12..16]);
+
+        editor.set_masked(true);
+        assert!(editor.misspelled_words().is_empty());
+    }
+
+    #[test]
+    fn edits_keep_highlighting_current() {
+        use crate::text::JsonHighlighter;
+
+        let json: Arc<dyn Highlighter> = Arc::new(JsonHighlighter::default());
+        let mut editor = Editor::new();
+        editor.set_multiline(true);
+        let mut data = "{\"a\": 1}".to_string();
+        editor.set_text(data.clone());
+        editor.set_highlighter(Some(json.clone()));
+        editor.set_selection(Selection::caret(7));
+
+        let insert = EditAction::Insert(",\n\"b\": true".to_string());
+        edit(&mut editor, &mut data, insert);
+        edit(&mut editor, &mut data, EditAction::Backspace);
+        assert_eq!(data, "{\"a\": 1,\n\"b\": tru}");
+        let highlighting = editor.highlighting.as_ref().unwrap();
+        assert_eq!(highlighting.spans(), json.highlight(&data));
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "{\"a\": 1}");
+        let highlighting = editor.highlighting.as_ref().unwrap();
+        assert_eq!(highlighting.spans(), json.highlight(&data));
+    }
+
+    #[test]
+    fn edited_text_is_not_highlighted_again() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Counting(AtomicUsize);
+        impl Highlighter for Counting {
+            fn highlight_line(&self, _line: &str) -> Vec<(Range<usize>, crate::text::Attribute)> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Vec::new()
+            }
+        }
+
+        let counting = Arc::new(Counting::default());
+        let mut editor = Editor::new();
+        let mut data = "one\ntwo\nthree".to_string();
+        editor.set_text(data.clone());
+        editor.set_highlighter(Some(counting.clone()));
+        assert_eq!(counting.0.load(Ordering::Relaxed), 3);
+
+        // the edited data is given back to the editor, which only highlights
+        // the changed line
+        editor.set_selection(Selection::caret(5));
+        edit(&mut editor, &mut data, EditAction::Insert("x".into()));
+        assert_eq!(counting.0.load(Ordering::Relaxed), 4);
+
+        // other text is highlighted in full
+        editor.set_text("a\nb".to_string());
+        assert_eq!(counting.0.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn undo_restores_replaced_selection() {
+        let mut editor = Editor::new();
+        let mut data = "hello world".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::new(6, 11));
+
+        edit(
+            &mut editor,
+            &mut data,
+            EditAction::Paste("coat".to_string()),
+        );
+        assert_eq!(data, "hello coat");
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "hello world");
+        assert_eq!(editor.selection(), &Selection::new(6, 11));
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut editor = Editor::new();
+        let mut data = "".to_string();
+        editor.set_text(data.clone());
+        editor.set_history_limit(1);
+
+        edit(&mut editor, &mut data, EditAction::Insert("a".to_string()));
+        edit(&mut editor, &mut data, EditAction::Paste("b".to_string()));
+        edit(&mut editor, &mut data, EditAction::Undo);
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "a");
+
+        editor.clear_history();
+        assert!(!editor.can_undo());
+        assert!(!editor.can_redo());
+    }
+
+    fn click(column: usize, count: u8) -> MouseAction {
+        MouseAction {
+            row: 0,
+            column,
+            mods: Default::default(),
+            count,
+        }
+    }
+
+    #[test]
+    fn word_ranges() {
+        let text = "Hello, wörld!";
+        assert_eq!(word_range(text, 0), 0..5);
+        assert_eq!(word_range(text, 3), 0..5);
+        assert_eq!(word_range(text, 5), 5..6);
+        assert_eq!(word_range(text, 6), 6..7);
+        assert_eq!(word_range(text, 9), 7..13);
+        // at the end, the last segment is the punctuation
+        assert_eq!(word_range(text, 14), 13..14);
+        assert_eq!(word_range("one two", 7), 4..7);
+        assert_eq!(word_range("one ", 4), 3..4);
+        assert_eq!(word_range("", 0), 0..0);
+    }
+
+    #[test]
+    fn double_click_drag_extends_by_words() {
+        let mut editor = Editor::new();
+        let mut data = "one two three\nfour".to_string();
+        editor.set_text(data.clone());
+
+        editor.do_edit(EditAction::Click(click(5, 2)), &mut data);
+        assert_eq!(editor.selection(), &Selection::new(4, 7));
+        editor.do_edit(EditAction::Drag(click(10, 2)), &mut data);
+        assert_eq!(editor.selection(), &Selection::new(4, 13));
+        editor.do_edit(EditAction::Drag(click(1, 2)), &mut data);
+        assert_eq!(editor.selection(), &Selection::new(7, 0));
+    }
+
+    #[test]
+    fn triple_click_selects_paragraph() {
+        let mut editor = Editor::new();
+        let mut data = "one two three\nfour".to_string();
+        editor.set_text(data.clone());
+
+        editor.do_edit(EditAction::Click(click(5, 3)), &mut data);
+        assert_eq!(editor.selection(), &Selection::new(0, 13));
+        editor.do_edit(EditAction::Drag(click(16, 3)), &mut data);
+        assert_eq!(editor.selection(), &Selection::new(0, 18));
+    }
+
+    /// A headless stand-in for a platform input method, which composes
+    /// the keys typed so far until a candidate is chosen.
+    #[derive(Default)]
+    struct TestInputMethod {
+        typed: String,
+    }
+
+    impl TestInputMethod {
+        fn key(&mut self, key: char, editor: &mut Editor<String>, data: &mut String) {
+            self.typed.push(key);
+            editor.set_preedit(&self.typed, data);
+            editor.set_text(data.clone());
+        }
+
+        fn choose(&mut self, candidate: &str, editor: &mut Editor<String>, data: &mut String) {
+            self.typed.clear();
+            editor.set_preedit(candidate, data);
+            editor.commit_preedit(data);
+            editor.set_text(data.clone());
+        }
+
+        fn escape(&mut self, editor: &mut Editor<String>, data: &mut String) {
+            self.typed.clear();
+            editor.cancel_preedit(data);
+            editor.set_text(data.clone());
+        }
+    }
+
+    #[test]
+    fn compose_and_commit() {
+        let mut editor = Editor::new();
+        let mut data = "hello ".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::caret(6));
+        let mut ime = TestInputMethod::default();
+
+        ime.key('n', &mut editor, &mut data);
+        ime.key('i', &mut editor, &mut data);
+        assert_eq!(data, "hello ni");
+        assert_eq!(editor.preedit_range(), Some(6..8));
+        assert!(!editor.can_undo());
+
+        ime.choose("你", &mut editor, &mut data);
+        assert_eq!(data, "hello 你");
+        assert!(!editor.is_composing());
+        assert_eq!(editor.selection(), &Selection::caret(9));
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "hello ");
+        edit(&mut editor, &mut data, EditAction::Redo);
+        assert_eq!(data, "hello 你");
+    }
+
+    #[test]
+    fn cancel_composition() {
+        let mut editor = Editor::new();
+        let mut data = "hello".to_string();
+        editor.set_text(data.clone());
+        editor.set_selection(Selection::new(0, 5));
+        let mut ime = TestInputMethod::default();
+
+        ime.key('n', &mut editor, &mut data);
+        assert_eq!(data, "n");
+        ime.escape(&mut editor, &mut data);
+        assert_eq!(data, "hello");
+        assert_eq!(editor.selection(), &Selection::new(0, 5));
+        assert!(!editor.can_undo());
+    }
+
+    #[test]
+    fn edit_commits_composition() {
+        let mut editor = Editor::new();
+        let mut data = String::new();
+        editor.set_text(data.clone());
+        let mut ime = TestInputMethod::default();
+
+        ime.key('n', &mut editor, &mut data);
+        ime.key('i', &mut editor, &mut data);
+        edit(&mut editor, &mut data, EditAction::Insert("!".into()));
+        assert_eq!(data, "ni!");
+        assert!(!editor.is_composing());
+
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "ni");
+        edit(&mut editor, &mut data, EditAction::Undo);
+        assert_eq!(data, "");
+    }
+
+    /// Masked offsets count one bullet per grapheme cluster.
+    #[test]
+    fn masked_offsets() {
+        let mut editor = Editor::new();
+        editor.set_masked(true);
+        editor.set_text("a\u{006F}\u{0337}\u{1F4A9}".to_string());
+
+        let bullet = MASK_CHAR.len_utf8();
+        assert_eq!(editor.masked_offset(0), 0);
+        assert_eq!(editor.masked_offset(1), bullet);
+        assert_eq!(editor.masked_offset(4), 2 * bullet);
+        assert_eq!(editor.masked_offset(8), 3 * bullet);
+        assert_eq!(editor.unmasked_offset(2 * bullet), 4);
+        assert_eq!(editor.unmasked_offset(3 * bullet), 8);
+        assert_eq!(
+            editor.masked_layout.as_ref().unwrap().text().unwrap(),
+            &"\u{2022}\u{2022}\u{2022}".to_string()
+        );
+    }
+
+    /// Word, sentence and paragraph movements don't stop inside masked text.
+    #[test]
+    fn masked_movements() {
+        let mut editor = Editor::<String>::new();
+        assert_eq!(
+            editor.visible_movement(Movement::LeftWord),
+            Movement::LeftWord
+        );
+        editor.set_masked(true);
+        assert_eq!(
+            editor.visible_movement(Movement::LeftWord),
+            Movement::StartOfDocument
+        );
+        assert_eq!(
+            editor.visible_movement(Movement::StartOfSentence),
+            Movement::StartOfDocument
+        );
+        assert_eq!(
+            editor.visible_movement(Movement::RightWordStart),
+            Movement::EndOfDocument
+        );
+        assert_eq!(
+            editor.visible_movement(Movement::EndOfParagraph),
+            Movement::EndOfDocument
+        );
+        assert_eq!(editor.visible_movement(Movement::Left), Movement::Left);
+    }
+
     /// Test backspace on the combo character o̷
     #[test]
     fn backspace_combining() {
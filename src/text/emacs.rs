@@ -0,0 +1,211 @@
+//! An emacs-style `TextInput`.
+
+use super::{BasicTextInput, EditAction, Movement, TextInput};
+use druid::{Code, KbKey, KeyEvent};
+
+/// A [`TextInput`] with emacs-style key bindings.
+///
+/// This supports `C-a`, `C-e`, `C-f`, `C-b`, `C-n`, `C-p`, `M-f`, `M-b`,
+/// `M-a`, `M-e`, `M-{`, `M-}`, `M-<`, `M->`, `C-v` and `M-v` for movement,
+/// `C-d` to delete, `C-k`, `M-d`, `M-k` and `C-w` to kill, `M-w` to copy,
+/// `C-y` and `M-y` to yank, `C-space` to set the mark, `C-g` to clear it
+/// and `C-/` to undo. Other keys behave as in a [`BasicTextInput`].
+///
+/// Killed text is kept in the editor's kill ring rather than the system
+/// clipboard, and consecutive kills are joined into a single entry.
+///
+/// [`TextInput`]: trait.TextInput.html
+/// [`BasicTextInput`]: struct.BasicTextInput.html
+#[derive(Debug, Clone, Default)]
+pub struct EmacsTextInput {
+    /// Whether the mark is set, so that movement extends the selection.
+    mark_active: bool,
+    viewport_height: f64,
+    basic: BasicTextInput,
+}
+
+impl EmacsTextInput {
+    /// Create a new `EmacsTextInput`.
+    pub fn new() -> Self {
+        EmacsTextInput::default()
+    }
+
+    /// Returns `true` if the mark is set.
+    pub fn mark_active(&self) -> bool {
+        self.mark_active
+    }
+
+    /// Move, extending the selection if the mark is set.
+    fn motion(&self, movement: Movement) -> Vec<EditAction> {
+        if self.mark_active {
+            vec![EditAction::ModifySelection(movement)]
+        } else {
+            vec![EditAction::Move(movement)]
+        }
+    }
+
+    fn control_binding(&mut self, key: &str) -> Option<Vec<EditAction>> {
+        let actions = match key {
+            "a" => self.motion(Movement::PrecedingLineBreak),
+            "e" => self.motion(Movement::NextLineBreak),
+            "f" => self.motion(Movement::Right),
+            "b" => self.motion(Movement::Left),
+            "n" => self.motion(Movement::Down),
+            "p" => self.motion(Movement::Up),
+            "v" => self.motion(Movement::PageDown(self.viewport_height)),
+            "d" => vec![EditAction::Delete],
+            "k" => vec![EditAction::Kill(Movement::NextLineBreak)],
+            "w" => vec![EditAction::KillSelection],
+            "y" => vec![EditAction::Yank],
+            "/" | "_" => vec![EditAction::Undo],
+            "g" => vec![EditAction::CollapseSelection { to_start: false }],
+            _ => return None,
+        };
+        Some(actions)
+    }
+
+    fn meta_binding(&mut self, key: &str) -> Option<Vec<EditAction>> {
+        let actions = match key {
+            "f" => self.motion(Movement::RightWord),
+            "b" => self.motion(Movement::LeftWord),
+            "a" => self.motion(Movement::StartOfSentence),
+            "e" => self.motion(Movement::EndOfSentence),
+            "{" => self.motion(Movement::StartOfParagraph),
+            "}" => self.motion(Movement::EndOfParagraph),
+            "v" => self.motion(Movement::PageUp(self.viewport_height)),
+            "<" => self.motion(Movement::StartOfDocument),
+            ">" => self.motion(Movement::EndOfDocument),
+            "d" => vec![EditAction::Kill(Movement::RightWord)],
+            "k" => vec![EditAction::Kill(Movement::EndOfSentence)],
+            "w" => vec![
+                EditAction::CopyToKillRing,
+                EditAction::CollapseSelection { to_start: false },
+            ],
+            "y" => vec![EditAction::YankPop],
+            _ => return None,
+        };
+        Some(actions)
+    }
+}
+
+/// The key pressed, ignoring the way modifiers change the character.
+///
+/// On macOS the option key produces a different character, so letters are
+/// taken from the physical key when it is known.
+fn key_name(event: &KeyEvent) -> Option<String> {
+    if event.code != Code::Unidentified {
+        let code = event.code.to_string();
+        if let Some(letter) = code.strip_prefix("Key") {
+            return Some(letter.to_lowercase());
+        }
+    }
+    match &event.key {
+        KbKey::Character(c) => Some(c.to_lowercase()),
+        _ => None,
+    }
+}
+
+impl TextInput for EmacsTextInput {
+    fn handle_event(&mut self, event: &KeyEvent) -> Vec<EditAction> {
+        let mods = event.mods;
+        if mods.ctrl() && key_name(event).as_deref() == Some(" ") {
+            self.mark_active = true;
+            return vec![EditAction::CollapseSelection { to_start: false }];
+        }
+        let binding = match key_name(event) {
+            Some(key) if mods.ctrl() && !mods.alt() => self.control_binding(&key),
+            Some(key) if mods.alt() && !mods.ctrl() => self.meta_binding(&key),
+            _ => None,
+        };
+        let actions = binding.unwrap_or_else(|| self.basic.handle_event(event));
+        // any edit deactivates the mark
+        if actions
+            .iter()
+            .any(|action| !matches!(action, EditAction::Move(_) | EditAction::ModifySelection(_)))
+        {
+            self.mark_active = false;
+        }
+        actions
+    }
+
+    fn set_viewport_height(&mut self, height: f64) {
+        self.viewport_height = height;
+        self.basic.set_viewport_height(height);
+    }
+
+    fn set_secondary_cursors(&mut self, secondary: bool) {
+        self.basic.set_secondary_cursors(secondary);
+    }
+
+    fn handle_click(&mut self) {
+        self.mark_active = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::Modifiers;
+
+    fn key(input: &mut EmacsTextInput, mods: Modifiers, key: &str) -> Vec<EditAction> {
+        input.handle_event(&KeyEvent::for_test(mods, key))
+    }
+
+    #[test]
+    fn bindings() {
+        let mut emacs = EmacsTextInput::new();
+        assert_eq!(
+            key(&mut emacs, Modifiers::CONTROL, "a"),
+            vec![EditAction::Move(Movement::PrecedingLineBreak)]
+        );
+        assert_eq!(
+            key(&mut emacs, Modifiers::ALT, "f"),
+            vec![EditAction::Move(Movement::RightWord)]
+        );
+        assert_eq!(
+            key(&mut emacs, Modifiers::CONTROL, "k"),
+            vec![EditAction::Kill(Movement::NextLineBreak)]
+        );
+        assert_eq!(
+            key(&mut emacs, Modifiers::ALT, "y"),
+            vec![EditAction::YankPop]
+        );
+
+        // the option key on macOS changes the character, but not the code
+        let mut event = KeyEvent::for_test(Modifiers::ALT, "ƒ");
+        event.code = Code::KeyF;
+        assert_eq!(
+            emacs.handle_event(&event),
+            vec![EditAction::Move(Movement::RightWord)]
+        );
+    }
+
+    #[test]
+    fn mark() {
+        let mut emacs = EmacsTextInput::new();
+        key(&mut emacs, Modifiers::CONTROL, " ");
+        assert!(emacs.mark_active());
+        assert_eq!(
+            key(&mut emacs, Modifiers::CONTROL, "e"),
+            vec![EditAction::ModifySelection(Movement::NextLineBreak)]
+        );
+        assert_eq!(
+            key(&mut emacs, Modifiers::empty(), "x"),
+            vec![EditAction::Insert("x".into())]
+        );
+        assert!(!emacs.mark_active());
+        assert_eq!(
+            key(&mut emacs, Modifiers::CONTROL, "b"),
+            vec![EditAction::Move(Movement::Left)]
+        );
+
+        // clicking moves the selection, and deactivates the mark
+        key(&mut emacs, Modifiers::CONTROL, " ");
+        emacs.handle_click();
+        assert!(!emacs.mark_active());
+        assert_eq!(
+            key(&mut emacs, Modifiers::CONTROL, "f"),
+            vec![EditAction::Move(Movement::Right)]
+        );
+    }
+}
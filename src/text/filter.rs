@@ -0,0 +1,248 @@
+//! Restricting the text that can be typed or pasted into an editor.
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::format::{Formatter, Validation, ValidationError};
+use super::Selection;
+use unicode_segmentation::UnicodeSegmentation;
+
+type CharFilter = Arc<dyn Fn(char) -> bool>;
+type TextFilter = Arc<dyn Fn(&str) -> bool>;
+type Validator = Arc<dyn Fn(&str, &Selection) -> Validation>;
+
+/// Restrictions on the text that is typed or pasted into an [`Editor`].
+///
+/// Typed text that breaks a restriction is rejected. Pasted text is
+/// truncated, and characters that aren't allowed are left out, so that as
+/// much of it as possible is kept.
+///
+/// [`Editor`]: struct.Editor.html
+#[derive(Clone, Default)]
+pub struct InputFilter {
+    max_length: Option<usize>,
+    char_filter: Option<CharFilter>,
+    text_filter: Option<TextFilter>,
+    validator: Option<Validator>,
+}
+
+/// The result of filtering an insertion.
+#[derive(Debug, Clone)]
+pub(crate) enum Filtered {
+    /// Insert this text, which may be shorter than the original, and then
+    /// set the selection if there is one.
+    Insert(String, Option<Selection>),
+    /// Replace the whole text, as requested by a validator.
+    Replace(String, Selection),
+    /// Reject the insertion, and set the selection if there is one, such
+    /// as the range a validator found invalid.
+    Rejected(Option<Selection>, Option<ValidationError>),
+}
+
+impl InputFilter {
+    /// Create a new `InputFilter` that allows any input.
+    pub fn new() -> Self {
+        InputFilter::default()
+    }
+
+    /// Builder-style method to limit the text to `max_length` grapheme
+    /// clusters.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Builder-style method to only allow characters for which `filter`
+    /// returns `true`.
+    pub fn chars(mut self, filter: impl Fn(char) -> bool + 'static) -> Self {
+        self.char_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Builder-style method to only allow edits for which `filter` returns
+    /// `true` when it is passed the edited text.
+    pub fn text(mut self, filter: impl Fn(&str) -> bool + 'static) -> Self {
+        self.text_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Builder-style method to validate the edited text with
+    /// [`Formatter::validate_partial_input`].
+    ///
+    /// The [`Validation`] may change the text or the selection, even when
+    /// the edit is rejected.
+    ///
+    /// [`Formatter::validate_partial_input`]: format/trait.Formatter.html#tymethod.validate_partial_input
+    /// [`Validation`]: format/struct.Validation.html
+    pub fn formatter<T>(mut self, formatter: impl Formatter<T> + 'static) -> Self {
+        self.validator = Some(Arc::new(move |text: &str, selection: &Selection| {
+            formatter.validate_partial_input(text, selection)
+        }));
+        self
+    }
+
+    /// Returns `true` if this filter allows any input.
+    pub fn is_empty(&self) -> bool {
+        self.max_length.is_none()
+            && self.char_filter.is_none()
+            && self.text_filter.is_none()
+            && self.validator.is_none()
+    }
+
+    /// Filter inserting `inserted` in place of `range` of `text`.
+    ///
+    /// If `truncate` is true, as for a paste, the longest part of `inserted`
+    /// that is allowed is inserted; otherwise it is inserted as a whole or
+    /// not at all.
+    pub(crate) fn apply(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        inserted: &str,
+        truncate: bool,
+    ) -> Filtered {
+        let mut inserted = inserted.to_owned();
+        if let Some(filter) = &self.char_filter {
+            if truncate {
+                inserted.retain(|c| filter(c));
+            } else if !inserted.chars().all(|c| filter(c)) {
+                return Filtered::Rejected(None, None);
+            }
+        }
+
+        if let Some(max_length) = self.max_length {
+            let kept = text.graphemes(true).count() - text[range.clone()].graphemes(true).count();
+            let available = max_length.saturating_sub(kept);
+            if let Some((end, _)) = inserted.grapheme_indices(true).nth(available) {
+                if !truncate {
+                    return Filtered::Rejected(None, None);
+                }
+                inserted.truncate(end);
+            }
+        }
+
+        let edited = |inserted: &str| {
+            let mut edited = String::with_capacity(text.len() + inserted.len());
+            edited.push_str(&text[..range.start]);
+            edited.push_str(inserted);
+            edited.push_str(&text[range.end..]);
+            edited
+        };
+
+        if let Some(filter) = &self.text_filter {
+            while !filter(&edited(&inserted)) {
+                match inserted.grapheme_indices(true).next_back() {
+                    Some((last, _)) if truncate => inserted.truncate(last),
+                    _ => return Filtered::Rejected(None, None),
+                }
+            }
+        }
+        if inserted.is_empty() {
+            return Filtered::Rejected(None, None);
+        }
+
+        if let Some(validator) = &self.validator {
+            let selection = Selection::caret(range.start + inserted.len());
+            let validation = validator(&edited(&inserted), &selection);
+            if let Some(text) = validation.text_change {
+                let selection = validation.selection_change.unwrap_or(selection);
+                return Filtered::Replace(text, selection);
+            }
+            if let Some(error) = validation.error() {
+                let error = error.clone();
+                return Filtered::Rejected(validation.selection_change, Some(error));
+            }
+            return Filtered::Insert(inserted, validation.selection_change);
+        }
+        Filtered::Insert(inserted, None)
+    }
+}
+
+impl PartialEq for Filtered {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Filtered::Insert(a, a_sel), Filtered::Insert(b, b_sel)) => a == b && a_sel == b_sel,
+            (Filtered::Replace(a, a_sel), Filtered::Replace(b, b_sel)) => a == b && a_sel == b_sel,
+            // errors can't be compared, so only their presence is
+            (Filtered::Rejected(a_sel, a), Filtered::Rejected(b_sel, b)) => {
+                a_sel == b_sel && a.is_some() == b.is_some()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for InputFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InputFilter")
+            .field("max_length", &self.max_length)
+            .field("char_filter", &self.char_filter.is_some())
+            .field("text_filter", &self.text_filter.is_some())
+            .field("validator", &self.validator.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::format::InputMask;
+
+    #[test]
+    fn max_length_and_chars() {
+        let filter = InputFilter::new()
+            .max_length(5)
+            .chars(|c| c.is_ascii_alphabetic());
+        assert_eq!(
+            filter.apply("abc", 3..3, "de", false),
+            Filtered::Insert("de".into(), None)
+        );
+        assert_eq!(
+            filter.apply("abc", 3..3, "def", false),
+            Filtered::Rejected(None, None)
+        );
+        assert_eq!(
+            filter.apply("abc", 3..3, "1", false),
+            Filtered::Rejected(None, None)
+        );
+        // a paste is truncated, and leaves out characters that aren't allowed
+        assert_eq!(
+            filter.apply("abc", 3..3, "d1e2f", true),
+            Filtered::Insert("de".into(), None)
+        );
+        // the selected text is replaced, making room for the insertion
+        assert_eq!(
+            filter.apply("abc", 1..3, "defg", true),
+            Filtered::Insert("defg".into(), None)
+        );
+    }
+
+    #[test]
+    fn text_filter_and_mask() {
+        let filter = InputFilter::new().text(|text| text.parse::<u8>().is_ok());
+        assert_eq!(
+            filter.apply("25", 2..2, "5", false),
+            Filtered::Insert("5".into(), None)
+        );
+        assert_eq!(
+            filter.apply("25", 2..2, "6", false),
+            Filtered::Rejected(None, None)
+        );
+        assert_eq!(
+            filter.apply("2", 1..1, "567", true),
+            Filtered::Insert("5".into(), None)
+        );
+
+        let hex = InputFilter::new().formatter(InputMask::Hex);
+        assert_eq!(
+            hex.apply("A", 1..1, "f", false),
+            Filtered::Replace("AF".into(), Selection::caret(2))
+        );
+        let error = ValidationError::new(crate::text::format::InvalidCharacter('g'));
+        assert_eq!(
+            hex.apply("A", 1..1, "g", false),
+            Filtered::Rejected(None, Some(error))
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Fonts that are shipped with an application.
+
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+
+use crate::piet::{FontFamily, PietText, Text as _};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The fonts that are shipped with an app.
+///
+/// Fonts are registered with [`App::register_font`], and the app passes
+/// them to the text layouts it builds.
+///
+/// [`App::register_font`]: crate::app::App::register_font
+#[derive(Default)]
+pub struct Fonts {
+    fonts: Vec<RegisteredFont>,
+}
+
+/// An error from registering font data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FontError {
+    /// The data isn't an OpenType or TrueType font.
+    Invalid,
+    /// The font doesn't have a family name.
+    MissingFamilyName,
+}
+
+/// A font whose data is shipped with the application.
+struct RegisteredFont {
+    /// The family that was returned when the font was registered.
+    family: FontFamily,
+    /// The family returned by the text backend, once the font is loaded.
+    loaded: Option<FontFamily>,
+    data: Option<Vec<u8>>,
+    /// The ranges of the characters that the font has glyphs for, in order.
+    coverage: Vec<RangeInclusive<u32>>,
+}
+
+impl Fonts {
+    /// Register font data, to be loaded by [`load`].
+    ///
+    /// The returned family is named after the font's family name.
+    ///
+    /// [`load`]: Fonts::load
+    pub(crate) fn register(&mut self, data: Vec<u8>) -> Result<FontFamily, FontError> {
+        let face = ttf_parser::Face::parse(&data, 0).map_err(|_| FontError::Invalid)?;
+        let family = family_name(&face).ok_or(FontError::MissingFamilyName)?;
+        let family = FontFamily::new_unchecked(family);
+        let coverage = coverage(&face);
+        self.fonts.push(RegisteredFont {
+            family: family.clone(),
+            loaded: None,
+            data: Some(data),
+            coverage,
+        });
+        Ok(family)
+    }
+
+    /// Load the registered fonts that haven't been loaded yet into `text`.
+    ///
+    /// A font that the backend can't load is logged, and text in its family
+    /// is shown in the backend's default font instead.
+    pub(crate) fn load(&mut self, text: &mut PietText) {
+        for font in &mut self.fonts {
+            let data = match font.data.take() {
+                Some(data) => data,
+                None => continue,
+            };
+            match text.load_font(&data) {
+                Ok(family) => font.loaded = Some(family),
+                Err(err) => log::warn!("failed to load font {:?}: {}", font.family.name(), err),
+            }
+        }
+    }
+
+    /// The family to pass to the text backend for `family`.
+    ///
+    /// This is the family that a registered font was loaded as, which may be
+    /// named differently by the backend.
+    pub(crate) fn resolve(&self, family: &FontFamily) -> FontFamily {
+        self.fonts
+            .iter()
+            .find(|font| &font.family == family)
+            .and_then(|font| font.loaded.clone())
+            .unwrap_or_else(|| family.clone())
+    }
+
+    /// The ranges of `text` that are shown in a family from `fallback`
+    /// because `primary` is missing glyphs for them, with the family for
+    /// each.
+    pub(crate) fn fallback_runs(
+        &self,
+        text: &str,
+        primary: &FontFamily,
+        fallback: &[FontFamily],
+    ) -> Vec<(Range<usize>, FontFamily)> {
+        if fallback.is_empty() {
+            return Vec::new();
+        }
+        let covers = |family: &FontFamily, c: char| {
+            self.fonts
+                .iter()
+                .find(|font| &font.family == family)
+                .map_or(true, |font| font_covers(&font.coverage, c))
+        };
+        fallback_runs_with(text, primary, fallback, covers)
+    }
+
+    /// The families for the `range` of `text` in a font with the given
+    /// fallback families, as runs that cover the whole range.
+    pub(crate) fn family_runs(
+        &self,
+        text: &str,
+        range: Range<usize>,
+        primary: &FontFamily,
+        fallback: &[FontFamily],
+    ) -> Vec<(Range<usize>, FontFamily)> {
+        let fallback_runs = match text.get(range.clone()) {
+            Some(text) => self.fallback_runs(text, primary, fallback),
+            None => Vec::new(),
+        };
+        // ranges of an attribute that are overlapped by a later one may be
+        // reset to the default, so the primary family is given only where no
+        // fallback family is used
+        let mut runs = Vec::new();
+        let mut end = range.start;
+        for (run, family) in fallback_runs {
+            let run = range.start + run.start..range.start + run.end;
+            if run.start > end {
+                runs.push((end..run.start, self.resolve(primary)));
+            }
+            end = run.end;
+            runs.push((run, self.resolve(&family)));
+        }
+        if end < range.end || runs.is_empty() {
+            runs.push((end..range.end, self.resolve(primary)));
+        }
+        runs
+    }
+}
+
+fn fallback_runs_with(
+    text: &str,
+    primary: &FontFamily,
+    fallback: &[FontFamily],
+    covers: impl Fn(&FontFamily, char) -> bool,
+) -> Vec<(Range<usize>, FontFamily)> {
+    let mut runs: Vec<(Range<usize>, FontFamily)> = Vec::new();
+    for (start, grapheme) in text.grapheme_indices(true) {
+        // the whole cluster is shown in one font, so that marks stay with
+        // the character they belong to
+        let has_glyphs = |family: &&FontFamily| grapheme.chars().all(|c| covers(family, c));
+        if has_glyphs(&primary) {
+            continue;
+        }
+        let family = match fallback.iter().find(has_glyphs) {
+            Some(family) => family,
+            None => continue,
+        };
+        let range = start..start + grapheme.len();
+        match runs.last_mut() {
+            Some((last, last_family)) if last.end == start && last_family == family => {
+                last.end = range.end
+            }
+            _ => runs.push((range, family.clone())),
+        }
+    }
+    runs
+}
+
+fn font_covers(coverage: &[RangeInclusive<u32>], c: char) -> bool {
+    let c = c as u32;
+    let index = coverage.partition_point(|range| *range.end() < c);
+    coverage.get(index).is_some_and(|range| range.contains(&c))
+}
+
+fn family_name(face: &ttf_parser::Face) -> Option<String> {
+    let name = |id| {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == id && name.is_unicode())
+            .find_map(|name| name.to_string())
+    };
+    name(ttf_parser::name_id::FAMILY).or_else(|| name(ttf_parser::name_id::TYPOGRAPHIC_FAMILY))
+}
+
+/// The ranges of the characters that `face` has glyphs for, in order.
+fn coverage(face: &ttf_parser::Face) -> Vec<RangeInclusive<u32>> {
+    let mut chars = Vec::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables {
+            if subtable.is_unicode() {
+                subtable.codepoints(|c| {
+                    if subtable.glyph_index(c).is_some_and(|glyph| glyph.0 != 0) {
+                        chars.push(c)
+                    }
+                });
+            }
+        }
+    }
+    to_ranges(chars)
+}
+
+fn to_ranges(mut chars: Vec<u32>) -> Vec<RangeInclusive<u32>> {
+    chars.sort_unstable();
+    chars.dedup();
+    let mut ranges: Vec<RangeInclusive<u32>> = Vec::new();
+    for c in chars {
+        match ranges.last_mut() {
+            Some(last) if *last.end() + 1 == c => *last = *last.start()..=c,
+            _ => ranges.push(c..=c),
+        }
+    }
+    ranges
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Invalid => write!(f, "not a valid font"),
+            FontError::MissingFamilyName => write!(f, "the font has no family name"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coverage_ranges() {
+        let ranges = to_ranges(vec![0x63, 0x61, 0x62, 0xe000, 0x62, 0xe002]);
+        assert_eq!(ranges, vec![0x61..=0x63, 0xe000..=0xe000, 0xe002..=0xe002]);
+        assert!(font_covers(&ranges, 'b'));
+        assert!(!font_covers(&ranges, 'd'));
+        assert!(font_covers(&ranges, '\u{e000}'));
+        assert!(!font_covers(&ranges, '\u{e001}'));
+    }
+
+    #[test]
+    fn fallback() {
+        let corporate = FontFamily::new_unchecked("Corporate");
+        let icons = FontFamily::new_unchecked("Icons");
+        let covers = |family: &FontFamily, c: char| match family.name() {
+            "Corporate" => c.is_ascii(),
+            "Icons" => ('\u{e000}'..='\u{f8ff}').contains(&c),
+            _ => true,
+        };
+        let fallback = [icons.clone(), FontFamily::SYSTEM_UI];
+        let text = "ok \u{e001}\u{e002} caf\u{e9}";
+        let runs = fallback_runs_with(text, &corporate, &fallback, covers);
+        assert_eq!(
+            runs,
+            vec![(3..9, icons.clone()), (13..15, FontFamily::SYSTEM_UI)]
+        );
+
+        // a cluster stays in one font
+        let text = "e\u{301}";
+        let runs = fallback_runs_with(text, &corporate, &fallback, covers);
+        assert_eq!(runs, vec![(0..3, FontFamily::SYSTEM_UI)]);
+        assert!(fallback_runs_with("abc", &corporate, &fallback, covers).is_empty());
+    }
+}
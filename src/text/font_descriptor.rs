@@ -14,12 +14,29 @@
 
 //! Font attributes
 
-use druid::{Data, FontFamily, FontStyle, FontWeight};
+use druid::{Data, Env, FontFamily, FontStyle, FontWeight, Key, KeyOrValue, Value};
+
+/// The key of [`druid::theme::UI_FONT`], for the [`FontDescriptor`] of this
+/// crate.
+///
+/// [`druid::theme::UI_FONT`]: druid::theme::UI_FONT
+pub(crate) const UI_FONT: Key<FontDescriptor> = Key::new("org.linebender.druid.theme.ui-font");
 
 /// A collection of attributes that describe a font.
 ///
 /// This is provided as a convenience; library consumers may wish to have
 /// a single type that represents a specific font face at a specific size.
+///
+/// Characters that the font has no glyphs for are shown in the first of the
+/// [`fallback`] families that has them. Only fonts registered with
+/// [`App::register_font`] are known to be missing glyphs; any other family
+/// in the list is assumed to have all of them.
+///
+/// A descriptor stored in the [`Env`] loses its fallback families.
+///
+/// [`fallback`]: #structfield.fallback
+/// [`App::register_font`]: crate::app::App::register_font
+/// [`Env`]: druid::Env
 #[derive(Debug, Data, Clone, PartialEq)]
 pub struct FontDescriptor {
     /// The font's [`FontFamily`](struct.FontFamily.html).
@@ -30,6 +47,10 @@ pub struct FontDescriptor {
     pub weight: FontWeight,
     /// The font's [`FontStyle`](struct.FontStyle.html).
     pub style: FontStyle,
+    /// The families to use, in order, for characters that are missing from
+    /// the font.
+    #[data(same_fn = "PartialEq::eq")]
+    pub fallback: Vec<FontFamily>,
 }
 
 impl FontDescriptor {
@@ -42,6 +63,7 @@ impl FontDescriptor {
             size: crate::piet::util::DEFAULT_FONT_SIZE,
             weight: FontWeight::REGULAR,
             style: FontStyle::Regular,
+            fallback: Vec::new(),
         }
     }
 
@@ -66,6 +88,19 @@ impl FontDescriptor {
         self.style = style;
         self
     }
+
+    /// Builder-style method to set the families to use, in order, for
+    /// characters that are missing from the font.
+    ///
+    /// The fallback families are not kept if the descriptor is stored in the
+    /// [`Env`], so a font with fallback families should be given to widgets
+    /// directly rather than by key.
+    ///
+    /// [`Env`]: druid::Env
+    pub fn with_fallback(mut self, fallback: impl IntoIterator<Item = FontFamily>) -> Self {
+        self.fallback = fallback.into_iter().collect();
+        self
+    }
 }
 
 impl Default for FontDescriptor {
@@ -75,6 +110,60 @@ impl Default for FontDescriptor {
             weight: Default::default(),
             style: Default::default(),
             size: crate::piet::util::DEFAULT_FONT_SIZE,
+            fallback: Vec::new(),
+        }
+    }
+}
+
+impl From<druid::FontDescriptor> for FontDescriptor {
+    fn from(font: druid::FontDescriptor) -> Self {
+        FontDescriptor {
+            family: font.family,
+            size: font.size,
+            weight: font.weight,
+            style: font.style,
+            fallback: Vec::new(),
+        }
+    }
+}
+
+impl From<FontDescriptor> for druid::FontDescriptor {
+    fn from(font: FontDescriptor) -> Self {
+        druid::FontDescriptor::new(font.family)
+            .with_size(font.size)
+            .with_weight(font.weight)
+            .with_style(font.style)
+    }
+}
+
+/// The [`Env`] stores druid's font descriptors, which have no fallback
+/// families, so those of `font` are dropped.
+///
+/// [`Env`]: druid::Env
+impl From<FontDescriptor> for Value {
+    fn from(font: FontDescriptor) -> Self {
+        if !font.fallback.is_empty() {
+            log::warn!(
+                "the fallback families of {:?} are not kept in the env",
+                font.family
+            );
         }
+        Value::Font(font.into())
+    }
+}
+
+/// Resolve a font from the [`Env`].
+///
+/// The [`Env`] stores druid's font descriptors, so a key is looked up as one
+/// of those, without fallback families.
+///
+/// [`Env`]: druid::Env
+pub(crate) fn resolve(font: &KeyOrValue<FontDescriptor>, env: &Env) -> FontDescriptor {
+    match font {
+        KeyOrValue::Concrete(font) => font.clone(),
+        KeyOrValue::Key(key) => match env.get_untyped(key) {
+            Value::Font(font) => font.clone().into(),
+            other => panic!("expected a font for {:?}, found {:?}", key, other),
+        },
     }
 }
@@ -0,0 +1,346 @@
+//! Formatters for ISO 8601 dates and times.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use super::{FormatError, Formatter, MaskFormatter, Selection, Validation, ValidationError};
+
+/// A [`Formatter`] for dates in the ISO 8601 format `YYYY-MM-DD`.
+///
+/// The dashes are filled in as the user types, and digits that can't start
+/// a valid month or day are rejected.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormatter {
+    fields: DateFields,
+}
+
+/// A [`Formatter`] for times in the ISO 8601 format `HH:MM`, or `HH:MM:SS`
+/// with seconds.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeFormatter {
+    fields: DateFields,
+}
+
+/// A [`Formatter`] for dates and times in the ISO 8601 format
+/// `YYYY-MM-DDTHH:MM`, or `YYYY-MM-DDTHH:MM:SS` with seconds.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateTimeFormatter {
+    fields: DateFields,
+}
+
+/// A number with a fixed number of digits in a date or time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field {
+    start: usize,
+    width: usize,
+    min: u32,
+    max: u32,
+}
+
+const YEAR: Field = Field::new(0, 4, 0, 9999);
+const MONTH: Field = Field::new(5, 2, 1, 12);
+const DAY: Field = Field::new(8, 2, 1, 31);
+const HOUR: Field = Field::new(0, 2, 0, 23);
+const MINUTE: Field = Field::new(3, 2, 0, 59);
+const SECOND: Field = Field::new(6, 2, 0, 59);
+/// The offset of the time after a date and the `T` separator.
+const TIME_OFFSET: usize = 11;
+
+/// The fields of a date or time format, and a mask that fills in the
+/// separators between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DateFields {
+    mask: MaskFormatter,
+    fields: Vec<Field>,
+}
+
+impl Field {
+    const fn new(start: usize, width: usize, min: u32, max: u32) -> Self {
+        Field {
+            start,
+            width,
+            min,
+            max,
+        }
+    }
+
+    const fn after_date(self) -> Self {
+        Field::new(self.start + TIME_OFFSET, self.width, self.min, self.max)
+    }
+
+    /// Returns `true` if the digits typed so far in `text` can start a
+    /// value of this field.
+    fn allows(self, text: &str) -> bool {
+        let end = text.len().min(self.start + self.width);
+        let digits = match text.get(self.start..end) {
+            Some(digits) if !digits.is_empty() => digits,
+            _ => return true,
+        };
+        let scale = 10u32.pow((self.width - digits.len()) as u32);
+        let lowest = digits.parse::<u32>().unwrap_or(0) * scale;
+        lowest <= self.max && lowest + scale > self.min
+    }
+
+    fn value(self, text: &str) -> u32 {
+        text[self.start..self.start + self.width]
+            .parse()
+            .unwrap_or(0)
+    }
+}
+
+impl DateFields {
+    fn new(pattern: &str, fields: Vec<Field>) -> Self {
+        DateFields {
+            mask: MaskFormatter::new(pattern),
+            fields,
+        }
+    }
+
+    /// Validate `input`, and if it is complete check it with `is_valid`.
+    fn validate(
+        &self,
+        input: &str,
+        sel: &Selection,
+        is_valid: impl Fn(&str) -> bool,
+    ) -> Validation {
+        let validation = self.mask.validate_partial_input(input, sel);
+        if validation.is_err() {
+            return validation;
+        }
+        let text = validation.text_change.as_deref().unwrap_or(input);
+        if !self.fields.iter().all(|field| field.allows(text)) {
+            return Validation::failure(FormatError::Invalid);
+        }
+        if text.len() == self.mask.len() && !is_valid(text) {
+            return Validation::failure(FormatError::Invalid);
+        }
+        validation
+    }
+
+    /// The value of each field of the complete `input`.
+    fn values(&self, input: &str) -> Result<Vec<u32>, ValidationError> {
+        let text = self.mask.value(input)?;
+        Ok(self.fields.iter().map(|field| field.value(&text)).collect())
+    }
+}
+
+fn date_from(values: &[u32]) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(values[0] as i32, values[1], values[2])
+}
+
+fn time_from(values: &[u32]) -> Option<NaiveTime> {
+    NaiveTime::from_hms_opt(values[0], values[1], values.get(2).copied().unwrap_or(0))
+}
+
+fn format_date(date: &NaiveDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+fn format_time(time: &NaiveTime, seconds: bool) -> String {
+    if seconds {
+        format!(
+            "{:02}:{:02}:{:02}",
+            time.hour(),
+            time.minute(),
+            time.second()
+        )
+    } else {
+        format!("{:02}:{:02}", time.hour(), time.minute())
+    }
+}
+
+fn invalid() -> ValidationError {
+    ValidationError::new(FormatError::Invalid)
+}
+
+impl DateFormatter {
+    /// Create a new `DateFormatter`.
+    pub fn new() -> Self {
+        DateFormatter {
+            fields: DateFields::new("####-##-##", vec![YEAR, MONTH, DAY]),
+        }
+    }
+}
+
+impl TimeFormatter {
+    /// Create a new `TimeFormatter` for hours and minutes.
+    pub fn new() -> Self {
+        TimeFormatter {
+            fields: DateFields::new("##:##", vec![HOUR, MINUTE]),
+        }
+    }
+
+    /// Builder-style method to include seconds.
+    pub fn with_seconds(self) -> Self {
+        TimeFormatter {
+            fields: DateFields::new("##:##:##", vec![HOUR, MINUTE, SECOND]),
+        }
+    }
+
+    fn has_seconds(&self) -> bool {
+        self.fields.fields.len() == 3
+    }
+}
+
+impl DateTimeFormatter {
+    /// Create a new `DateTimeFormatter` for dates, hours and minutes.
+    pub fn new() -> Self {
+        let fields = vec![YEAR, MONTH, DAY, HOUR.after_date(), MINUTE.after_date()];
+        DateTimeFormatter {
+            fields: DateFields::new("####-##-##T##:##", fields),
+        }
+    }
+
+    /// Builder-style method to include seconds.
+    pub fn with_seconds(mut self) -> Self {
+        self.fields = DateFields::new("####-##-##T##:##:##", self.fields.fields);
+        self.fields.fields.push(SECOND.after_date());
+        self
+    }
+
+    fn has_seconds(&self) -> bool {
+        self.fields.fields.len() == 6
+    }
+}
+
+impl Formatter<NaiveDate> for DateFormatter {
+    fn format(&self, value: &NaiveDate) -> String {
+        format_date(value)
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        self.fields.validate(input, sel, |text| {
+            self.fields
+                .values(text)
+                .is_ok_and(|values| date_from(&values).is_some())
+        })
+    }
+
+    fn value(&self, input: &str) -> Result<NaiveDate, ValidationError> {
+        date_from(&self.fields.values(input)?).ok_or_else(invalid)
+    }
+}
+
+impl Formatter<NaiveTime> for TimeFormatter {
+    fn format(&self, value: &NaiveTime) -> String {
+        format_time(value, self.has_seconds())
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        // each field is checked against its range while typing, so a
+        // complete time is always valid
+        self.fields.validate(input, sel, |_| true)
+    }
+
+    fn value(&self, input: &str) -> Result<NaiveTime, ValidationError> {
+        time_from(&self.fields.values(input)?).ok_or_else(invalid)
+    }
+}
+
+impl Formatter<NaiveDateTime> for DateTimeFormatter {
+    fn format(&self, value: &NaiveDateTime) -> String {
+        let date = format_date(&value.date());
+        format!(
+            "{}T{}",
+            date,
+            format_time(&value.time(), self.has_seconds())
+        )
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        self.fields.validate(input, sel, |text| {
+            self.fields
+                .values(text)
+                .is_ok_and(|values| date_from(&values).is_some())
+        })
+    }
+
+    fn value(&self, input: &str) -> Result<NaiveDateTime, ValidationError> {
+        let values = self.fields.values(input)?;
+        let date = date_from(&values).ok_or_else(invalid)?;
+        let time = time_from(&values[3..]).ok_or_else(invalid)?;
+        Ok(date.and_time(time))
+    }
+}
+
+impl Default for DateFormatter {
+    fn default() -> Self {
+        DateFormatter::new()
+    }
+}
+
+impl Default for TimeFormatter {
+    fn default() -> Self {
+        TimeFormatter::new()
+    }
+}
+
+impl Default for DateTimeFormatter {
+    fn default() -> Self {
+        DateTimeFormatter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accepts<T>(formatter: &impl Formatter<T>, input: &str) -> bool {
+        !formatter
+            .validate_partial_input(input, &Selection::caret(input.len()))
+            .is_err()
+    }
+
+    #[test]
+    fn dates() {
+        let formatter = DateFormatter::new();
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(formatter.format(&date), "2024-02-29");
+        assert_eq!(formatter.value("2024-02-29").unwrap(), date);
+
+        let validation = formatter.validate_partial_input("20240", &Selection::caret(5));
+        assert_eq!(validation.text_change.as_deref(), Some("2024-0"));
+        assert_eq!(validation.selection_change, Some(Selection::caret(6)));
+        assert!(accepts(&formatter, "2024-1"));
+        assert!(!accepts(&formatter, "2024-2"));
+        assert!(!accepts(&formatter, "2024-13"));
+        assert!(!accepts(&formatter, "2024-00"));
+        assert!(accepts(&formatter, "2024-02-3"));
+        assert!(!accepts(&formatter, "2024-02-4"));
+        assert!(!accepts(&formatter, "2023-02-29"));
+        assert!(!accepts(&formatter, "2024-02-291"));
+        assert!(formatter.value("2024-02").is_err());
+    }
+
+    #[test]
+    fn times() {
+        let formatter = TimeFormatter::new();
+        let time = NaiveTime::from_hms_opt(9, 5, 0).unwrap();
+        assert_eq!(formatter.format(&time), "09:05");
+        assert_eq!(formatter.value("0905").unwrap(), time);
+        assert!(accepts(&formatter, "2"));
+        assert!(!accepts(&formatter, "3"));
+        assert!(!accepts(&formatter, "24"));
+        assert!(!accepts(&formatter, "23:6"));
+
+        let seconds = TimeFormatter::new().with_seconds();
+        assert_eq!(seconds.format(&time), "09:05:00");
+        assert!(!accepts(&seconds, "23:59:60"));
+
+        let formatter = DateTimeFormatter::new().with_seconds();
+        let value = NaiveDate::from_ymd_opt(2021, 12, 31)
+            .unwrap()
+            .and_hms_opt(23, 59, 1)
+            .unwrap();
+        assert_eq!(formatter.format(&value), "2021-12-31T23:59:01");
+        assert_eq!(formatter.value("2021-12-31T23:59:01").unwrap(), value);
+        let validation = formatter.validate_partial_input("2021-12-311", &Selection::caret(11));
+        assert_eq!(validation.text_change.as_deref(), Some("2021-12-31T1"));
+        assert!(!accepts(&formatter, "2021-12-31T24"));
+    }
+}
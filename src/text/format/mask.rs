@@ -0,0 +1,193 @@
+//! A formatter for text that follows a fixed pattern.
+
+use super::{FormatError, Formatter, InvalidCharacter, Selection, Validation, ValidationError};
+
+/// A [`Formatter`] for text that follows a pattern, such as a phone number
+/// like `"###-###-####"`.
+///
+/// In the pattern, `#` stands for a digit, `A` for a letter and `*` for a
+/// digit or a letter; any other character, or a character escaped with `\`,
+/// stands for itself. These literal characters are filled in as the user
+/// types, so typing `5551234` gives `555-123-4`.
+///
+/// The value is the text including the literal characters, and is only
+/// valid once the whole pattern is filled in.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskFormatter {
+    pattern: String,
+    slots: Vec<Slot>,
+}
+
+/// A position in the pattern of a [`MaskFormatter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Digit,
+    Letter,
+    Alphanumeric,
+    Literal(char),
+}
+
+impl MaskFormatter {
+    /// Create a new `MaskFormatter` for `pattern`.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let pattern = pattern.into();
+        let mut slots = Vec::with_capacity(pattern.len());
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            slots.push(match c {
+                '#' => Slot::Digit,
+                'A' => Slot::Letter,
+                '*' => Slot::Alphanumeric,
+                '\\' => Slot::Literal(chars.next().unwrap_or('\\')),
+                c => Slot::Literal(c),
+            });
+        }
+        MaskFormatter { pattern, slots }
+    }
+
+    /// The pattern this formatter was created with.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The number of characters in text that fills in the whole pattern.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if the pattern is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Fit the characters of `input` into the pattern, returning the fitted
+    /// text and the offset in it that matches `caret` in `input`.
+    ///
+    /// Literal characters in `input` are kept where the pattern has them and
+    /// skipped elsewhere, so that deleting a character moves the characters
+    /// after it along the pattern.
+    fn fit(&self, input: &str, caret: usize) -> Result<(String, usize), ValidationError> {
+        let mut fitted = String::with_capacity(input.len());
+        let mut fitted_caret = None;
+        let mut chars = input.char_indices().peekable();
+        let mut slots = self.slots.iter();
+        'fill: while chars.peek().is_some() {
+            let slot = match slots.next() {
+                Some(slot) => *slot,
+                None => return Err(ValidationError::new(FormatError::TooLong)),
+            };
+            if let Slot::Literal(literal) = slot {
+                if let Some(&(i, c)) = chars.peek() {
+                    if c == literal {
+                        if fitted_caret.is_none() && i >= caret {
+                            fitted_caret = Some(fitted.len());
+                        }
+                        chars.next();
+                    }
+                }
+                fitted.push(literal);
+                continue;
+            }
+            loop {
+                let (i, c) = match chars.next() {
+                    Some(next) => next,
+                    None => break 'fill,
+                };
+                if fitted_caret.is_none() && i >= caret {
+                    fitted_caret = Some(fitted.len());
+                }
+                if slot.accepts(c) {
+                    fitted.push(c);
+                    break;
+                }
+                if !self.slots.contains(&Slot::Literal(c)) {
+                    return Err(ValidationError::new(InvalidCharacter(c)));
+                }
+            }
+        }
+        let fitted_caret = fitted_caret.unwrap_or(fitted.len());
+        Ok((fitted, fitted_caret))
+    }
+}
+
+impl Slot {
+    fn accepts(self, c: char) -> bool {
+        match self {
+            Slot::Digit => c.is_ascii_digit(),
+            Slot::Letter => c.is_alphabetic(),
+            Slot::Alphanumeric => c.is_alphanumeric(),
+            Slot::Literal(literal) => c == literal,
+        }
+    }
+}
+
+impl Formatter<String> for MaskFormatter {
+    fn format(&self, value: &String) -> String {
+        value.clone()
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        match self.fit(input, sel.end) {
+            Ok((fitted, _)) if fitted == input => Validation::success(),
+            Ok((fitted, caret)) => Validation::success()
+                .change_text(fitted)
+                .change_selection(Selection::caret(caret)),
+            Err(error) => Validation::from_error(error),
+        }
+    }
+
+    fn value(&self, input: &str) -> Result<String, ValidationError> {
+        let (fitted, _) = self.fit(input, input.len())?;
+        if fitted.chars().count() < self.slots.len() {
+            return Err(ValidationError::new(FormatError::Incomplete));
+        }
+        Ok(fitted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(mask: &MaskFormatter, input: &str) -> (Option<String>, Option<Selection>) {
+        let validation = mask.validate_partial_input(input, &Selection::caret(input.len()));
+        assert!(!validation.is_err(), "{:?} was rejected", input);
+        (validation.text_change, validation.selection_change)
+    }
+
+    #[test]
+    fn fills_literals() {
+        let phone = MaskFormatter::new("(###) ###-####");
+        assert_eq!(validate(&phone, "(555"), (None, None));
+        assert_eq!(
+            validate(&phone, "5551"),
+            (Some("(555) 1".into()), Some(Selection::caret(7)))
+        );
+        assert_eq!(validate(&phone, "(555) 123-"), (None, None));
+        assert!(phone
+            .validate_partial_input("(555) 12a", &Selection::caret(9))
+            .is_err());
+        assert!(phone
+            .validate_partial_input("(555) 123-45678", &Selection::caret(15))
+            .is_err());
+
+        // deleting a digit moves the following ones back along the pattern
+        let validation = phone.validate_partial_input("(555) 13-4567", &Selection::caret(7));
+        assert_eq!(validation.text_change.as_deref(), Some("(555) 134-567"));
+        assert_eq!(validation.selection_change, Some(Selection::caret(7)));
+
+        assert_eq!(phone.value("5551234567").unwrap(), "(555) 123-4567");
+        assert!(phone.value("(555) 123").is_err());
+
+        let plate = MaskFormatter::new("AA\\-**");
+        assert_eq!(
+            validate(&plate, "ab1"),
+            (Some("ab-1".into()), Some(Selection::caret(4)))
+        );
+        assert!(plate
+            .validate_partial_input("a1", &Selection::caret(2))
+            .is_err());
+    }
+}
@@ -20,6 +20,16 @@ use std::sync::Arc;
 use super::Selection;
 use druid::Data;
 
+mod date;
+mod mask;
+mod number;
+
+pub use date::{DateFormatter, DateTimeFormatter, TimeFormatter};
+pub use mask::MaskFormatter;
+pub use number::{
+    CurrencyFormatter, FloatFormatter, IntegerFormatter, PercentFormatter, SymbolPlacement,
+};
+
 /// A trait for types that create, interpret, and validate textual representations
 /// of values.
 ///
@@ -118,6 +128,50 @@ pub struct ParseFormatter<T> {
     fmt_fn: Box<dyn Fn(&T) -> String>,
 }
 
+/// A [`Formatter`] for strings that may only contain certain characters,
+/// such as those typed into a text box with an [`InputFilter`].
+///
+/// [`Formatter`]: Formatter
+/// [`InputFilter`]: super::InputFilter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMask {
+    /// Only the ASCII digits `0` to `9`.
+    Digits,
+    /// Hexadecimal digits, which are converted to upper case.
+    Hex,
+    /// Digits, spaces, dashes, dots and parentheses, with an optional
+    /// leading `+`.
+    PhoneNumber,
+}
+
+/// The error for a string that doesn't match an [`InputMask`].
+///
+/// [`InputMask`]: InputMask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidCharacter(pub char);
+
+/// The reasons the built-in formatters reject input, other than an
+/// [`InvalidCharacter`].
+///
+/// [`InvalidCharacter`]: InvalidCharacter
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FormatError {
+    /// The input is only the start of a value, such as a date without its
+    /// day.
+    Incomplete,
+    /// The input is longer than the value can be.
+    TooLong,
+    /// The value is less than this minimum.
+    TooSmall(String),
+    /// The value is greater than this maximum.
+    TooLarge(String),
+    /// The value has more than this many decimal places.
+    TooPrecise(usize),
+    /// The input is not a valid value, such as the 30th of February.
+    Invalid,
+}
+
 impl Validation {
     /// Create a `Validation` indicating succes.
     pub fn success() -> Self {
@@ -136,6 +190,14 @@ impl Validation {
         }
     }
 
+    /// Create a `Validation` with an error that was already wrapped.
+    fn from_error(err: ValidationError) -> Self {
+        Validation {
+            result: Err(err),
+            ..Validation::success()
+        }
+    }
+
     /// Optionally set a `String` that will replace the current contents.
     pub fn change_text(mut self, text: String) -> Self {
         self.text_change = Some(text);
@@ -208,6 +270,114 @@ where
     }
 }
 
+impl<T, F: Formatter<T> + ?Sized> Formatter<T> for Arc<F> {
+    fn format(&self, value: &T) -> String {
+        (**self).format(value)
+    }
+
+    fn format_for_editing(&self, value: &T) -> String {
+        (**self).format_for_editing(value)
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        (**self).validate_partial_input(input, sel)
+    }
+
+    fn value(&self, input: &str) -> Result<T, ValidationError> {
+        (**self).value(input)
+    }
+}
+
+impl InputMask {
+    /// Returns the first character of `input` that doesn't match this mask.
+    fn invalid_char(self, input: &str) -> Option<char> {
+        input.char_indices().find_map(|(i, c)| {
+            let valid = match self {
+                InputMask::Digits => c.is_ascii_digit(),
+                InputMask::Hex => c.is_ascii_hexdigit(),
+                InputMask::PhoneNumber => {
+                    c.is_ascii_digit() || " -.()".contains(c) || (c == '+' && i == 0)
+                }
+            };
+            if valid {
+                None
+            } else {
+                Some(c)
+            }
+        })
+    }
+}
+
+impl Formatter<String> for InputMask {
+    fn format(&self, value: &String) -> String {
+        value.clone()
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        if let Some(c) = self.invalid_char(input) {
+            return Validation::failure(InvalidCharacter(c));
+        }
+        match self {
+            InputMask::Hex if input.chars().any(|c| c.is_ascii_lowercase()) => {
+                Validation::success()
+                    .change_text(input.to_ascii_uppercase())
+                    .change_selection(*sel)
+            }
+            _ => Validation::success(),
+        }
+    }
+
+    fn value(&self, input: &str) -> Result<String, ValidationError> {
+        match self.invalid_char(input) {
+            Some(c) => Err(ValidationError::new(InvalidCharacter(c))),
+            None if *self == InputMask::Hex => Ok(input.to_ascii_uppercase()),
+            None => Ok(input.to_owned()),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidCharacter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid character '{}'", self.0)
+    }
+}
+
+impl std::error::Error for InvalidCharacter {}
+
+impl std::fmt::Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FormatError::Incomplete => write!(f, "incomplete value"),
+            FormatError::TooLong => write!(f, "too long"),
+            FormatError::TooSmall(min) => write!(f, "must be at least {}", min),
+            FormatError::TooLarge(max) => write!(f, "must be at most {}", max),
+            FormatError::TooPrecise(1) => write!(f, "must have at most 1 decimal place"),
+            FormatError::TooPrecise(places) => {
+                write!(f, "must have at most {} decimal places", places)
+            }
+            FormatError::Invalid => write!(f, "invalid value"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Returns the offset in `output` matching the `caret` offset in `input`,
+/// where the characters for which `significant` returns `true` are the same
+/// in both, and any others may have been added or removed.
+fn map_caret(input: &str, output: &str, caret: usize, significant: impl Fn(char) -> bool) -> usize {
+    let before = input.get(..caret).unwrap_or(input);
+    let count = before.chars().filter(|c| significant(*c)).count();
+    if count == 0 {
+        return 0;
+    }
+    output
+        .char_indices()
+        .filter(|(_, c)| significant(*c))
+        .nth(count - 1)
+        .map_or(output.len(), |(i, c)| i + c.len_utf8())
+}
+
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", &self.inner)
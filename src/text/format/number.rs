@@ -0,0 +1,604 @@
+//! Formatters for numbers.
+
+use super::{
+    map_caret, FormatError, Formatter, InvalidCharacter, Selection, Validation, ValidationError,
+};
+
+/// A [`Formatter`] for integers, with optional bounds and a thousands
+/// separator.
+///
+/// While the user types, the separators are moved to group the digits in
+/// threes, and input that can't become a number within the bounds is
+/// rejected: a minus sign is only allowed if the minimum is negative.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerFormatter {
+    min: i64,
+    max: i64,
+    separator: Option<char>,
+}
+
+/// A [`Formatter`] for floating point numbers, with optional bounds, a
+/// maximum number of decimal places and a thousands separator.
+///
+/// The decimal separator is always `.`. Input such as `"-"`, `"1."` or
+/// `".5"` is accepted while typing.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatFormatter {
+    min: f64,
+    max: f64,
+    precision: Option<usize>,
+    separator: Option<char>,
+}
+
+/// Where a [`CurrencyFormatter`] puts the currency symbol.
+///
+/// [`CurrencyFormatter`]: CurrencyFormatter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolPlacement {
+    /// Before the number, as in `$1.00`.
+    Before,
+    /// After the number and a space, as in `1.00 €`.
+    After,
+}
+
+/// A [`Formatter`] for amounts of money, shown with a currency symbol.
+///
+/// Amounts have two decimal places and a `,` thousands separator by default.
+/// The symbol is left out while editing, and removed if the user types it.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencyFormatter {
+    symbol: String,
+    placement: SymbolPlacement,
+    number: FloatFormatter,
+}
+
+/// A [`Formatter`] for fractions shown as percentages, so that the value
+/// `0.25` is shown as `25%`.
+///
+/// The percent sign is left out while editing, and removed if the user
+/// types it.
+///
+/// [`Formatter`]: Formatter
+#[derive(Debug, Clone, PartialEq)]
+pub struct PercentFormatter {
+    /// The formatter for the percentage, rather than the fraction.
+    number: FloatFormatter,
+}
+
+/// The parts of a number, which may still be being typed.
+struct NumberText {
+    negative: bool,
+    /// The digits before the decimal point.
+    int: String,
+    /// The digits after the decimal point, if there is one.
+    frac: Option<String>,
+}
+
+impl NumberText {
+    /// Split `input` into its parts, ignoring thousands separators.
+    fn parse(
+        input: &str,
+        separator: Option<char>,
+        decimal: bool,
+    ) -> Result<Self, InvalidCharacter> {
+        let mut text = NumberText {
+            negative: false,
+            int: String::new(),
+            frac: None,
+        };
+        for (i, c) in input.char_indices() {
+            match c {
+                '-' if i == 0 => text.negative = true,
+                '0'..='9' => match &mut text.frac {
+                    Some(frac) => frac.push(c),
+                    None => text.int.push(c),
+                },
+                '.' if decimal && text.frac.is_none() => text.frac = Some(String::new()),
+                c if Some(c) == separator && text.frac.is_none() => (),
+                c => return Err(InvalidCharacter(c)),
+            }
+        }
+        Ok(text)
+    }
+
+    /// Returns `true` if any digits have been typed.
+    fn has_digits(&self) -> bool {
+        !self.int.is_empty() || self.frac.as_ref().is_some_and(|frac| !frac.is_empty())
+    }
+
+    /// The number without thousands separators, which can be parsed by
+    /// `str::parse`.
+    fn plain(&self) -> String {
+        self.with_separator(None)
+    }
+
+    /// The number with the digits before the decimal point grouped in
+    /// threes by `separator`.
+    fn with_separator(&self, separator: Option<char>) -> String {
+        let mut text = String::with_capacity(self.int.len() * 4 / 3 + 2);
+        if self.negative {
+            text.push('-');
+        }
+        for (i, c) in self.int.chars().enumerate() {
+            if let Some(separator) = separator {
+                if i > 0 && (self.int.len() - i) % 3 == 0 {
+                    text.push(separator);
+                }
+            }
+            text.push(c);
+        }
+        if let Some(frac) = &self.frac {
+            text.push('.');
+            text.push_str(frac);
+        }
+        text
+    }
+}
+
+/// Accept `input`, changing it to `output` if they differ, and keeping the
+/// caret next to the same digit.
+fn regroup(input: &str, output: String, sel: &Selection) -> Validation {
+    if output == input {
+        return Validation::success();
+    }
+    let is_number_char = |c: char| c.is_ascii_digit() || c == '-' || c == '.';
+    let caret = map_caret(input, &output, sel.end, is_number_char);
+    Validation::success()
+        .change_text(output)
+        .change_selection(Selection::caret(caret))
+}
+
+/// Validate `input` with `number`, after removing a symbol such as a
+/// currency symbol from it, in which case the text is changed to the
+/// number alone.
+fn validate_without(
+    number: &FloatFormatter,
+    symbol: &str,
+    input: &str,
+    sel: &Selection,
+) -> Validation {
+    if symbol.is_empty() || !input.contains(symbol) {
+        return number.validate_partial_input(input, sel);
+    }
+    let stripped = input.replace(symbol, "").trim().to_owned();
+    let caret = map_caret(input, &stripped, sel.end, |c| !c.is_whitespace());
+    let validation = number.validate_partial_input(&stripped, &Selection::caret(caret));
+    if validation.is_err() || validation.text_change.is_some() {
+        return validation;
+    }
+    validation
+        .change_text(stripped)
+        .change_selection(Selection::caret(caret))
+}
+
+impl IntegerFormatter {
+    /// Create a new `IntegerFormatter` that accepts any `i64`, without a
+    /// thousands separator.
+    pub fn new() -> Self {
+        IntegerFormatter {
+            min: i64::MIN,
+            max: i64::MAX,
+            separator: None,
+        }
+    }
+
+    /// Builder-style method to set the smallest accepted value.
+    pub fn min(mut self, min: i64) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Builder-style method to set the largest accepted value.
+    pub fn max(mut self, max: i64) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Builder-style method to group thousands with `separator`, such as
+    /// `','`.
+    pub fn thousands_separator(mut self, separator: impl Into<Option<char>>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    fn check_bounds(&self, value: i64) -> Result<i64, FormatError> {
+        if value < self.min {
+            Err(FormatError::TooSmall(self.format(&self.min)))
+        } else if value > self.max {
+            Err(FormatError::TooLarge(self.format(&self.max)))
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+impl Formatter<i64> for IntegerFormatter {
+    fn format(&self, value: &i64) -> String {
+        let text = NumberText {
+            negative: *value < 0,
+            int: value.unsigned_abs().to_string(),
+            frac: None,
+        };
+        text.with_separator(self.separator)
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        let text = match NumberText::parse(input, self.separator, false) {
+            Ok(text) => text,
+            Err(err) => return Validation::failure(err),
+        };
+        if text.negative && self.min >= 0 {
+            return Validation::failure(InvalidCharacter('-'));
+        }
+        if text.has_digits() {
+            // a positive number only grows as digits are typed, and a
+            // negative one only shrinks
+            let value = text.plain().parse::<i64>().ok();
+            if text.negative && value.map_or(true, |value| value < self.min) {
+                return Validation::failure(FormatError::TooSmall(self.format(&self.min)));
+            }
+            if !text.negative && value.map_or(true, |value| value > self.max) {
+                return Validation::failure(FormatError::TooLarge(self.format(&self.max)));
+            }
+        }
+        regroup(input, text.with_separator(self.separator), sel)
+    }
+
+    fn value(&self, input: &str) -> Result<i64, ValidationError> {
+        let text =
+            NumberText::parse(input.trim(), self.separator, false).map_err(ValidationError::new)?;
+        if !text.has_digits() {
+            return Err(ValidationError::new(FormatError::Incomplete));
+        }
+        let value = match text.plain().parse::<i64>() {
+            Ok(value) => value,
+            Err(_) if text.negative => i64::MIN,
+            Err(_) => i64::MAX,
+        };
+        self.check_bounds(value).map_err(ValidationError::new)
+    }
+}
+
+impl FloatFormatter {
+    /// Create a new `FloatFormatter` that accepts any finite `f64` with
+    /// any number of decimal places, without a thousands separator.
+    pub fn new() -> Self {
+        FloatFormatter {
+            min: f64::MIN,
+            max: f64::MAX,
+            precision: None,
+            separator: None,
+        }
+    }
+
+    /// Builder-style method to set the smallest accepted value.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// Builder-style method to set the largest accepted value.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Builder-style method to allow at most `precision` decimal places,
+    /// and to always show that many.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Builder-style method to group thousands with `separator`, such as
+    /// `','`.
+    pub fn thousands_separator(mut self, separator: impl Into<Option<char>>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    fn check_bounds(&self, value: f64) -> Result<f64, FormatError> {
+        if value < self.min {
+            Err(FormatError::TooSmall(self.format(&self.min)))
+        } else if value > self.max {
+            Err(FormatError::TooLarge(self.format(&self.max)))
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn parse(&self, input: &str) -> Result<NumberText, ValidationError> {
+        let decimal = self.precision != Some(0);
+        let text =
+            NumberText::parse(input, self.separator, decimal).map_err(ValidationError::new)?;
+        match (&text.frac, self.precision) {
+            (Some(frac), Some(precision)) if frac.len() > precision => {
+                Err(ValidationError::new(FormatError::TooPrecise(precision)))
+            }
+            _ => Ok(text),
+        }
+    }
+}
+
+impl Formatter<f64> for FloatFormatter {
+    fn format(&self, value: &f64) -> String {
+        let plain = match self.precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        };
+        match NumberText::parse(&plain, None, true) {
+            Ok(text) => text.with_separator(self.separator),
+            // NaN or infinite
+            Err(_) => plain,
+        }
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        let text = match self.parse(input) {
+            Ok(text) => text,
+            Err(err) => return Validation::from_error(err),
+        };
+        if text.negative && self.min >= 0.0 {
+            return Validation::failure(InvalidCharacter('-'));
+        }
+        if text.has_digits() {
+            let value: f64 = text.plain().parse().unwrap_or(f64::NAN);
+            if text.negative && value < self.min {
+                return Validation::failure(FormatError::TooSmall(self.format(&self.min)));
+            }
+            if !text.negative && value > self.max {
+                return Validation::failure(FormatError::TooLarge(self.format(&self.max)));
+            }
+        }
+        regroup(input, text.with_separator(self.separator), sel)
+    }
+
+    fn value(&self, input: &str) -> Result<f64, ValidationError> {
+        let text = self.parse(input.trim())?;
+        if !text.has_digits() {
+            return Err(ValidationError::new(FormatError::Incomplete));
+        }
+        let value = text.plain().parse().map_err(ValidationError::new)?;
+        self.check_bounds(value).map_err(ValidationError::new)
+    }
+}
+
+impl CurrencyFormatter {
+    /// Create a new `CurrencyFormatter` that shows `symbol` before the
+    /// amount.
+    pub fn new(symbol: impl Into<String>) -> Self {
+        CurrencyFormatter {
+            symbol: symbol.into(),
+            placement: SymbolPlacement::Before,
+            number: FloatFormatter::new().precision(2).thousands_separator(','),
+        }
+    }
+
+    /// Builder-style method to set where the symbol is shown.
+    pub fn placement(mut self, placement: SymbolPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Builder-style method to set the smallest accepted amount.
+    pub fn min(mut self, min: f64) -> Self {
+        self.number = self.number.min(min);
+        self
+    }
+
+    /// Builder-style method to set the largest accepted amount.
+    pub fn max(mut self, max: f64) -> Self {
+        self.number = self.number.max(max);
+        self
+    }
+
+    /// Builder-style method to set the number of decimal places.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.number = self.number.precision(precision);
+        self
+    }
+
+    /// Builder-style method to set the thousands separator, or to have none.
+    pub fn thousands_separator(mut self, separator: impl Into<Option<char>>) -> Self {
+        self.number = self.number.thousands_separator(separator);
+        self
+    }
+}
+
+impl Formatter<f64> for CurrencyFormatter {
+    fn format(&self, value: &f64) -> String {
+        let number = self.number.format(value);
+        match self.placement {
+            SymbolPlacement::Before => match number.strip_prefix('-') {
+                Some(number) => format!("-{}{}", self.symbol, number),
+                None => format!("{}{}", self.symbol, number),
+            },
+            SymbolPlacement::After => format!("{} {}", number, self.symbol),
+        }
+    }
+
+    fn format_for_editing(&self, value: &f64) -> String {
+        self.number.format(value)
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        validate_without(&self.number, &self.symbol, input, sel)
+    }
+
+    fn value(&self, input: &str) -> Result<f64, ValidationError> {
+        self.number.value(&input.replace(&self.symbol, ""))
+    }
+}
+
+impl PercentFormatter {
+    /// Create a new `PercentFormatter` that shows whole percentages.
+    pub fn new() -> Self {
+        PercentFormatter {
+            number: FloatFormatter::new().precision(0),
+        }
+    }
+
+    /// Builder-style method to set the smallest accepted fraction, such as
+    /// `0.0` for `0%`.
+    pub fn min(mut self, min: f64) -> Self {
+        self.number = self.number.min(min * 100.0);
+        self
+    }
+
+    /// Builder-style method to set the largest accepted fraction, such as
+    /// `1.0` for `100%`.
+    pub fn max(mut self, max: f64) -> Self {
+        self.number = self.number.max(max * 100.0);
+        self
+    }
+
+    /// Builder-style method to set the number of decimal places of the
+    /// percentage.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.number = self.number.precision(precision);
+        self
+    }
+}
+
+impl Formatter<f64> for PercentFormatter {
+    fn format(&self, value: &f64) -> String {
+        format!("{}%", self.format_for_editing(value))
+    }
+
+    fn format_for_editing(&self, value: &f64) -> String {
+        self.number.format(&(value * 100.0))
+    }
+
+    fn validate_partial_input(&self, input: &str, sel: &Selection) -> Validation {
+        validate_without(&self.number, "%", input, sel)
+    }
+
+    fn value(&self, input: &str) -> Result<f64, ValidationError> {
+        self.number
+            .value(&input.replace('%', ""))
+            .map(|percent| percent / 100.0)
+    }
+}
+
+impl Default for IntegerFormatter {
+    fn default() -> Self {
+        IntegerFormatter::new()
+    }
+}
+
+impl Default for FloatFormatter {
+    fn default() -> Self {
+        FloatFormatter::new()
+    }
+}
+
+impl Default for PercentFormatter {
+    fn default() -> Self {
+        PercentFormatter::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Validate `input` with the caret at the end, returning the changed
+    /// text and caret, or `None` if it was rejected.
+    fn validate<T>(formatter: &impl Formatter<T>, input: &str) -> Option<(String, usize)> {
+        let validation = formatter.validate_partial_input(input, &Selection::caret(input.len()));
+        if validation.is_err() {
+            return None;
+        }
+        let caret = validation
+            .selection_change
+            .map_or(input.len(), |sel| sel.end);
+        Some((
+            validation.text_change.unwrap_or_else(|| input.into()),
+            caret,
+        ))
+    }
+
+    #[test]
+    fn integers() {
+        let formatter = IntegerFormatter::new()
+            .min(-500)
+            .max(20_000)
+            .thousands_separator(',');
+        assert_eq!(formatter.format(&12345), "12,345");
+        assert_eq!(formatter.format(&-123), "-123");
+        assert_eq!(validate(&formatter, ""), Some(("".into(), 0)));
+        assert_eq!(validate(&formatter, "-"), Some(("-".into(), 1)));
+        assert_eq!(validate(&formatter, "1234"), Some(("1,234".into(), 5)));
+        assert_eq!(validate(&formatter, "12,345"), Some(("12,345".into(), 6)));
+        assert_eq!(validate(&formatter, "1,2345"), Some(("12,345".into(), 6)));
+        assert_eq!(validate(&formatter, "20001"), None);
+        assert_eq!(validate(&formatter, "-501"), None);
+        assert_eq!(validate(&formatter, "12a"), None);
+        assert_eq!(validate(&formatter, "99999999999999999999"), None);
+
+        // the caret stays before the same digit when separators move
+        let validation = formatter.validate_partial_input("1,2345", &Selection::caret(3));
+        assert_eq!(validation.selection_change, Some(Selection::caret(2)));
+
+        assert_eq!(formatter.value("12,345").unwrap(), 12345);
+        assert!(formatter.value("-").is_err());
+        assert!(formatter.value("30000").is_err());
+
+        let positive = IntegerFormatter::new().min(0);
+        assert_eq!(validate(&positive, "-"), None);
+    }
+
+    #[test]
+    fn floats() {
+        let formatter = FloatFormatter::new()
+            .min(-10.0)
+            .max(1000.0)
+            .precision(2)
+            .thousands_separator(',');
+        assert_eq!(formatter.format(&999.5), "999.50");
+        assert_eq!(formatter.format(&-0.126), "-0.13");
+        assert_eq!(validate(&formatter, "1."), Some(("1.".into(), 2)));
+        assert_eq!(validate(&formatter, ".5"), Some((".5".into(), 2)));
+        assert_eq!(validate(&formatter, "-"), Some(("-".into(), 1)));
+        assert_eq!(validate(&formatter, "1.234"), None);
+        assert_eq!(validate(&formatter, "1.2.3"), None);
+        assert_eq!(validate(&formatter, "1000.01"), None);
+        assert_eq!(validate(&formatter, "-10.5"), None);
+        assert_eq!(formatter.value("12.5").unwrap(), 12.5);
+        assert!(formatter.value(".").is_err());
+
+        let whole = FloatFormatter::new().precision(0).thousands_separator(',');
+        assert_eq!(whole.format(&1234567.0), "1,234,567");
+        assert_eq!(validate(&whole, "1."), None);
+    }
+
+    #[test]
+    fn currency_and_percent() {
+        let dollars = CurrencyFormatter::new("$").min(0.0);
+        assert_eq!(dollars.format(&1234.5), "$1,234.50");
+        assert_eq!(dollars.format_for_editing(&1234.5), "1,234.50");
+        assert_eq!(dollars.value("$1,234.50").unwrap(), 1234.5);
+        assert_eq!(validate(&dollars, "$12"), Some(("12".into(), 2)));
+        assert_eq!(validate(&dollars, "$-12"), None);
+
+        let euros = CurrencyFormatter::new("€")
+            .placement(SymbolPlacement::After)
+            .thousands_separator(None);
+        assert_eq!(euros.format(&-5.0), "-5.00 €");
+        assert_eq!(euros.value("-5.00 €").unwrap(), -5.0);
+
+        let percent = PercentFormatter::new().min(0.0).max(1.0);
+        assert_eq!(percent.format(&0.25), "25%");
+        assert_eq!(percent.format(&0.07), "7%");
+        assert_eq!(percent.format_for_editing(&0.25), "25");
+        assert_eq!(validate(&percent, "50%"), Some(("50".into(), 2)));
+        assert_eq!(validate(&percent, "101"), None);
+        assert_eq!(percent.value("50%").unwrap(), 0.5);
+    }
+}
@@ -0,0 +1,206 @@
+//! Syntax highlighting for edited text.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::lines::Lines;
+use super::{Attribute, AttributeSpans, EditableText};
+use crate::piet::Color;
+
+/// A type that computes syntax highlighting for text.
+///
+/// Text is highlighted one line at a time, so that an [`Editor`] only has to
+/// highlight the lines changed by each edit.
+///
+/// [`Editor`]: struct.Editor.html
+pub trait Highlighter {
+    /// Returns the attributes for a line of text, without its line break.
+    ///
+    /// The ranges are relative to the start of the line.
+    fn highlight_line(&self, line: &str) -> Vec<(Range<usize>, Attribute)>;
+
+    /// Returns the attributes for all of `text`.
+    fn highlight(&self, text: &str) -> AttributeSpans {
+        let mut spans = AttributeSpans::new();
+        let mut offset = 0;
+        for line in text.split('\n') {
+            for (range, attr) in self.highlight_line(line) {
+                spans.add(range.start + offset..range.end + offset, attr);
+            }
+            offset += line.len() + 1;
+        }
+        spans
+    }
+}
+
+/// The highlighting of each line of some text, which is kept up to date
+/// as the text is edited.
+#[derive(Clone)]
+pub(crate) struct Highlighting {
+    highlighter: Arc<dyn Highlighter>,
+    lines: Lines<Vec<(Range<usize>, Attribute)>>,
+}
+
+impl Highlighting {
+    pub fn new(highlighter: Arc<dyn Highlighter>, text: &str) -> Self {
+        let lines = Lines::new(text, |line| highlighter.highlight_line(line));
+        Highlighting { highlighter, lines }
+    }
+
+    pub fn highlighter(&self) -> &Arc<dyn Highlighter> {
+        &self.highlighter
+    }
+
+    /// Highlight all of `text`.
+    pub fn set_text(&mut self, text: &str) {
+        *self = Highlighting::new(self.highlighter.clone(), text);
+    }
+
+    /// Update the highlighting after `range` of the highlighted text was
+    /// replaced with `inserted` bytes, re-highlighting only the changed
+    /// lines. `text` is the text after the edit.
+    pub fn edit<T: EditableText>(&mut self, text: &T, range: Range<usize>, inserted: usize) {
+        let highlighter = &self.highlighter;
+        self.lines.edit(text, range, inserted, |line| {
+            highlighter.highlight_line(line)
+        });
+    }
+
+    /// The attributes for the whole text.
+    pub fn spans(&self) -> AttributeSpans {
+        let mut spans = AttributeSpans::new();
+        for (offset, attrs) in self.lines.iter() {
+            for (range, attr) in attrs {
+                spans.add(range.start + offset..range.end + offset, attr.clone());
+            }
+        }
+        spans
+    }
+}
+
+impl std::fmt::Debug for Highlighting {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Highlighting")
+            .field("lines", &self.lines.len())
+            .finish()
+    }
+}
+
+/// A simple highlighter for JSON and similar languages.
+///
+/// Object keys, strings, numbers and the literals `true`, `false` and
+/// `null` are each drawn in their own color. Strings can't span lines.
+#[derive(Debug, Clone)]
+pub struct JsonHighlighter {
+    pub key_color: Color,
+    pub string_color: Color,
+    pub number_color: Color,
+    pub literal_color: Color,
+}
+
+impl Default for JsonHighlighter {
+    fn default() -> Self {
+        JsonHighlighter {
+            key_color: Color::rgb8(0x9c, 0xdc, 0xfe),
+            string_color: Color::rgb8(0xce, 0x91, 0x78),
+            number_color: Color::rgb8(0xb5, 0xce, 0xa8),
+            literal_color: Color::rgb8(0x56, 0x9c, 0xd6),
+        }
+    }
+}
+
+impl Highlighter for JsonHighlighter {
+    fn highlight_line(&self, line: &str) -> Vec<(Range<usize>, Attribute)> {
+        let mut attrs = Vec::new();
+        let bytes = line.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let start = i;
+            let color = match bytes[i] {
+                b'"' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        // skip the escaped character
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i = (i + 1).min(bytes.len());
+                    // a string followed by a colon is an object key
+                    if line[i..].trim_start().starts_with(':') {
+                        &self.key_color
+                    } else {
+                        &self.string_color
+                    }
+                }
+                b'-' | b'0'..=b'9' => {
+                    i += 1;
+                    while i < bytes.len()
+                        && matches!(bytes[i], b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+                    {
+                        i += 1;
+                    }
+                    &self.number_color
+                }
+                b'a'..=b'z' => {
+                    while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                        i += 1;
+                    }
+                    match &line[start..i] {
+                        "true" | "false" | "null" => &self.literal_color,
+                        _ => continue,
+                    }
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            attrs.push((start..i, Attribute::text_color(color.clone())));
+        }
+        attrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_tokens() {
+        let json = JsonHighlighter::default();
+        let attrs = json.highlight_line(r#"  "a\"b": [1.5e3, "x", true, nil],"#);
+        let ranges: Vec<_> = attrs.iter().map(|(range, _)| range.clone()).collect();
+        assert_eq!(ranges, vec![2..8, 11..16, 18..21, 23..27]);
+        assert!(matches!(
+            &attrs[0].1,
+            Attribute::TextColor(color) if *color == json.key_color.clone().into()
+        ));
+        assert!(matches!(
+            &attrs[2].1,
+            Attribute::TextColor(color) if *color == json.string_color.clone().into()
+        ));
+    }
+
+    #[test]
+    fn incremental_edits() {
+        let json: Arc<dyn Highlighter> = Arc::new(JsonHighlighter::default());
+        let mut text = "{\n  \"a\": 1,\n  \"b\": 2\n}".to_string();
+        let mut highlighting = Highlighting::new(json.clone(), &text);
+        let mut edit = |text: &mut String, range: Range<usize>, inserted: &str| {
+            text.edit(range.clone(), inserted);
+            highlighting.edit(text, range, inserted.len());
+            highlighting.spans()
+        };
+
+        // join the two middle lines, then split them again differently
+        let join = text.find(",\n").unwrap() + 1;
+        let spans = edit(&mut text, join..join + 3, " ");
+        assert_eq!(text, "{\n  \"a\": 1, \"b\": 2\n}");
+        assert_eq!(spans, json.highlight(&text));
+
+        let spans = edit(&mut text, 5..5, "\"c\": [\n\"d\"],\n  ");
+        assert_eq!(spans, json.highlight(&text));
+        let len = text.len();
+        let spans = edit(&mut text, 0..len, "");
+        assert_eq!(spans, json.highlight(""));
+    }
+}
@@ -0,0 +1,232 @@
+//! Undo and redo history for text editing.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use super::Selection;
+
+/// The number of undo steps kept by default.
+pub(crate) const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// A single reversible change to a text buffer.
+#[derive(Debug, Clone)]
+pub(crate) struct Change {
+    /// The offset at which the change was applied.
+    pub offset: usize,
+    /// The text that was replaced by this change.
+    pub removed: String,
+    /// The text that was inserted by this change.
+    pub inserted: String,
+    /// The selection before the change was applied.
+    pub selection_before: Selection,
+    /// The selection after the change was applied.
+    pub selection_after: Selection,
+}
+
+/// The changes made by a single edit, such as at each of several cursors,
+/// which are undone and redone together.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Step {
+    /// The changes, in the order they were applied.
+    pub changes: Vec<Change>,
+    /// The index of the change made at the primary selection.
+    pub primary: usize,
+}
+
+/// A bounded stack of [`Step`]s that can be undone and redone.
+#[derive(Debug, Clone)]
+pub(crate) struct History {
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+    limit: usize,
+    /// `true` if the most recent step was an insertion that following
+    /// insertions may be merged into.
+    open_insert: bool,
+    /// The step being recorded, and whether all of its changes are
+    /// insertions.
+    group: Option<(Step, bool)>,
+}
+
+impl Change {
+    /// The range of the removed text, before the change was applied.
+    pub fn removed_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.removed.len()
+    }
+
+    /// The range of the inserted text, after the change was applied.
+    pub fn inserted_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.inserted.len()
+    }
+}
+
+impl Step {
+    /// Merge `next` into this step if each of its changes is an insertion
+    /// at the end of the matching change of this step, returning `true` if
+    /// it was merged.
+    fn merge(&mut self, next: &Step) -> bool {
+        if self.changes.len() != next.changes.len() || self.primary != next.primary {
+            return false;
+        }
+        // the changes are applied back to front, so the offsets in `next`
+        // are moved by the insertions of this step that are before them
+        let mut moved = vec![0; self.changes.len()];
+        let mut total = 0;
+        for (i, (last, change)) in self.changes.iter().zip(&next.changes).enumerate().rev() {
+            if !change.removed.is_empty() || last.inserted_range().end + total != change.offset {
+                return false;
+            }
+            moved[i] = total;
+            total += last.inserted.len();
+        }
+        for ((last, change), moved) in self.changes.iter_mut().zip(&next.changes).zip(moved) {
+            last.inserted.push_str(&change.inserted);
+            last.selection_after = change.selection_after;
+            last.selection_after.start -= moved;
+            last.selection_after.end -= moved;
+        }
+        true
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: DEFAULT_HISTORY_LIMIT,
+            open_insert: false,
+            group: None,
+        }
+    }
+
+    /// Set the maximum number of undo steps, dropping the oldest steps
+    /// if there are more than that.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        while self.undo.len() > limit {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Forget all changes.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open_insert = false;
+    }
+
+    /// Prevent the next insertion from being merged into the last change.
+    pub fn seal(&mut self) {
+        self.open_insert = false;
+        if let Some((_, all_inserts)) = &mut self.group {
+            *all_inserts = false;
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Start recording the changes that follow as a single step, until
+    /// [`end_group`] is called.
+    ///
+    /// [`end_group`]: History::end_group
+    pub fn begin_group(&mut self) {
+        self.group = Some((Step::default(), true));
+    }
+
+    /// The number of changes recorded since [`begin_group`] was called.
+    ///
+    /// [`begin_group`]: History::begin_group
+    pub fn group_len(&self) -> usize {
+        self.group
+            .as_ref()
+            .map_or(0, |(step, _)| step.changes.len())
+    }
+
+    /// Mark the most recent change of the group as the change made at the
+    /// primary selection.
+    pub fn mark_primary(&mut self) {
+        if let Some((step, _)) = &mut self.group {
+            step.primary = step.changes.len().saturating_sub(1);
+        }
+    }
+
+    /// Record the changes since [`begin_group`] was called as one step.
+    ///
+    /// [`begin_group`]: History::begin_group
+    pub fn end_group(&mut self) {
+        if let Some((step, is_insert)) = self.group.take() {
+            if !step.changes.is_empty() {
+                self.push_step(step, is_insert);
+            }
+        }
+    }
+
+    /// Record a change that has just been applied.
+    ///
+    /// Consecutive insertions, where each one starts where the last one
+    /// ended, are merged into a single undo step.
+    pub fn push(&mut self, change: Change, is_insert: bool) {
+        match &mut self.group {
+            Some((step, all_inserts)) => {
+                step.changes.push(change);
+                *all_inserts &= is_insert;
+            }
+            None => {
+                let step = Step {
+                    changes: vec![change],
+                    primary: 0,
+                };
+                self.push_step(step, is_insert);
+            }
+        }
+    }
+
+    fn push_step(&mut self, step: Step, is_insert: bool) {
+        self.redo.clear();
+        if self.limit == 0 {
+            return;
+        }
+        if is_insert && self.open_insert {
+            if let Some(last) = self.undo.back_mut() {
+                if last.merge(&step) {
+                    return;
+                }
+            }
+        }
+        self.undo.push_back(step);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        self.open_insert = is_insert;
+    }
+
+    /// Take the most recent step, whose changes are to be reverted by the
+    /// caller in reverse order.
+    pub fn undo(&mut self) -> Option<Step> {
+        self.open_insert = false;
+        let step = self.undo.pop_back()?;
+        self.redo.push(step.clone());
+        Some(step)
+    }
+
+    /// Take the most recently undone step, whose changes are to be
+    /// reapplied by the caller in order.
+    pub fn redo(&mut self) -> Option<Step> {
+        self.open_insert = false;
+        let step = self.redo.pop()?;
+        self.undo.push_back(step.clone());
+        Some(step)
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
+    }
+}
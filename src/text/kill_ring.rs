@@ -0,0 +1,55 @@
+//! A kill ring of text that was cut, separate from the system clipboard.
+
+use std::collections::VecDeque;
+
+/// The number of kills kept by default.
+pub(crate) const DEFAULT_KILL_RING_LIMIT: usize = 60;
+
+/// A bounded list of killed text, most recent first.
+#[derive(Debug, Clone)]
+pub(crate) struct KillRing {
+    entries: VecDeque<String>,
+    limit: usize,
+}
+
+impl KillRing {
+    pub fn new() -> Self {
+        KillRing {
+            entries: VecDeque::new(),
+            limit: DEFAULT_KILL_RING_LIMIT,
+        }
+    }
+
+    /// Add a new kill, dropping the oldest one if the ring is full.
+    pub fn push(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        self.entries.push_front(text);
+        self.entries.truncate(self.limit);
+    }
+
+    /// Add text to the most recent kill, as consecutive kills make a single
+    /// entry. Text killed backwards is prepended.
+    pub fn extend(&mut self, text: &str, backwards: bool) {
+        match self.entries.front_mut() {
+            Some(last) if backwards => last.insert_str(0, text),
+            Some(last) => last.push_str(text),
+            None => self.push(text.to_owned()),
+        }
+    }
+
+    /// Returns the kill `index` entries before the most recent one,
+    /// wrapping around to the most recent after the oldest.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some(&self.entries[index % self.entries.len()])
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
@@ -16,15 +16,18 @@
 
 use std::ops::Range;
 
-use super::TextStorage;
+use super::attribute::Decorations;
+use super::bidi::{self, Direction, VisualCell};
+use super::{Affinity, AttributeSpans, Fonts, TextStorage};
 use crate::context::PaintCtx;
-use crate::kurbo::{Line, Point, Rect, Size};
+use crate::kurbo::{BezPath, Line, Point, Rect, Size, Vec2};
 use crate::piet::{
     Color, PietText, PietTextLayout, Text as _, TextAlignment, TextAttribute, TextLayout as _,
     TextLayoutBuilder as _,
 };
-use crate::text::FontDescriptor;
+use crate::text::font_descriptor::{self, FontDescriptor, UI_FONT};
 use druid::{Env, KeyOrValue, RenderContext, UpdateCtx};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A component for displaying text on screen.
 ///
@@ -55,8 +58,43 @@ pub struct TextLayout<T> {
     text_size_override: Option<KeyOrValue<f64>>,
     text_color: KeyOrValue<Color>,
     layout: Option<PietTextLayout>,
+    // attributes applied on top of those of the text, such as syntax
+    // highlighting.
+    attributes: Option<AttributeSpans>,
+    // attributes of the text that are drawn on top of the piet layout.
+    decorations: Decorations,
+    // text that is drawn away from where piet laid it out, because of
+    // letter spacing, a baseline shift or the alignment of a paragraph in
+    // another direction; found when the layout is rebuilt.
+    displaced: Vec<Displaced>,
+    // the horizontal offset of each line that is aligned for a different
+    // direction than the layout, or empty if there are none.
+    line_shifts: Vec<f64>,
     wrap_width: f64,
     alignment: TextAlignment,
+    // whether any of the text is shown from right to left, in which case
+    // carets and selections are found from the visual order of the text.
+    has_rtl: bool,
+}
+
+/// The grapheme clusters of a line in the order they are shown, and the
+/// positions of the boundaries between them.
+struct VisualLine {
+    cells: Vec<VisualCell>,
+    /// The x position of each boundary, from the left edge of the line.
+    edges: Vec<f64>,
+    y0: f64,
+    y1: f64,
+}
+
+/// A piece of text that is drawn away from where it was laid out.
+#[derive(Clone)]
+struct Displaced {
+    range: Range<usize>,
+    /// Where the text was laid out.
+    rect: Rect,
+    /// The offset at which the text is drawn.
+    offset: Vec2,
 }
 
 /// Metrics describing the layout text.
@@ -78,12 +116,17 @@ impl<T> TextLayout<T> {
     pub fn new() -> Self {
         TextLayout {
             text: None,
-            font: druid::theme::UI_FONT.into(),
+            font: KeyOrValue::Key(UI_FONT),
             text_color: druid::theme::LABEL_COLOR.into(),
             text_size_override: None,
             layout: None,
+            attributes: None,
+            decorations: Decorations::default(),
+            displaced: Vec::new(),
+            line_shifts: Vec::new(),
             wrap_width: f64::INFINITY,
             alignment: Default::default(),
+            has_rtl: false,
         }
     }
 
@@ -152,7 +195,13 @@ impl<T> TextLayout<T> {
 
     /// Set the [`TextAlignment`] for this layout.
     ///
+    /// [`TextAlignment::Start`] and [`TextAlignment::End`] are relative to
+    /// the direction of the first paragraph, so that right-to-left text is
+    /// aligned to the right by default.
+    ///
     /// [`TextAlignment`]: enum.TextAlignment.html
+    /// [`TextAlignment::Start`]: enum.TextAlignment.html#variant.Start
+    /// [`TextAlignment::End`]: enum.TextAlignment.html#variant.End
     pub fn set_text_alignment(&mut self, alignment: TextAlignment) {
         if self.alignment != alignment {
             self.alignment = alignment;
@@ -174,12 +223,25 @@ impl<T: TextStorage> TextLayout<T> {
 
     /// Set the text to display.
     pub fn set_text(&mut self, text: T) {
-        if self.text.is_none() || self.text.as_ref().unwrap().as_str() != text.as_str() {
+        // compare with `same`, so that a change to only the attributes of
+        // rich text also causes a rebuild
+        if self.text.is_none() || !self.text.as_ref().unwrap().same(&text) {
             self.text = Some(text);
             self.layout = None;
         }
     }
 
+    /// Set attributes to apply on top of those of the text.
+    ///
+    /// This is used for styling that isn't part of the text itself, such as
+    /// syntax highlighting. The ranges must be valid for the current text.
+    pub fn set_attributes(&mut self, attributes: Option<AttributeSpans>) {
+        if attributes != self.attributes {
+            self.attributes = attributes;
+            self.layout = None;
+        }
+    }
+
     /// Returns the [`TextStorage`] backing this layout, if it exists.
     ///
     /// [`TextStorage`]: trait.TextStorage.html
@@ -199,11 +261,19 @@ impl<T: TextStorage> TextLayout<T> {
     /// This is not meaningful until [`rebuild_if_needed`] has been called.
     ///
     /// [`rebuild_if_needed`]: #method.rebuild_if_needed
+    ///
+    /// This includes text that is moved by letter spacing or a baseline
+    /// shift.
     pub fn size(&self) -> Size {
-        self.layout
+        let size = self
+            .layout
             .as_ref()
             .map(|layout| layout.size())
-            .unwrap_or_default()
+            .unwrap_or_default();
+        self.displaced.iter().fold(size, |size, piece| {
+            let rect = piece.rect + piece.offset;
+            Size::new(size.width.max(rect.x1), size.height.max(rect.y1))
+        })
     }
 
     /// Return the text's [`LayoutMetrics`].
@@ -221,7 +291,7 @@ impl<T: TextStorage> TextLayout<T> {
 
         if let Some(layout) = self.layout.as_ref() {
             let first_baseline = layout.line_metric(0).unwrap().baseline;
-            let size = layout.size();
+            let size = self.size();
             LayoutMetrics {
                 size,
                 first_baseline,
@@ -234,10 +304,21 @@ impl<T: TextStorage> TextLayout<T> {
     /// For a given `Point` (relative to this object's origin), returns index
     /// into the underlying text of the nearest grapheme boundary.
     pub fn text_position_for_point(&self, point: Point) -> usize {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.hit_test_point(point).idx)
-            .unwrap_or_default()
+        let displaced = self
+            .displaced
+            .iter()
+            .find(|piece| (piece.rect + piece.offset).contains(point));
+        let layout = match self.layout.as_ref() {
+            Some(layout) => layout,
+            None => return 0,
+        };
+        match displaced {
+            Some(piece) => {
+                let offset = layout.hit_test_point(point - piece.offset).idx;
+                offset.clamp(piece.range.start, piece.range.end)
+            }
+            None => layout.hit_test_point(point).idx,
+        }
     }
 
     /// Given the utf-8 position of a character boundary in the underlying text,
@@ -250,36 +331,234 @@ impl<T: TextStorage> TextLayout<T> {
     pub fn point_for_text_position(&self, text_pos: usize) -> Point {
         self.layout
             .as_ref()
-            .map(|layout| layout.hit_test_text_position(text_pos).point)
+            .map(|layout| {
+                let point = layout.hit_test_text_position(text_pos).point;
+                point + self.shift_at(text_pos, point)
+            })
             .unwrap_or_default()
     }
 
+    /// The horizontal offset at which a caret at `text_pos` is drawn, from
+    /// letter spacing before it on its line or the alignment of the line.
+    /// `point` is where the caret was laid out.
+    fn shift_at(&self, text_pos: usize, point: Point) -> Vec2 {
+        let on_line = |piece: &&Displaced| piece.rect.y0 <= point.y && point.y <= piece.rect.y1;
+        let piece = self
+            .displaced
+            .iter()
+            .find(|piece| piece.range.contains(&text_pos))
+            .or_else(|| {
+                self.displaced
+                    .iter()
+                    .filter(on_line)
+                    .find(|piece| piece.range.end == text_pos)
+            });
+        Vec2::new(piece.map_or(0.0, |piece| piece.offset.x), 0.0)
+    }
+
     /// Given a utf-8 range in the underlying text, return a `Vec` of `Rect`s
     /// representing the nominal bounding boxes of the text in that range.
     ///
     /// # Panics
     ///
     /// Panics if the range start or end is not a character boundary.
+    ///
+    /// Where the range covers text in both directions, there is a rect for
+    /// each part of a line that is contiguous on screen.
     pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
-        self.layout
-            .as_ref()
-            .map(|layout| layout.rects_for_range(range))
-            .unwrap_or_default()
+        let layout = match self.layout.as_ref() {
+            Some(layout) => layout,
+            None => return Vec::new(),
+        };
+        if !self.displaced.is_empty() && !range.is_empty() {
+            return self.displaced_rects_for_range(layout, range);
+        }
+        if !self.has_rtl || range.is_empty() {
+            return layout.rects_for_range(range);
+        }
+        let first_line = layout.hit_test_text_position(range.start).line;
+        let last_line = layout.hit_test_text_position(range.end).line;
+        let mut rects = Vec::new();
+        for line in first_line..=last_line {
+            let visual = match self.visual_line(layout, line) {
+                Some(visual) => visual,
+                None => continue,
+            };
+            let rect = |start: usize, end: usize| {
+                Rect::new(visual.edges[start], visual.y0, visual.edges[end], visual.y1)
+            };
+            let mut run_start = None;
+            for (i, cell) in visual.cells.iter().enumerate() {
+                let selected = cell.range.start >= range.start && cell.range.end <= range.end;
+                match run_start {
+                    None if selected => run_start = Some(i),
+                    Some(start) if !selected => {
+                        rects.push(rect(start, i));
+                        run_start = None;
+                    }
+                    _ => (),
+                }
+            }
+            if let Some(start) = run_start {
+                rects.push(rect(start, visual.cells.len()));
+            }
+        }
+        rects
+    }
+
+    /// The rects of `range`, with the displaced text moved to where it is
+    /// drawn.
+    fn displaced_rects_for_range(&self, layout: &PietTextLayout, range: Range<usize>) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        let mut end = range.start;
+        for piece in &self.displaced {
+            let overlap = piece.range.start.max(range.start)..piece.range.end.min(range.end);
+            if overlap.is_empty() {
+                continue;
+            }
+            if overlap.start > end {
+                rects.extend(layout.rects_for_range(end..overlap.start));
+            }
+            // only the horizontal offset, so that the selection of a line
+            // stays as high as the line
+            let offset = Vec2::new(piece.offset.x, 0.0);
+            if overlap == piece.range {
+                rects.push(piece.rect + offset);
+            } else {
+                for rect in layout.rects_for_range(overlap.clone()) {
+                    let rect = rect.intersect(piece.rect);
+                    if rect.width() > 0.0 {
+                        rects.push(rect + offset);
+                    }
+                }
+            }
+            end = end.max(overlap.end);
+        }
+        if end < range.end {
+            rects.extend(layout.rects_for_range(end..range.end));
+        }
+        rects
     }
 
     /// Given the utf-8 position of a character boundary in the underlying text,
     /// return a `Line` suitable for drawing a vertical cursor at that boundary.
+    ///
+    /// This is the cursor for [`Affinity::Downstream`]; see
+    /// [`cursor_lines_for_text_position`] for the cursors of positions
+    /// between text in different directions.
+    ///
+    /// [`Affinity::Downstream`]: enum.Affinity.html#variant.Downstream
+    /// [`cursor_lines_for_text_position`]: #method.cursor_lines_for_text_position
     pub fn cursor_line_for_text_position(&self, text_pos: usize) -> Line {
+        self.cursor_lines_for_text_position(text_pos, Affinity::Downstream)
+            .0
+    }
+
+    /// Given the utf-8 position of a character boundary in the underlying text,
+    /// return the `Line`s for drawing a cursor there with the given
+    /// [`Affinity`].
+    ///
+    /// The first line is next to the text on the side of the affinity. Where
+    /// the position is between runs of text in different directions, it is
+    /// at two places on screen, and the second line is at the other one.
+    ///
+    /// [`Affinity`]: enum.Affinity.html
+    pub fn cursor_lines_for_text_position(
+        &self,
+        text_pos: usize,
+        affinity: Affinity,
+    ) -> (Line, Option<Line>) {
+        let layout = match self.layout.as_ref() {
+            Some(layout) => layout,
+            None => return (Line::new(Point::ZERO, Point::ZERO), None),
+        };
+        let pos = layout.hit_test_text_position(text_pos);
+        let visual = if self.has_rtl {
+            self.visual_line(layout, pos.line)
+        } else {
+            None
+        };
+        let visual = match visual {
+            Some(visual) => visual,
+            None => {
+                let line_metrics = layout.line_metric(pos.line).unwrap();
+                let x = pos.point.x + self.shift_at(text_pos, pos.point).x;
+                let p1 = (x, line_metrics.y_offset);
+                let p2 = (x, (line_metrics.y_offset + line_metrics.height));
+                return (Line::new(p1, p2), None);
+            }
+        };
+        let line = |slot: usize| {
+            let x = visual.edges[slot];
+            Line::new((x, visual.y0), (x, visual.y1))
+        };
+        let slot = bidi::caret_slot(&visual.cells, text_pos, affinity);
+        let other = match affinity {
+            Affinity::Upstream => Affinity::Downstream,
+            Affinity::Downstream => Affinity::Upstream,
+        };
+        let other_slot = bidi::caret_slot(&visual.cells, text_pos, other);
+        (line(slot), (other_slot != slot).then(|| line(other_slot)))
+    }
+
+    /// Returns `true` if any of the text is shown from right to left.
+    ///
+    /// This is not meaningful until [`rebuild_if_needed`] has been called.
+    ///
+    /// [`rebuild_if_needed`]: #method.rebuild_if_needed
+    pub fn has_rtl(&self) -> bool {
+        self.has_rtl
+    }
+
+    /// The grapheme clusters of the line containing `text_pos`, from left
+    /// to right.
+    pub(crate) fn visual_cells(&self, text_pos: usize) -> Vec<VisualCell> {
         self.layout
             .as_ref()
-            .map(|layout| {
-                let pos = layout.hit_test_text_position(text_pos);
-                let line_metrics = layout.line_metric(pos.line).unwrap();
-                let p1 = (pos.point.x, line_metrics.y_offset);
-                let p2 = (pos.point.x, (line_metrics.y_offset + line_metrics.height));
-                Line::new(p1, p2)
+            .and_then(|layout| {
+                let line = layout.hit_test_text_position(text_pos).line;
+                self.visual_line(layout, line)
             })
-            .unwrap_or_else(|| Line::new(Point::ZERO, Point::ZERO))
+            .map(|visual| visual.cells)
+            .unwrap_or_default()
+    }
+
+    fn visual_line(&self, layout: &PietTextLayout, line: usize) -> Option<VisualLine> {
+        let text = self.text.as_ref()?.as_str();
+        let metrics = layout.line_metric(line)?;
+        let start = metrics.start_offset;
+        let end = start
+            + text[start..metrics.end_offset]
+                .trim_end_matches(&['\n', '\r'][..])
+                .len();
+        let cells = bidi::visual_cells(text, start..end);
+        // each cluster is measured from its own rects, as some backends give
+        // the positions of offsets without reordering, and the clusters are
+        // then placed in visual order from the left edge of the line
+        let widths: Vec<(f64, f64)> = cells
+            .iter()
+            .map(|cell| {
+                let rects = layout.rects_for_range(cell.range.clone());
+                let left = rects
+                    .iter()
+                    .map(|rect| rect.x0)
+                    .fold(f64::INFINITY, f64::min);
+                (left, rects.iter().map(Rect::width).sum())
+            })
+            .collect();
+        let end_x = layout.hit_test_text_position(end).point.x;
+        let left = widths.iter().map(|(left, _)| *left).fold(end_x, f64::min);
+        let mut edges = Vec::with_capacity(cells.len() + 1);
+        edges.push(left + self.line_shifts.get(line).copied().unwrap_or(0.0));
+        for (_, width) in &widths {
+            edges.push(edges[edges.len() - 1] + width);
+        }
+        Some(VisualLine {
+            cells,
+            edges,
+            y0: metrics.y_offset,
+            y1: metrics.y_offset + metrics.height,
+        })
     }
 
     /// Called during the containing widgets `update` method; this text object
@@ -314,11 +593,15 @@ impl<T: TextStorage> TextLayout<T> {
     /// A simple way to ensure this is correct is to always call this method
     /// as part of your widget's [`layout`] method.
     ///
+    /// Registered font families are resolved with `fonts`, which the
+    /// contexts provide as [`LayoutCtx::fonts`].
+    ///
     /// [`layout`]: trait.Widget.html#method.layout
-    pub fn rebuild_if_needed(&mut self, factory: &mut PietText, env: &Env) {
+    /// [`LayoutCtx::fonts`]: crate::context::LayoutCtx::fonts
+    pub fn rebuild_if_needed(&mut self, factory: &mut PietText, fonts: &Fonts, env: &Env) {
         if let Some(text) = &self.text {
             if self.layout.is_none() {
-                let font = self.font.resolve(env);
+                let font = font_descriptor::resolve(&self.font, env);
                 let color = self.text_color.resolve(env);
                 let size_override = self.text_size_override.as_ref().map(|key| key.resolve(env));
 
@@ -328,16 +611,42 @@ impl<T: TextStorage> TextLayout<T> {
                     font
                 };
 
-                let builder = factory
+                self.has_rtl = bidi::has_rtl(text.as_str());
+                let direction = Direction::of_paragraph(text.as_str(), 0);
+                let mut builder = factory
                     .new_text_layout(text.clone())
                     .max_width(self.wrap_width)
-                    .alignment(self.alignment)
-                    .font(descriptor.family.clone(), descriptor.size)
+                    .alignment(direction.resolve_alignment(self.alignment))
+                    .font(fonts.resolve(&descriptor.family), descriptor.size)
                     .default_attribute(descriptor.weight)
                     .default_attribute(descriptor.style)
                     .default_attribute(TextAttribute::TextColor(color));
-                let layout = text.add_attributes(builder, env).build().unwrap();
+                let fallback = &descriptor.fallback;
+                for (range, family) in
+                    fonts.fallback_runs(text.as_str(), &descriptor.family, fallback)
+                {
+                    let family = TextAttribute::FontFamily(fonts.resolve(&family));
+                    builder = builder.range_attribute(range, family);
+                }
+                let mut builder = text.add_attributes(builder, fonts, env);
+                let mut decorations = text
+                    .attributes()
+                    .map(|attrs| attrs.to_decorations(env))
+                    .unwrap_or_default();
+                if let Some(attrs) = &self.attributes {
+                    for (range, attr) in attrs.to_piet_attrs(text.as_str(), fonts, env) {
+                        builder = builder.range_attribute(range, attr);
+                    }
+                    decorations.extend(attrs.to_decorations(env));
+                }
+                let layout = builder.build().unwrap();
+                self.line_shifts = match self.has_rtl {
+                    true => line_shifts(text.as_str(), &layout, self.alignment, self.wrap_width),
+                    false => Vec::new(),
+                };
+                self.displaced = displaced(text.as_str(), &layout, &decorations, &self.line_shifts);
                 self.layout = Some(layout);
+                self.decorations = decorations;
             }
         }
     }
@@ -360,9 +669,175 @@ impl<T: TextStorage> TextLayout<T> {
                 .unwrap_or("layout is missing text")
         );
         if let Some(layout) = self.layout.as_ref() {
-            ctx.draw_text(layout, point);
+            let point = point.into();
+            // backgrounds follow the text that is drawn away from its place
+            for (range, color) in &self.decorations.background {
+                for rect in self.rects_for_range(range.clone()) {
+                    ctx.fill(rect + point.to_vec2(), color);
+                }
+            }
+
+            let displaced = &self.displaced;
+            if displaced.is_empty() {
+                ctx.draw_text(layout, point);
+                return;
+            }
+
+            // Draw the text that stays in place, clipping out the displaced
+            // rects; these are wound in reverse so that they become holes.
+            let size = layout.size();
+            let bounds = size.to_rect().inflate(size.height, size.height) + point.to_vec2();
+            let mut clip = BezPath::new();
+            clip.move_to((bounds.x0, bounds.y0));
+            clip.line_to((bounds.x1, bounds.y0));
+            clip.line_to((bounds.x1, bounds.y1));
+            clip.line_to((bounds.x0, bounds.y1));
+            clip.close_path();
+            for piece in displaced {
+                let rect = piece.rect + point.to_vec2();
+                clip.move_to((rect.x0, rect.y0));
+                clip.line_to((rect.x0, rect.y1));
+                clip.line_to((rect.x1, rect.y1));
+                clip.line_to((rect.x1, rect.y0));
+                clip.close_path();
+            }
+            ctx.with_save(|ctx| {
+                ctx.clip(clip);
+                ctx.draw_text(layout, point);
+            });
+
+            for piece in displaced {
+                ctx.with_save(|ctx| {
+                    ctx.clip(piece.rect + point.to_vec2() + piece.offset);
+                    ctx.draw_text(layout, point + piece.offset);
+                });
+            }
+        }
+    }
+}
+
+/// The horizontal offset of each line of `layout`, to align the lines of
+/// paragraphs in another direction than the first one.
+///
+/// The layout is aligned for the direction of the first paragraph, which
+/// is wrong for [`TextAlignment::Start`] and [`TextAlignment::End`] in
+/// paragraphs in the other direction. This returns an empty `Vec` if no
+/// line needs to be moved.
+///
+/// [`TextAlignment::Start`]: crate::piet::TextAlignment::Start
+/// [`TextAlignment::End`]: crate::piet::TextAlignment::End
+fn line_shifts(
+    text: &str,
+    layout: &PietTextLayout,
+    alignment: TextAlignment,
+    wrap_width: f64,
+) -> Vec<f64> {
+    if !matches!(alignment, TextAlignment::Start | TextAlignment::End) || !text.contains('\n') {
+        return Vec::new();
+    }
+    let first = Direction::of_paragraph(text, 0);
+    let width = match wrap_width.is_finite() {
+        true => wrap_width,
+        false => layout.size().width,
+    };
+    let mut direction = first;
+    let mut shifts = Vec::with_capacity(layout.line_count());
+    for line in (0..layout.line_count()).filter_map(|i| layout.line_metric(i)) {
+        let start = line.start_offset;
+        if start == 0 || text[..start].ends_with('\n') {
+            direction = Direction::of_paragraph(text, start);
+        }
+        let end = start + text[start..line.end_offset].trim_end().len();
+        let rects = layout.rects_for_range(start..end);
+        if direction == first || rects.is_empty() {
+            shifts.push(0.0);
+            continue;
+        }
+        let x0 = rects
+            .iter()
+            .map(|rect| rect.x0)
+            .fold(f64::INFINITY, f64::min);
+        let x1 = rects
+            .iter()
+            .map(|rect| rect.x1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        shifts.push(match direction.resolve_alignment(alignment) {
+            TextAlignment::End => width - x1,
+            _ => -x0,
+        });
+    }
+    if shifts.iter().all(|shift| *shift == 0.0) {
+        shifts.clear();
+    }
+    shifts
+}
+
+/// The pieces of `text` that are drawn away from where they were laid out,
+/// because of letter spacing, a baseline shift or the shift of their line.
+///
+/// Letter spacing moves each grapheme of the spaced text, as well as any
+/// following text on the same line.
+fn displaced(
+    text: &str,
+    layout: &PietTextLayout,
+    decorations: &Decorations,
+    line_shifts: &[f64],
+) -> Vec<Displaced> {
+    let Decorations {
+        letter_spacing,
+        baseline_shift,
+        ..
+    } = decorations;
+    if letter_spacing.is_empty() && baseline_shift.is_empty() && line_shifts.is_empty() {
+        return Vec::new();
+    }
+    let value_at = |spans: &[(Range<usize>, f64)], offset: usize| {
+        spans
+            .iter()
+            .find(|(range, _)| range.contains(&offset))
+            .map(|(_, value)| *value)
+    };
+
+    let mut displaced = Vec::new();
+    let mut push = |range: Range<usize>, offset: Vec2| {
+        for rect in layout.rects_for_range(range.clone()) {
+            displaced.push(Displaced {
+                range: range.clone(),
+                rect,
+                offset,
+            });
+        }
+    };
+    for (i, line) in (0..layout.line_count()).filter_map(|i| Some((i, layout.line_metric(i)?))) {
+        let line_range = line.start_offset..line.end_offset;
+        // split the line wherever a decoration starts or ends
+        let mut bounds: Vec<usize> = letter_spacing
+            .iter()
+            .chain(baseline_shift)
+            .flat_map(|(range, _)| vec![range.start, range.end])
+            .filter(|offset| line_range.contains(offset))
+            .chain(vec![line_range.start, line_range.end])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut dx = line_shifts.get(i).copied().unwrap_or(0.0);
+        for piece in bounds.windows(2).map(|w| w[0]..w[1]) {
+            let dy = -value_at(baseline_shift, piece.start).unwrap_or(0.0);
+            match value_at(letter_spacing, piece.start) {
+                Some(spacing) => {
+                    for (start, grapheme) in text[piece.clone()].grapheme_indices(true) {
+                        let start = piece.start + start;
+                        push(start..start + grapheme.len(), Vec2::new(dx, dy));
+                        dx += spacing;
+                    }
+                }
+                None => push(piece, Vec2::new(dx, dy)),
+            }
         }
     }
+    displaced.retain(|piece| piece.offset != Vec2::ZERO);
+    displaced
 }
 
 impl<T> std::fmt::Debug for TextLayout<T> {
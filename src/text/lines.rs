@@ -0,0 +1,123 @@
+//! Values computed for each line of some text.
+
+use std::ops::Range;
+
+use super::EditableText;
+
+/// A value for each line of some text, such as its highlighting, which is
+/// kept up to date as the text is edited.
+///
+/// Only the offsets of the lines are kept rather than the text, and an
+/// edit only computes the values of the lines it changed.
+#[derive(Clone)]
+pub(crate) struct Lines<V> {
+    /// The offset of the start of each line; the first is always 0.
+    starts: Vec<usize>,
+    values: Vec<V>,
+}
+
+impl<V> Lines<V> {
+    /// Compute the value of each line of `text`, without its line break.
+    pub fn new(text: &str, value: impl FnMut(&str) -> V) -> Self {
+        let (starts, values) = lines_from(0, text, value);
+        Lines { starts, values }
+    }
+
+    /// Update the lines after `range` of the text was replaced with
+    /// `inserted` bytes, computing the values of the changed lines again.
+    ///
+    /// `text` is the text after the edit.
+    pub fn edit<T: EditableText>(
+        &mut self,
+        text: &T,
+        range: Range<usize>,
+        inserted: usize,
+        value: impl FnMut(&str) -> V,
+    ) {
+        let first = self.line_at(range.start);
+        let last = self.line_at(range.end);
+        let start = self.starts[first];
+        let end = text.next_line_break(range.start + inserted);
+        let changed = text.slice(start..end).unwrap_or_default();
+        let (starts, values) = lines_from(start, &changed, value);
+
+        let delta = inserted as isize - range.len() as isize;
+        for start in &mut self.starts[last + 1..] {
+            *start = (*start as isize + delta) as usize;
+        }
+        self.starts.splice(first..=last, starts);
+        self.values.splice(first..=last, values);
+    }
+
+    /// The offset of the start and the value of each line.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &V)> {
+        self.starts.iter().copied().zip(&self.values)
+    }
+
+    /// The offset of the start and the value of each line that has any of
+    /// `range`.
+    pub fn in_range(&self, range: Range<usize>) -> impl Iterator<Item = (usize, &V)> {
+        let lines = self.line_at(range.start)..self.line_at(range.end) + 1;
+        let starts = self.starts[lines.clone()].iter().copied();
+        starts.zip(&self.values[lines])
+    }
+
+    /// The number of lines.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The index of the line containing `offset`.
+    fn line_at(&self, offset: usize) -> usize {
+        self.starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1)
+    }
+}
+
+/// The starts and values of the lines of `text`, which starts at `offset`.
+fn lines_from<V>(
+    offset: usize,
+    text: &str,
+    mut value: impl FnMut(&str) -> V,
+) -> (Vec<usize>, Vec<V>) {
+    let mut start = offset;
+    text.split('\n')
+        .map(|line| {
+            let line_start = start;
+            start += line.len() + 1;
+            (line_start, value(line))
+        })
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_recompute_changed_lines() {
+        let mut text = "one\ntwo\nthree".to_string();
+        let mut lines = Lines::new(&text, str::to_owned);
+        let mut computed = Vec::new();
+
+        text.edit(5..9, "X\nY");
+        lines.edit(&text, 5..9, 3, |line| {
+            computed.push(line.to_owned());
+            line.to_owned()
+        });
+        assert_eq!(text, "one\ntX\nYhree");
+        // only the lines of the edit are computed again
+        assert_eq!(computed, vec!["tX", "Yhree"]);
+        let all: Vec<_> = lines.iter().map(|(s, v)| (s, v.as_str())).collect();
+        assert_eq!(all, vec![(0, "one"), (4, "tX"), (7, "Yhree")]);
+        let visible: Vec<_> = lines.in_range(5..6).map(|(s, _)| s).collect();
+        assert_eq!(visible, vec![4]);
+
+        text.edit(0..4, "");
+        lines.edit(&text, 0..4, 0, str::to_owned);
+        let all: Vec<_> = lines.iter().map(|(s, v)| (s, v.as_str())).collect();
+        assert_eq!(all, vec![(0, "tX"), (3, "Yhree")]);
+        assert_eq!(lines.len(), 2);
+    }
+}
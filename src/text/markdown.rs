@@ -0,0 +1,373 @@
+//! Converting markdown to [`RichText`].
+//!
+//! This understands a subset of CommonMark: paragraphs, ATX headings,
+//! bullet and ordered lists, fenced code blocks, and the inline markup for
+//! emphasis, strong emphasis, code spans and links.
+//!
+//! [`RichText`]: ../struct.RichText.html
+
+use std::ops::Range;
+
+use super::{Attribute, RichText};
+use crate::piet::{Color, FontFamily, FontStyle, FontWeight};
+
+/// The color of link text.
+const LINK_COLOR: Color = Color::rgb8(0x5c, 0xc4, 0xff);
+
+/// The kind of a [`Block`].
+///
+/// [`Block`]: struct.Block.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    /// A paragraph of text.
+    Paragraph,
+    /// A heading, with its level from 1 to 6.
+    Heading(u8),
+    /// An item of a bullet or ordered list, with its nesting depth,
+    /// starting at 0. The text starts with the bullet or number.
+    ListItem(usize),
+    /// A block of preformatted code.
+    CodeBlock,
+}
+
+/// A block of markdown, such as a paragraph or a list item.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// What kind of block this is.
+    pub kind: BlockKind,
+    /// The text of the block, with inline markup applied as attributes.
+    pub text: RichText,
+}
+
+/// Parse markdown into a sequence of blocks.
+pub fn parse(source: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    // the kind, prefix and source of a paragraph or list item, which may
+    // continue on the following lines
+    let mut pending: Option<(BlockKind, String, String)> = None;
+    fn flush(pending: &mut Option<(BlockKind, String, String)>, blocks: &mut Vec<Block>) {
+        if let Some((kind, prefix, source)) = pending.take() {
+            blocks.push(Block {
+                kind,
+                text: parse_inline(prefix, &source),
+            });
+        }
+    }
+
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if trimmed.starts_with("```") {
+            flush(&mut pending, &mut blocks);
+            let code: Vec<&str> = lines
+                .by_ref()
+                .take_while(|line| !line.trim_start().starts_with("```"))
+                .collect();
+            blocks.push(Block {
+                kind: BlockKind::CodeBlock,
+                text: RichText::new(code.join("\n").into()),
+            });
+        } else if trimmed.is_empty() {
+            flush(&mut pending, &mut blocks);
+        } else if let Some((level, text)) = heading(trimmed) {
+            flush(&mut pending, &mut blocks);
+            blocks.push(Block {
+                kind: BlockKind::Heading(level),
+                text: parse_inline(String::new(), text),
+            });
+        } else if let Some((marker, text)) = list_marker(trimmed) {
+            flush(&mut pending, &mut blocks);
+            let kind = BlockKind::ListItem(indent / 2);
+            pending = Some((kind, format!("{} ", marker), text.to_owned()));
+        } else if let Some((_, _, source)) = &mut pending {
+            source.push(' ');
+            source.push_str(trimmed.trim_end());
+        } else {
+            let source = trimmed.trim_end().to_owned();
+            pending = Some((BlockKind::Paragraph, String::new(), source));
+        }
+    }
+    flush(&mut pending, &mut blocks);
+    blocks
+}
+
+/// If the line is an ATX heading, returns its level and text.
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level as u8, rest.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+/// If the line starts a list item, returns the marker to display and the
+/// text of the item.
+fn list_marker(line: &str) -> Option<(String, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            return Some(("\u{2022}".to_owned(), text.trim()));
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let rest = &line[digits..];
+    if (1..10).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        Some((format!("{}.", &line[..digits]), rest[2..].trim()))
+    } else {
+        None
+    }
+}
+
+/// Parse the inline markup of `source`, appending its text to `prefix`.
+fn parse_inline(prefix: String, source: &str) -> RichText {
+    let mut buffer = prefix;
+    let mut attrs = Vec::new();
+    push_inline(source, &mut buffer, &mut attrs);
+    let mut text = RichText::new(buffer.into());
+    for (range, attr) in attrs {
+        text.add_attribute(range, attr);
+    }
+    text
+}
+
+/// Append the text of `source` to `buffer`, and the attributes for its
+/// inline markup to `attrs`.
+///
+/// A delimiter only opens emphasis if it is closed later on, otherwise it
+/// is kept as text.
+fn push_inline(source: &str, buffer: &mut String, attrs: &mut Vec<(Range<usize>, Attribute)>) {
+    // the start and delimiter of open emphasis and strong emphasis
+    let mut emphasis: Option<(usize, char)> = None;
+    let mut strong: Option<(usize, &str)> = None;
+    let mut prev = None;
+    let mut rest = source;
+
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        let next = after.chars().next();
+
+        if c == '\\' && next.is_some_and(|next| next.is_ascii_punctuation()) {
+            buffer.push(next.unwrap());
+            prev = next;
+            rest = &after[1..];
+            continue;
+        }
+        if c == '`' {
+            if let Some(end) = after.find('`') {
+                let start = buffer.len();
+                buffer.push_str(&after[..end]);
+                let font = Attribute::font_family(FontFamily::MONOSPACE);
+                attrs.push((start..buffer.len(), font));
+                prev = Some('`');
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        if c == '[' {
+            if let Some((text, url, len)) = link(rest) {
+                let start = buffer.len();
+                push_inline(text, buffer, attrs);
+                attrs.push((start..buffer.len(), Attribute::link(url)));
+                attrs.push((start..buffer.len(), Attribute::text_color(LINK_COLOR)));
+                prev = Some(')');
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        // an underscore between letters, as in snake_case, is not a delimiter
+        let intraword = c == '_'
+            && prev.is_some_and(char::is_alphanumeric)
+            && next.is_some_and(char::is_alphanumeric);
+        if (c == '*' || c == '_') && !intraword {
+            let double = if c == '*' { "**" } else { "__" };
+            if rest.starts_with(double) {
+                match strong {
+                    Some((start, delim)) if delim == double => {
+                        let bold = Attribute::weight(FontWeight::BOLD);
+                        attrs.push((start..buffer.len(), bold));
+                        strong = None;
+                        prev = Some(c);
+                        rest = &rest[2..];
+                        continue;
+                    }
+                    None if rest[2..].contains(double) => {
+                        strong = Some((buffer.len(), double));
+                        prev = Some(c);
+                        rest = &rest[2..];
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            match emphasis {
+                Some((start, delim)) if delim == c => {
+                    let italic = Attribute::style(FontStyle::Italic);
+                    attrs.push((start..buffer.len(), italic));
+                    emphasis = None;
+                    prev = Some(c);
+                    rest = after;
+                    continue;
+                }
+                None if after.contains(c) => {
+                    emphasis = Some((buffer.len(), c));
+                    prev = Some(c);
+                    rest = after;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        buffer.push(c);
+        prev = Some(c);
+        rest = after;
+    }
+}
+
+/// If `source` starts with a link, returns its text, its destination and
+/// the length of its markup.
+///
+/// The text ends at the `]` that closes the opening `[`, which must be
+/// followed immediately by the destination in parentheses.
+fn link(source: &str) -> Option<(&str, &str, usize)> {
+    let mut depth = 0;
+    let mut escaped = false;
+    let mut text_end = None;
+    for (i, c) in source.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    text_end = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let text_end = text_end?;
+    let destination = source[text_end + 1..].strip_prefix('(')?;
+    let url_end = text_end + 2 + destination.find(')')?;
+    let text = &source[1..text_end];
+    let url = source[text_end + 2..url_end].trim();
+    Some((text, url, url_end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piet::TextStorage as _;
+    use crate::text::{AttributeSpans, TextStorage};
+
+    fn spans(attrs: Vec<(Range<usize>, Attribute)>) -> AttributeSpans {
+        let mut spans = AttributeSpans::new();
+        for (range, attr) in attrs {
+            spans.add(range, attr);
+        }
+        spans
+    }
+
+    #[test]
+    fn inline_markup() {
+        let text = parse_inline(String::new(), "a *b* **c** `d` [e](f) snake_case 2*3");
+        assert_eq!(text.as_str(), "a b c d e snake_case 2*3");
+        assert_eq!(
+            text.attributes(),
+            Some(&spans(vec![
+                (2..3, Attribute::style(FontStyle::Italic)),
+                (4..5, Attribute::weight(FontWeight::BOLD)),
+                (6..7, Attribute::font_family(FontFamily::MONOSPACE)),
+                (8..9, Attribute::link("f")),
+                (8..9, Attribute::text_color(LINK_COLOR)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn nested_markup() {
+        let text = parse_inline(String::new(), "***both*** and [a **b**](c)");
+        assert_eq!(text.as_str(), "both and a b");
+        assert_eq!(
+            text.attributes(),
+            Some(&spans(vec![
+                (0..4, Attribute::weight(FontWeight::BOLD)),
+                (0..4, Attribute::style(FontStyle::Italic)),
+                (11..12, Attribute::weight(FontWeight::BOLD)),
+                (9..12, Attribute::link("c")),
+                (9..12, Attribute::text_color(LINK_COLOR)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn links() {
+        let text = parse_inline(String::new(), "[a] b [c](d) [[e]](f) [g] (h)");
+        assert_eq!(text.as_str(), "[a] b c [e] [g] (h)");
+        assert_eq!(
+            text.attributes(),
+            Some(&spans(vec![
+                (6..7, Attribute::link("d")),
+                (6..7, Attribute::text_color(LINK_COLOR)),
+                (8..11, Attribute::link("f")),
+                (8..11, Attribute::text_color(LINK_COLOR)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        let source = "# Release *notes*\n\
+                      First line\n\
+                      continued.\n\
+                      \n\
+                      - one\n\
+                      \x20 - nested `code`\n\
+                      2. two\n\
+                      ```\n\
+                      let x = 1;\n\
+                      ```";
+        let blocks = parse(source);
+        let kinds: Vec<_> = blocks.iter().map(|block| block.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Heading(1),
+                BlockKind::Paragraph,
+                BlockKind::ListItem(0),
+                BlockKind::ListItem(1),
+                BlockKind::ListItem(0),
+                BlockKind::CodeBlock,
+            ]
+        );
+        let texts: Vec<_> = blocks.iter().map(|block| block.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Release notes",
+                "First line continued.",
+                "\u{2022} one",
+                "\u{2022} nested code",
+                "2. two",
+                "let x = 1;",
+            ]
+        );
+        assert_eq!(
+            blocks[0].text.attributes(),
+            Some(&spans(vec![(8..13, Attribute::style(FontStyle::Italic))]))
+        );
+        let bullet = "\u{2022} nested ".len();
+        assert_eq!(
+            blocks[3].text.attributes(),
+            Some(&spans(vec![(
+                bullet..bullet + 4,
+                Attribute::font_family(FontFamily::MONOSPACE)
+            )]))
+        );
+    }
+}
@@ -16,26 +16,51 @@
 
 mod attribute;
 pub mod backspace;
+mod bidi;
+mod completion;
+mod composition;
 mod editable_text;
 mod editor;
-//mod font_descriptor;
+mod emacs;
+mod filter;
+pub(crate) mod font;
+mod font_descriptor;
 pub mod format;
+mod highlight;
+mod history;
+mod kill_ring;
 mod layout;
+mod lines;
+pub mod markdown;
 pub mod movement;
 mod rich_text;
+mod rope;
+mod search;
 pub mod selection;
+mod spell;
 mod storage;
 mod text_input;
+mod vim;
 
 pub use self::attribute::{Attribute, AttributeSpans};
 pub use self::backspace::offset_for_delete_backwards;
+pub use self::bidi::Direction;
+pub use self::completion::{CompletionSource, Suggestion};
+pub use self::composition::{Composition, CompositionEvent, COMPOSE};
 pub use self::editable_text::{EditableText, EditableTextCursor, StringCursor};
-//pub use self::font_descriptor::FontDescriptor;
+pub use self::emacs::EmacsTextInput;
+pub use self::filter::InputFilter;
+pub use self::font::{FontError, Fonts};
+pub use self::font_descriptor::FontDescriptor;
+pub use self::highlight::{Highlighter, JsonHighlighter};
 pub use self::layout::{LayoutMetrics, TextLayout};
 pub use self::movement::{movement, Movement};
-pub use self::selection::Selection;
-pub use self::text_input::{BasicTextInput, EditAction, MouseAction, TextInput};
-pub use druid::FontDescriptor;
+pub use self::selection::{Affinity, Selection};
+pub use self::spell::{edit_distance, SpellChecker, WordList};
+pub use self::text_input::{BasicTextInput, CursorShape, EditAction, MouseAction, TextInput};
+pub use self::vim::{VimMode, VimTextInput};
 pub use editor::Editor;
 pub use rich_text::{AttributesAdder, RichText, RichTextBuilder};
+pub use rope::{Chunks, Rope, RopeCursor};
+pub use search::SearchQuery;
 pub use storage::{ArcStr, TextStorage};
@@ -16,23 +16,39 @@
 
 use crate::kurbo::Point;
 use crate::piet::TextLayout as _;
-use crate::text::{EditableText, Selection, TextLayout, TextStorage};
+use crate::text::bidi::{self, Direction};
+use crate::text::{Affinity, EditableText, Selection, TextLayout, TextStorage};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// The specification of a movement.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Movement {
     /// Move to the left by one grapheme cluster.
+    ///
+    /// In text with both directions, this moves by what is shown on screen,
+    /// which may be forwards or backwards in the text.
     Left,
     /// Move to the right by one grapheme cluster.
+    ///
+    /// In text with both directions, this moves by what is shown on screen,
+    /// which may be forwards or backwards in the text.
     Right,
     /// Move up one visible line.
     Up,
     /// Move down one visible line.
     Down,
     /// Move to the left by one word.
+    ///
+    /// This moves backwards in left-to-right paragraphs and forwards in
+    /// right-to-left ones.
     LeftWord,
     /// Move to the right by one word.
+    ///
+    /// This moves forwards in left-to-right paragraphs and backwards in
+    /// right-to-left ones.
     RightWord,
+    /// Move to the start of the next word.
+    RightWordStart,
     /// Move to left end of visible line.
     PrecedingLineBreak,
     /// Move to right end of visible line.
@@ -41,6 +57,26 @@ pub enum Movement {
     StartOfDocument,
     /// Move to the end of the document
     EndOfDocument,
+    /// Move to the start of the paragraph, or of the previous paragraph if
+    /// already at the start of one.
+    StartOfParagraph,
+    /// Move to the end of the paragraph, or of the next paragraph if
+    /// already at the end of one.
+    EndOfParagraph,
+    /// Move up by the given height, usually that of the viewport.
+    ///
+    /// This moves by at least one line.
+    PageUp(f64),
+    /// Move down by the given height, usually that of the viewport.
+    ///
+    /// This moves by at least one line.
+    PageDown(f64),
+    /// Move to the start of the sentence, or of the previous sentence if
+    /// already at the start of one.
+    StartOfSentence,
+    /// Move to the end of the sentence, or of the next sentence if
+    /// already at the end of one.
+    EndOfSentence,
 }
 
 /// Compute the result of movement on a selection.
@@ -53,36 +89,50 @@ pub enum Movement {
 pub fn movement<T: EditableText + TextStorage>(
     m: Movement,
     s: Selection,
-    layout: &TextLayout<T>,
+    text_layout: &TextLayout<T>,
     modify: bool,
 ) -> Selection {
-    let (text, layout) = match (layout.text(), layout.layout()) {
+    let (text, layout) = match (text_layout.text(), text_layout.layout()) {
         (Some(text), Some(layout)) => (text, layout),
         _ => {
             debug_assert!(false, "movement() called before layout rebuild");
             return s;
         }
     };
+    let rtl = text_layout.has_rtl() && Direction::of_paragraph(text.as_str(), s.end).is_rtl();
+    let mut affinity = Affinity::Downstream;
 
     let (offset, h_pos) = match m {
-        Movement::Left => {
-            if s.is_caret() || modify {
-                text.prev_grapheme_offset(s.end)
-                    .map(|off| (off, None))
-                    .unwrap_or((0, s.h_pos))
+        Movement::Left | Movement::Right if !(s.is_caret() || modify) => {
+            // collapse the selection to the edge on that side
+            let right = m == Movement::Right;
+            if right != rtl {
+                (s.max(), None)
             } else {
                 (s.min(), None)
             }
         }
-        Movement::Right => {
-            if s.is_caret() || modify {
-                text.next_grapheme_offset(s.end)
-                    .map(|off| (off, None))
-                    .unwrap_or((s.end, s.h_pos))
-            } else {
-                (s.max(), None)
+        Movement::Left | Movement::Right if text_layout.has_rtl() => {
+            let right = m == Movement::Right;
+            let cells = text_layout.visual_cells(s.end);
+            match bidi::move_visually(&cells, s.end, s.affinity, right) {
+                Some((offset, moved)) => {
+                    affinity = moved;
+                    (offset, None)
+                }
+                // at the edge of the line, go on to the next or previous one
+                None if right != rtl => (text.next_grapheme_offset(s.end).unwrap_or(s.end), None),
+                None => (text.prev_grapheme_offset(s.end).unwrap_or(0), None),
             }
         }
+        Movement::Left => text
+            .prev_grapheme_offset(s.end)
+            .map(|off| (off, None))
+            .unwrap_or((0, s.h_pos)),
+        Movement::Right => text
+            .next_grapheme_offset(s.end)
+            .map(|off| (off, None))
+            .unwrap_or((s.end, s.h_pos)),
 
         Movement::Up => {
             let cur_pos = layout.hit_test_text_position(s.end);
@@ -96,6 +146,30 @@ pub fn movement<T: EditableText + TextStorage>(
                 (up_pos.idx, Some(point_above.x))
             }
         }
+        Movement::PageUp(height) => {
+            let cur_pos = layout.hit_test_text_position(s.end);
+            let h_pos = s.h_pos.unwrap_or(cur_pos.point.x);
+            let lm = layout.line_metric(cur_pos.line).unwrap();
+            let y_above = cur_pos.point.y - height.max(lm.height);
+            if y_above < 0.0 {
+                (0, Some(h_pos))
+            } else {
+                let point_above = Point::new(h_pos, y_above);
+                (layout.hit_test_point(point_above).idx, Some(h_pos))
+            }
+        }
+        Movement::PageDown(height) => {
+            let cur_pos = layout.hit_test_text_position(s.end);
+            let h_pos = s.h_pos.unwrap_or(cur_pos.point.x);
+            let lm = layout.line_metric(cur_pos.line).unwrap();
+            let y_below = cur_pos.point.y + height.max(lm.height);
+            if y_below > layout.size().height {
+                (text.len(), Some(h_pos))
+            } else {
+                let point_below = Point::new(h_pos, y_below);
+                (layout.hit_test_point(point_below).idx, Some(h_pos))
+            }
+        }
         Movement::Down => {
             let cur_pos = layout.hit_test_text_position(s.end);
             let h_pos = s.h_pos.unwrap_or(cur_pos.point.x);
@@ -117,24 +191,111 @@ pub fn movement<T: EditableText + TextStorage>(
         Movement::StartOfDocument => (0, None),
         Movement::EndOfDocument => (text.len(), None),
 
-        Movement::LeftWord => {
-            let offset = if s.is_caret() || modify {
-                text.prev_word_offset(s.end).unwrap_or(0)
-            } else {
-                s.min()
+        Movement::StartOfParagraph => (start_of_paragraph(text, s.end), None),
+        Movement::EndOfParagraph => (end_of_paragraph(text, s.end), None),
+        Movement::StartOfSentence => (start_of_sentence(text.as_str(), s.end), None),
+        Movement::EndOfSentence => (end_of_sentence(text.as_str(), s.end), None),
+
+        Movement::LeftWord | Movement::RightWord => {
+            let forward = (m == Movement::RightWord) != rtl;
+            let offset = match (s.is_caret() || modify, forward) {
+                (true, false) => text.prev_word_offset(s.end).unwrap_or(0),
+                (true, true) => text.next_word_offset(s.end).unwrap_or(s.end),
+                (false, false) => s.min(),
+                (false, true) => s.max(),
             };
             (offset, None)
         }
-        Movement::RightWord => {
-            let offset = if s.is_caret() || modify {
-                text.next_word_offset(s.end).unwrap_or(s.end)
+        Movement::RightWordStart => {
+            // the start of the word that ends at the next word offset, unless
+            // that is the word we are in, in which case the one after it
+            let word_end = text.next_word_offset(s.end).unwrap_or(s.end);
+            let word_start = text.prev_word_offset(word_end).unwrap_or(0);
+            if word_start > s.end {
+                (word_start, None)
             } else {
-                s.max()
-            };
-            (offset, None)
+                let next_end = text.next_word_offset(word_end).unwrap_or(word_end);
+                if next_end == word_end {
+                    (text.len(), None)
+                } else {
+                    (text.prev_word_offset(next_end).unwrap_or(0), None)
+                }
+            }
         }
     };
 
     let start = if modify { s.start } else { offset };
-    Selection::new(start, offset).with_h_pos(h_pos)
+    Selection::new(start, offset)
+        .with_h_pos(h_pos)
+        .with_affinity(affinity)
+}
+
+/// The start of the paragraph containing `offset`, or of the previous
+/// paragraph if `offset` is at the start of one.
+fn start_of_paragraph<T: EditableText>(text: &T, offset: usize) -> usize {
+    let start = text.preceding_line_break(offset);
+    if start == offset && offset > 0 {
+        text.preceding_line_break(offset - 1)
+    } else {
+        start
+    }
+}
+
+/// The end of the paragraph containing `offset`, or of the next paragraph
+/// if `offset` is at the end of one.
+fn end_of_paragraph<T: EditableText>(text: &T, offset: usize) -> usize {
+    let end = text.next_line_break(offset);
+    if end == offset && offset < text.len() {
+        text.next_line_break(offset + 1)
+    } else {
+        end
+    }
+}
+
+/// The start of the sentence containing `offset`, or of the previous
+/// sentence if `offset` is at the start of one.
+fn start_of_sentence(text: &str, offset: usize) -> usize {
+    text.split_sentence_bound_indices()
+        .map(|(start, _)| start)
+        .take_while(|start| *start < offset)
+        .last()
+        .unwrap_or(0)
+}
+
+/// The end of the sentence containing `offset`, not including trailing
+/// whitespace, or of the next sentence if `offset` is at the end of one.
+fn end_of_sentence(text: &str, offset: usize) -> usize {
+    text.split_sentence_bound_indices()
+        .map(|(start, sentence)| start + sentence.trim_end().len())
+        .find(|end| *end > offset)
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs() {
+        let text = "one\ntwo three\n\nfour".to_string();
+        assert_eq!(start_of_paragraph(&text, 8), 4);
+        assert_eq!(start_of_paragraph(&text, 4), 0);
+        assert_eq!(start_of_paragraph(&text, 0), 0);
+        assert_eq!(end_of_paragraph(&text, 5), 13);
+        assert_eq!(end_of_paragraph(&text, 13), 14);
+        assert_eq!(end_of_paragraph(&text, 14), 19);
+        assert_eq!(end_of_paragraph(&text, 19), 19);
+    }
+
+    #[test]
+    fn sentences() {
+        let text = "Hello there. How are you? Fine.";
+        assert_eq!(start_of_sentence(text, 17), 13);
+        assert_eq!(start_of_sentence(text, 13), 0);
+        assert_eq!(start_of_sentence(text, 0), 0);
+        assert_eq!(end_of_sentence(text, 0), 12);
+        assert_eq!(end_of_sentence(text, 12), 25);
+        assert_eq!(end_of_sentence(text, 27), 31);
+        assert_eq!(end_of_sentence(text, 31), 31);
+    }
 }
@@ -17,15 +17,15 @@
 use std::ops::{Range, RangeBounds};
 use std::sync::Arc;
 
-use super::{Attribute, AttributeSpans, TextStorage};
+use super::{Attribute, AttributeSpans, FontDescriptor, Fonts, TextStorage};
 use crate::piet::{
     util, Color, FontFamily, FontStyle, FontWeight, PietTextLayoutBuilder, TextLayoutBuilder,
     TextStorage as PietTextStorage,
 };
-use druid::{ArcStr, Data, Env, FontDescriptor, KeyOrValue};
+use druid::{ArcStr, Data, Env, KeyOrValue};
 
 /// Text with optional style spans.
-#[derive(Debug, Clone, Data)]
+#[derive(Debug, Clone, Data, PartialEq)]
 pub struct RichText {
     buffer: ArcStr,
     attrs: Arc<AttributeSpans>,
@@ -70,6 +70,23 @@ impl RichText {
         let range = util::resolve_range(range, self.buffer.len());
         Arc::make_mut(&mut self.attrs).add(range, attr);
     }
+
+    /// Returns the range and payload of the [`Link`] at `offset`, if there is one.
+    ///
+    /// [`Link`]: enum.Attribute.html#variant.Link
+    pub fn link_at(&self, offset: usize) -> Option<(Range<usize>, &ArcStr)> {
+        self.attrs.link_at(offset)
+    }
+
+    /// A copy of this text with `range` replaced by `text`, which has no
+    /// attributes of its own.
+    pub(crate) fn with_replaced(&self, range: Range<usize>, text: &str) -> RichText {
+        let mut buffer = self.buffer.to_string();
+        buffer.replace_range(range.clone(), text);
+        let mut attrs = (*self.attrs).clone();
+        attrs.edit(range, text.len());
+        RichText::new_with_attributes(buffer.into(), attrs)
+    }
 }
 
 impl PietTextStorage for RichText {
@@ -82,13 +99,18 @@ impl TextStorage for RichText {
     fn add_attributes(
         &self,
         mut builder: PietTextLayoutBuilder,
+        fonts: &Fonts,
         env: &Env,
     ) -> PietTextLayoutBuilder {
-        for (range, attr) in self.attrs.to_piet_attrs(env) {
+        for (range, attr) in self.attrs.to_piet_attrs(&self.buffer, fonts, env) {
             builder = builder.range_attribute(range, attr);
         }
         builder
     }
+
+    fn attributes(&self) -> Option<&AttributeSpans> {
+        Some(&self.attrs)
+    }
 }
 
 /// A builder for creating [`RichText`] objects.
@@ -203,4 +225,34 @@ impl AttributesAdder<'_> {
         self.add_attr(Attribute::font_descriptor(font));
         self
     }
+
+    /// Add a background color attribute.
+    pub fn background_color(&mut self, color: impl Into<KeyOrValue<Color>>) -> &mut Self {
+        self.add_attr(Attribute::background_color(color));
+        self
+    }
+
+    /// Add a strikethrough attribute.
+    pub fn strikethrough(&mut self, strikethrough: bool) -> &mut Self {
+        self.add_attr(Attribute::strikethrough(strikethrough));
+        self
+    }
+
+    /// Add a letter spacing attribute.
+    pub fn letter_spacing(&mut self, spacing: f64) -> &mut Self {
+        self.add_attr(Attribute::letter_spacing(spacing));
+        self
+    }
+
+    /// Add a baseline shift attribute.
+    pub fn baseline_shift(&mut self, shift: f64) -> &mut Self {
+        self.add_attr(Attribute::baseline_shift(shift));
+        self
+    }
+
+    /// Add a link attribute.
+    pub fn link(&mut self, payload: impl Into<ArcStr>) -> &mut Self {
+        self.add_attr(Attribute::link(payload));
+        self
+    }
 }
@@ -0,0 +1,822 @@
+//! A rope, for editing large documents.
+
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
+
+use super::editable_text::len_utf8_from_first_byte;
+use super::{EditableText, EditableTextCursor, StringCursor, TextStorage};
+use crate::piet::TextStorage as PietTextStorage;
+use druid::Data;
+
+/// The smallest leaf that doesn't need to be merged with its neighbours.
+const MIN_LEAF: usize = 511;
+const MAX_LEAF: usize = 1024;
+/// The fewest children of a node that doesn't need to be merged with its
+/// neighbours.
+const MIN_CHILDREN: usize = 4;
+const MAX_CHILDREN: usize = 8;
+
+/// A string stored as a balanced tree of chunks, for editing large
+/// documents.
+///
+/// Editing a `Rope` only copies the chunks around the edit, and cloning it
+/// is cheap, as the unchanged chunks are shared. Movement by codepoints,
+/// graphemes, words and lines walks the chunks without building the whole
+/// string.
+///
+/// Piet lays out contiguous strings, so [`as_str`] builds the whole string
+/// the first time it is called after an edit. This happens once per edit,
+/// when the text is laid out again; editing, comparing and moving through
+/// the text don't need it.
+///
+/// [`as_str`]: #method.as_str
+#[derive(Clone, Default)]
+pub struct Rope {
+    root: Arc<Node>,
+    /// The whole string, built when it is first needed.
+    flat: Arc<OnceLock<String>>,
+}
+
+/// A cursor over a [`Rope`], which moves by codepoints without building the
+/// whole string.
+///
+/// [`Rope`]: struct.Rope.html
+#[derive(Debug)]
+pub struct RopeCursor<'a> {
+    rope: &'a Rope,
+    position: usize,
+}
+
+/// An iterator over the chunks of a [`Rope`].
+///
+/// [`Rope`]: struct.Rope.html
+pub struct Chunks<'a> {
+    stack: Vec<&'a Node>,
+}
+
+#[derive(Debug)]
+struct Node {
+    /// The height of this node; leaves have a height of 0.
+    height: usize,
+    len: usize,
+    body: Body,
+}
+
+#[derive(Debug)]
+enum Body {
+    Leaf(String),
+    /// Children that all have a height one less than the node.
+    Internal(Vec<Arc<Node>>),
+}
+
+impl Rope {
+    /// Returns the length of the text in bytes.
+    pub fn len(&self) -> usize {
+        self.root.len
+    }
+
+    /// Returns `true` if the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.root.len == 0
+    }
+
+    /// Returns the text as one contiguous string.
+    pub fn as_str(&self) -> &str {
+        self.flat_string()
+    }
+
+    /// Returns an iterator over the chunks of the text, in order.
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            stack: vec![&self.root],
+        }
+    }
+
+    /// Returns a cursor at `position`, if it is a codepoint boundary.
+    ///
+    /// Unlike [`EditableText::cursor`], this doesn't build the whole string.
+    ///
+    /// [`EditableText::cursor`]: trait.EditableText.html#tymethod.cursor
+    pub fn rope_cursor(&self, position: usize) -> Option<RopeCursor<'_>> {
+        if self.is_char_boundary(position) {
+            Some(RopeCursor {
+                rope: self,
+                position,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `offset` is a codepoint boundary.
+    pub fn is_char_boundary(&self, offset: usize) -> bool {
+        if offset > self.len() {
+            return false;
+        }
+        let (leaf, start) = self.leaf_at(offset);
+        leaf.is_char_boundary(offset - start)
+    }
+
+    fn flat_string(&self) -> &String {
+        self.flat.get_or_init(|| self.chunks().collect())
+    }
+
+    /// Returns the leaf containing `offset` and the offset of its start.
+    ///
+    /// The end of the text is in the last leaf.
+    fn leaf_at(&self, offset: usize) -> (&str, usize) {
+        let mut node = &*self.root;
+        let mut start = 0;
+        loop {
+            match &node.body {
+                Body::Leaf(text) => return (text, start),
+                Body::Internal(children) => {
+                    let last = children.len() - 1;
+                    for (i, child) in children.iter().enumerate() {
+                        if offset < start + child.len || i == last {
+                            node = child;
+                            break;
+                        }
+                        start += child.len;
+                    }
+                }
+            }
+        }
+    }
+
+    fn char_at(&self, offset: usize) -> Option<char> {
+        if offset >= self.len() {
+            return None;
+        }
+        let (leaf, start) = self.leaf_at(offset);
+        leaf.get(offset - start..)?.chars().next()
+    }
+}
+
+impl Node {
+    fn leaf(text: String) -> Arc<Node> {
+        Arc::new(Node {
+            height: 0,
+            len: text.len(),
+            body: Body::Leaf(text),
+        })
+    }
+
+    fn internal(children: Vec<Arc<Node>>) -> Arc<Node> {
+        Arc::new(Node {
+            height: children[0].height + 1,
+            len: children.iter().map(|child| child.len).sum(),
+            body: Body::Internal(children),
+        })
+    }
+
+    /// Build a balanced tree from a string.
+    fn from_str(text: &str) -> Arc<Node> {
+        let mut nodes: Vec<_> = split_evenly(text, MAX_LEAF)
+            .into_iter()
+            .map(|range| Node::leaf(text[range].to_owned()))
+            .collect();
+        while nodes.len() > 1 {
+            let groups = nodes.len().div_ceil(MAX_CHILDREN);
+            let count = nodes.len();
+            let mut rest = nodes.into_iter();
+            nodes = (0..groups)
+                .map(|i| {
+                    let size = count * (i + 1) / groups - count * i / groups;
+                    Node::internal(rest.by_ref().take(size).collect())
+                })
+                .collect();
+        }
+        nodes.pop().unwrap_or_default()
+    }
+
+    fn children(&self) -> &[Arc<Node>] {
+        match &self.body {
+            Body::Leaf(_) => &[],
+            Body::Internal(children) => children,
+        }
+    }
+
+    fn is_ok_child(&self) -> bool {
+        match &self.body {
+            Body::Leaf(text) => text.len() >= MIN_LEAF,
+            Body::Internal(children) => children.len() >= MIN_CHILDREN,
+        }
+    }
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node {
+            height: 0,
+            len: 0,
+            body: Body::Leaf(String::new()),
+        }
+    }
+}
+
+/// Split `text` at codepoint boundaries into about equal pieces of at most
+/// about `max` bytes.
+fn split_evenly(text: &str, max: usize) -> Vec<Range<usize>> {
+    let pieces = text.len().div_ceil(max).max(1);
+    let mut ranges = Vec::with_capacity(pieces);
+    let mut start = 0;
+    for i in 1..=pieces {
+        let mut end = text.len() * i / pieces;
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        if end > start {
+            ranges.push(start..end);
+            start = end;
+        }
+    }
+    if ranges.is_empty() {
+        ranges.push(0..0);
+    }
+    ranges
+}
+
+/// Join two trees into a balanced tree.
+fn concat(left: Arc<Node>, right: Arc<Node>) -> Arc<Node> {
+    if left.len == 0 {
+        return right;
+    }
+    if right.len == 0 {
+        return left;
+    }
+    match left.height.cmp(&right.height) {
+        Ordering::Less => {
+            let children = right.children();
+            if left.height == right.height - 1 && left.is_ok_child() {
+                return merge_nodes(&[left], children);
+            }
+            let merged = concat(left, children[0].clone());
+            if merged.height == right.height - 1 {
+                merge_nodes(&[merged], &children[1..])
+            } else {
+                merge_nodes(merged.children(), &children[1..])
+            }
+        }
+        Ordering::Equal => {
+            if left.is_ok_child() && right.is_ok_child() {
+                return Node::internal(vec![left, right]);
+            }
+            match (&left.body, &right.body) {
+                (Body::Leaf(a), Body::Leaf(b)) => merge_leaves(a, b),
+                _ => merge_nodes(left.children(), right.children()),
+            }
+        }
+        Ordering::Greater => {
+            let children = left.children();
+            if right.height == left.height - 1 && right.is_ok_child() {
+                return merge_nodes(children, &[right]);
+            }
+            let last = children.len() - 1;
+            let merged = concat(children[last].clone(), right);
+            if merged.height == left.height - 1 {
+                merge_nodes(&children[..last], &[merged])
+            } else {
+                merge_nodes(&children[..last], merged.children())
+            }
+        }
+    }
+}
+
+/// Make a node from two lists of children of the same height, splitting it
+/// in two if there are too many.
+fn merge_nodes(left: &[Arc<Node>], right: &[Arc<Node>]) -> Arc<Node> {
+    let mut children: Vec<_> = left.iter().chain(right).cloned().collect();
+    if children.len() <= MAX_CHILDREN {
+        Node::internal(children)
+    } else {
+        let split = MAX_CHILDREN.min(children.len() - MIN_CHILDREN);
+        let rest = children.split_off(split);
+        Node::internal(vec![Node::internal(children), Node::internal(rest)])
+    }
+}
+
+fn merge_leaves(left: &str, right: &str) -> Arc<Node> {
+    let text = [left, right].concat();
+    if text.len() <= MAX_LEAF {
+        Node::leaf(text)
+    } else {
+        let leaves = split_evenly(&text, text.len() / 2 + 1)
+            .into_iter()
+            .map(|range| Node::leaf(text[range].to_owned()))
+            .collect();
+        Node::internal(leaves)
+    }
+}
+
+/// Returns the tree for `range` of the text of `node`.
+fn subseq(node: &Arc<Node>, range: Range<usize>) -> Arc<Node> {
+    if range.start == 0 && range.end == node.len {
+        return node.clone();
+    }
+    match &node.body {
+        Body::Leaf(text) => Node::leaf(text[range].to_owned()),
+        Body::Internal(children) => {
+            let mut result = Arc::default();
+            let mut offset = 0;
+            for child in children {
+                let start = range.start.max(offset);
+                let end = range.end.min(offset + child.len);
+                if start < end {
+                    let piece = subseq(child, start - offset..end - offset);
+                    result = concat(result, piece);
+                }
+                offset += child.len;
+            }
+            result
+        }
+    }
+}
+
+impl EditableText for Rope {
+    /// Returns a cursor over the whole string, which is built if needed.
+    ///
+    /// Use [`rope_cursor`] to move through the text without building it.
+    ///
+    /// [`rope_cursor`]: struct.Rope.html#method.rope_cursor
+    fn cursor(&self, position: usize) -> Option<StringCursor> {
+        self.flat_string().cursor(position)
+    }
+
+    fn edit(&mut self, range: Range<usize>, new: impl Into<String>) {
+        let new = new.into();
+        assert!(
+            range.start <= range.end
+                && self.is_char_boundary(range.start)
+                && self.is_char_boundary(range.end),
+            "invalid range {:?} for rope of length {}",
+            range,
+            self.len()
+        );
+        if range.is_empty() && new.is_empty() {
+            return;
+        }
+        let left = subseq(&self.root, 0..range.start);
+        let right = subseq(&self.root, range.end..self.len());
+        self.root = concat(concat(left, Node::from_str(&new)), right);
+        self.flat = Default::default();
+    }
+
+    fn slice(&self, range: Range<usize>) -> Option<Cow<str>> {
+        if range.start > range.end
+            || !self.is_char_boundary(range.start)
+            || !self.is_char_boundary(range.end)
+        {
+            return None;
+        }
+        if let Some(flat) = self.flat.get() {
+            return Some(Cow::Borrowed(&flat[range]));
+        }
+        let (leaf, start) = self.leaf_at(range.start);
+        if range.end <= start + leaf.len() {
+            return Some(Cow::Borrowed(&leaf[range.start - start..range.end - start]));
+        }
+        let mut text = String::with_capacity(range.len());
+        let mut offset = range.start;
+        while offset < range.end {
+            let (leaf, start) = self.leaf_at(offset);
+            let end = range.end.min(start + leaf.len());
+            text.push_str(&leaf[offset - start..end - start]);
+            offset = end;
+        }
+        Some(Cow::Owned(text))
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn prev_word_offset(&self, from: usize) -> Option<usize> {
+        let mut offset = from;
+        let mut passed_alphanumeric = false;
+        while let Some(prev) = self.prev_grapheme_offset(offset) {
+            if self.char_at(prev)?.is_alphanumeric() {
+                passed_alphanumeric = true;
+            } else if passed_alphanumeric {
+                return Some(offset);
+            }
+            offset = prev;
+        }
+        None
+    }
+
+    fn next_word_offset(&self, from: usize) -> Option<usize> {
+        let mut offset = from;
+        let mut passed_alphanumeric = false;
+        while let Some(next) = self.next_grapheme_offset(offset) {
+            if self.char_at(offset)?.is_alphanumeric() {
+                passed_alphanumeric = true;
+            } else if passed_alphanumeric {
+                return Some(offset);
+            }
+            offset = next;
+        }
+        Some(self.len())
+    }
+
+    fn prev_grapheme_offset(&self, from: usize) -> Option<usize> {
+        if from == 0 || from > self.len() {
+            return None;
+        }
+        let mut cursor = GraphemeCursor::new(from, self.len(), true);
+        let (mut chunk, mut start) = self.leaf_at(from - 1);
+        loop {
+            match cursor.prev_boundary(chunk, start) {
+                Ok(offset) => return offset,
+                Err(GraphemeIncomplete::PrevChunk) => {
+                    let (prev, prev_start) = self.leaf_at(start - 1);
+                    chunk = prev;
+                    start = prev_start;
+                }
+                Err(GraphemeIncomplete::PreContext(end)) => {
+                    let (context, context_start) = self.leaf_at(end - 1);
+                    cursor.provide_context(&context[..end - context_start], context_start);
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn next_grapheme_offset(&self, from: usize) -> Option<usize> {
+        if from > self.len() {
+            return None;
+        }
+        let mut cursor = GraphemeCursor::new(from, self.len(), true);
+        let (mut chunk, mut start) = self.leaf_at(from);
+        loop {
+            match cursor.next_boundary(chunk, start) {
+                Ok(offset) => return offset,
+                Err(GraphemeIncomplete::NextChunk) => {
+                    let (next, next_start) = self.leaf_at(start + chunk.len());
+                    chunk = next;
+                    start = next_start;
+                }
+                Err(GraphemeIncomplete::PreContext(end)) => {
+                    let (context, context_start) = self.leaf_at(end - 1);
+                    cursor.provide_context(&context[..end - context_start], context_start);
+                }
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn prev_codepoint_offset(&self, from: usize) -> Option<usize> {
+        self.rope_cursor(from)?.prev()
+    }
+
+    fn next_codepoint_offset(&self, from: usize) -> Option<usize> {
+        let mut cursor = self.rope_cursor(from)?;
+        cursor.next().map(|_| cursor.pos())
+    }
+
+    fn preceding_line_break(&self, from: usize) -> usize {
+        let mut offset = from.min(self.len());
+        while offset > 0 {
+            let (leaf, start) = self.leaf_at(offset - 1);
+            if let Some(i) = leaf.as_bytes()[..offset - start]
+                .iter()
+                .rposition(|b| *b == b'\n')
+            {
+                return start + i + 1;
+            }
+            offset = start;
+        }
+        0
+    }
+
+    fn next_line_break(&self, from: usize) -> usize {
+        let mut offset = from;
+        while offset < self.len() {
+            let (leaf, start) = self.leaf_at(offset);
+            if let Some(i) = leaf.as_bytes()[offset - start..]
+                .iter()
+                .position(|b| *b == b'\n')
+            {
+                return offset + i;
+            }
+            offset = start + leaf.len();
+        }
+        self.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn is_char_boundary(&self, offset: usize) -> bool {
+        Rope::is_char_boundary(self, offset)
+    }
+
+    fn from_str(s: &str) -> Self {
+        Rope::from(s)
+    }
+}
+
+impl<'a> EditableTextCursor<&'a Rope> for RopeCursor<'a> {
+    fn set(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    fn pos(&self) -> usize {
+        self.position
+    }
+
+    fn is_boundary(&self) -> bool {
+        self.rope.is_char_boundary(self.position)
+    }
+
+    fn prev(&mut self) -> Option<usize> {
+        if self.position == 0 {
+            return None;
+        }
+        let mut len = 1;
+        while !self.rope.is_char_boundary(self.position - len) {
+            len += 1;
+        }
+        self.position -= len;
+        Some(self.position)
+    }
+
+    fn next(&mut self) -> Option<usize> {
+        let current_pos = self.position;
+        if current_pos >= self.rope.len() {
+            return None;
+        }
+        let (leaf, start) = self.rope.leaf_at(current_pos);
+        let b = leaf.as_bytes()[current_pos - start];
+        self.position += len_utf8_from_first_byte(b);
+        Some(current_pos)
+    }
+
+    fn peek_next_codepoint(&self) -> Option<char> {
+        self.rope.char_at(self.position)
+    }
+
+    fn prev_codepoint(&mut self) -> Option<char> {
+        let prev = self.prev()?;
+        self.rope.char_at(prev)
+    }
+
+    fn next_codepoint(&mut self) -> Option<char> {
+        let c = self.rope.char_at(self.position)?;
+        self.next();
+        Some(c)
+    }
+
+    fn at_or_next(&mut self) -> Option<usize> {
+        if self.is_boundary() {
+            Some(self.pos())
+        } else {
+            self.next()
+        }
+    }
+
+    fn at_or_prev(&mut self) -> Option<usize> {
+        if self.is_boundary() {
+            Some(self.pos())
+        } else {
+            self.prev()
+        }
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while let Some(node) = self.stack.pop() {
+            match &node.body {
+                Body::Leaf(text) if !text.is_empty() => return Some(text),
+                Body::Leaf(_) => {}
+                Body::Internal(children) => self.stack.extend(children.iter().rev().map(|c| &**c)),
+            }
+        }
+        None
+    }
+}
+
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Rope {
+            root: Node::from_str(text),
+            flat: Default::default(),
+        }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Self {
+        Rope::from(text.as_str())
+    }
+}
+
+impl PartialEq for Rope {
+    fn eq(&self, other: &Rope) -> bool {
+        self.same(other)
+            || (self.len() == other.len()
+                && self
+                    .chunks()
+                    .flat_map(str::bytes)
+                    .eq(other.chunks().flat_map(str::bytes)))
+    }
+}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        let mut rest = other.as_bytes();
+        self.len() == other.len()
+            && self.chunks().all(|chunk| {
+                let (head, tail) = rest.split_at(chunk.len());
+                rest = tail;
+                head == chunk.as_bytes()
+            })
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Rope").field(&self.to_string()).finish()
+    }
+}
+
+impl Data for Rope {
+    fn same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+}
+
+impl PietTextStorage for Rope {
+    fn as_str(&self) -> &str {
+        self.flat_string()
+    }
+}
+
+impl TextStorage for Rope {
+    fn text_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::{offset_for_delete_backwards, Selection};
+
+    /// Text long enough to span many leaves, with multi-byte codepoints and
+    /// graphemes.
+    fn long_text() -> String {
+        (0..2000)
+            .map(|i| format!("line {} caf\u{0065}\u{0301} \u{1F1FA}\u{1F1F8}\n", i))
+            .collect()
+    }
+
+    fn check_balanced(node: &Node) {
+        for child in node.children() {
+            assert_eq!(child.height + 1, node.height);
+            check_balanced(child);
+        }
+    }
+
+    #[test]
+    fn editing_does_not_build_string() {
+        let text = long_text();
+        let original = Rope::from(text.as_str());
+        let mut rope = original.clone();
+        rope.edit(0..4, "row");
+
+        let selection = Selection::new(3, rope.len() + 5).constrained(&rope);
+        assert_eq!(selection.range(), 3..rope.len());
+        let caret = Selection::caret(3);
+        assert_eq!(offset_for_delete_backwards(&caret, &rope), 2);
+        assert!(!rope.text_eq(&original));
+        assert!(rope.text_eq(&Rope::from(format!("row{}", &text[4..]))));
+        assert!(rope == format!("row{}", &text[4..]).as_str());
+        assert!(rope.flat.get().is_none());
+    }
+
+    #[test]
+    fn edits_match_string() {
+        let mut string = long_text();
+        let mut rope = Rope::from(string.as_str());
+        let mut seed = 7usize;
+        for i in 0..300 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345) % (1 << 31);
+            let mut start = seed % (string.len() + 1);
+            while !string.is_char_boundary(start) {
+                start -= 1;
+            }
+            let mut end = (start + seed % 3000).min(string.len());
+            while !string.is_char_boundary(end) {
+                end -= 1;
+            }
+            let inserted = "x\u{00E9}\n".repeat(i % 7 * (seed % 50));
+            string.edit(start..end, inserted.as_str());
+            rope.edit(start..end, inserted.as_str());
+            assert_eq!(rope.len(), string.len());
+        }
+        check_balanced(&rope.root);
+        assert_eq!(rope.to_string(), string);
+        assert_eq!(rope.as_str(), string);
+    }
+
+    #[test]
+    fn clones_share_chunks() {
+        let mut rope = Rope::from(long_text());
+        let copy = rope.clone();
+        assert!(copy.same(&rope));
+        rope.edit(5..13, "");
+        assert!(!copy.same(&rope));
+        assert_eq!(copy.to_string(), long_text());
+        assert_eq!(rope.slice(0..5), copy.slice(0..5));
+    }
+
+    #[test]
+    fn movement_matches_string() {
+        let string = long_text();
+        let rope = Rope::from(string.as_str());
+        // offsets around every leaf boundary, and some others
+        let mut offsets: Vec<usize> = (0..string.len()).step_by(97).collect();
+        let mut start = 0;
+        for chunk in rope.chunks() {
+            start += chunk.len();
+            offsets.extend(start.saturating_sub(8)..(start + 8).min(string.len()));
+        }
+        for offset in offsets {
+            if !string.is_char_boundary(offset) {
+                assert!(rope.rope_cursor(offset).is_none());
+                assert_eq!(rope.prev_codepoint_offset(offset), None);
+                assert_eq!(rope.next_codepoint_offset(offset), None);
+                continue;
+            }
+            assert_eq!(
+                rope.prev_grapheme_offset(offset),
+                string.prev_grapheme_offset(offset)
+            );
+            assert_eq!(
+                rope.next_grapheme_offset(offset),
+                string.next_grapheme_offset(offset)
+            );
+            assert_eq!(
+                rope.prev_codepoint_offset(offset),
+                string.prev_codepoint_offset(offset)
+            );
+            assert_eq!(
+                rope.next_codepoint_offset(offset),
+                string.next_codepoint_offset(offset)
+            );
+            assert_eq!(
+                rope.prev_word_offset(offset),
+                string.prev_word_offset(offset)
+            );
+            assert_eq!(
+                rope.next_word_offset(offset),
+                string.next_word_offset(offset)
+            );
+            assert_eq!(
+                rope.preceding_line_break(offset),
+                string.preceding_line_break(offset)
+            );
+            assert_eq!(rope.next_line_break(offset), string.next_line_break(offset));
+        }
+    }
+
+    #[test]
+    fn cursor_codepoints() {
+        let rope = Rope::from("$¢€£💶");
+        let mut cursor = rope.rope_cursor(0).unwrap();
+        assert_eq!(cursor.next_codepoint(), Some('$'));
+        assert_eq!(cursor.peek_next_codepoint(), Some('¢'));
+        assert_eq!(cursor.next_codepoint(), Some('¢'));
+        assert_eq!(cursor.prev_codepoint(), Some('¢'));
+        cursor.set(rope.len());
+        assert_eq!(cursor.next_codepoint(), None);
+        assert_eq!(cursor.prev_codepoint(), Some('💶'));
+        assert_eq!(cursor.pos(), rope.len() - 4);
+        assert!(rope.rope_cursor(2).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! Searching text.
+
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+/// What to search for with the find methods of [`EditableText`].
+///
+/// By default the pattern is matched literally and case-sensitively,
+/// anywhere in the text.
+///
+/// [`EditableText`]: trait.EditableText.html
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pattern: String,
+    case_insensitive: bool,
+    whole_word: bool,
+    regex: bool,
+    /// The compiled pattern, or `None` if it is empty or invalid.
+    matcher: Option<Regex>,
+    error: Option<String>,
+}
+
+impl SearchQuery {
+    /// Create a new `SearchQuery` for `pattern`.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        let mut query = SearchQuery {
+            pattern: pattern.into(),
+            case_insensitive: false,
+            whole_word: false,
+            regex: false,
+            matcher: None,
+            error: None,
+        };
+        query.compile();
+        query
+    }
+
+    /// Builder-style method to ignore case when matching.
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self.compile();
+        self
+    }
+
+    /// Builder-style method to only match whole words.
+    pub fn whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self.compile();
+        self
+    }
+
+    /// Builder-style method to treat the pattern as a regular expression.
+    pub fn regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self.compile();
+        self
+    }
+
+    /// The text or regular expression that is searched for.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns `true` if case is ignored when matching.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Returns `true` if only whole words are matched.
+    pub fn is_whole_word(&self) -> bool {
+        self.whole_word
+    }
+
+    /// Returns `true` if the pattern is a regular expression.
+    pub fn is_regex(&self) -> bool {
+        self.regex
+    }
+
+    /// The reason the pattern is not a valid regular expression, if it isn't.
+    ///
+    /// An invalid query matches nothing.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Returns the ranges of all matches in `text`, in order.
+    ///
+    /// Empty matches are skipped.
+    pub fn find_iter<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.matcher
+            .iter()
+            .flat_map(move |matcher| matcher.find_iter(text))
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+    }
+
+    /// Returns the text that replaces the match at `range` of `text`.
+    ///
+    /// For a regular expression, `$1` or `${name}` in `replacement` are
+    /// replaced with the text of the capture group; otherwise `replacement`
+    /// is used as is.
+    pub fn expand(&self, text: &str, range: Range<usize>, replacement: &str) -> String {
+        let captures = match &self.matcher {
+            Some(matcher) if self.regex => matcher.captures_at(text, range.start),
+            _ => None,
+        };
+        match captures {
+            Some(captures) if captures.get(0).map(|found| found.range()) == Some(range) => {
+                let mut expanded = String::new();
+                captures.expand(replacement, &mut expanded);
+                expanded
+            }
+            _ => replacement.to_owned(),
+        }
+    }
+
+    fn compile(&mut self) {
+        self.matcher = None;
+        self.error = None;
+        if self.pattern.is_empty() {
+            return;
+        }
+        let mut pattern = if self.regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+        match RegexBuilder::new(&pattern)
+            .case_insensitive(self.case_insensitive)
+            .multi_line(true)
+            .build()
+        {
+            Ok(matcher) => self.matcher = Some(matcher),
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::EditableText;
+
+    #[test]
+    fn find_with_options() {
+        let text = "Cat cat concat\ncat".to_string();
+        let query = SearchQuery::new("cat");
+        assert_eq!(text.find_all(&query), vec![4..7, 11..14, 15..18]);
+        assert_eq!(text.find_next(&query, 5), Some(11..14));
+        assert_eq!(text.find_prev(&query, 11), Some(4..7));
+        assert_eq!(text.find_prev(&query, 4), None);
+
+        let query = query.case_insensitive(true).whole_word(true);
+        assert_eq!(text.find_all(&query), vec![0..3, 4..7, 15..18]);
+        // a shorter alternative that isn't a whole word doesn't hide a
+        // longer one that is
+        let query = SearchQuery::new("ab|abc").regex(true).whole_word(true);
+        assert_eq!("ab abc".to_string().find_all(&query), vec![0..2, 3..6]);
+
+        let query = SearchQuery::new(r"^c\w+").regex(true);
+        assert_eq!(text.find_all(&query), vec![15..18]);
+        let query = SearchQuery::new("c[").regex(true);
+        assert!(query.error().is_some());
+        assert!(text.find_all(&query).is_empty());
+        assert!(text.find_all(&SearchQuery::new("")).is_empty());
+    }
+
+    #[test]
+    fn expand_captures() {
+        let text = "key = value";
+        let query = SearchQuery::new(r"(\w+) = (\w+)").regex(true);
+        assert_eq!(query.expand(text, 0..11, "$2: $1"), "value: key");
+        let literal = SearchQuery::new("key");
+        assert_eq!(literal.expand(text, 0..3, "$1"), "$1");
+    }
+}
@@ -32,6 +32,26 @@ pub struct Selection {
 
     /// The saved horizontal position, during vertical movement.
     pub h_pos: Option<f64>,
+
+    /// Which side of the `end` the caret is drawn on, where that offset is
+    /// at two places on screen, such as between runs of text in different
+    /// directions.
+    pub affinity: Affinity,
+}
+
+/// The text that a caret is attached to, where its offset is at two places
+/// on screen.
+///
+/// With bidirectional text, the offset between a left-to-right and a
+/// right-to-left run is at the end of one run and the start of the other,
+/// which may be far apart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Affinity {
+    /// The caret is attached to the text before its offset.
+    Upstream,
+    /// The caret is attached to the text after its offset.
+    #[default]
+    Downstream,
 }
 
 impl Selection {
@@ -42,6 +62,7 @@ impl Selection {
             start,
             end,
             h_pos: None,
+            affinity: Affinity::Downstream,
         }
     }
 
@@ -52,10 +73,10 @@ impl Selection {
         let s_len = s.len();
         self.start = min(self.start, s_len);
         self.end = min(self.end, s_len);
-        while s.cursor(self.start).is_none() {
+        while !s.is_char_boundary(self.start) {
             self.start += 1;
         }
-        while s.cursor(self.end).is_none() {
+        while !s.is_char_boundary(self.end) {
             self.end += 1;
         }
         self
@@ -67,6 +88,7 @@ impl Selection {
             start: pos,
             end: pos,
             h_pos: None,
+            affinity: Affinity::Downstream,
         }
     }
 
@@ -76,6 +98,12 @@ impl Selection {
         self
     }
 
+    /// Construct a new selection from this selection, with the provided affinity.
+    pub fn with_affinity(mut self, affinity: Affinity) -> Self {
+        self.affinity = affinity;
+        self
+    }
+
     /// If start == end, it's a caret
     pub fn is_caret(self) -> bool {
         self.start == self.end
@@ -0,0 +1,254 @@
+//! Spell checking for edited text.
+
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::lines::Lines;
+use super::EditableText;
+
+/// The largest edit distance of the suggestions for a misspelled word.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+/// The number of suggestions offered for a misspelled word.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// A type that checks the spelling of words.
+///
+/// Text is checked one paragraph at a time, so that an [`Editor`] only has
+/// to check the paragraphs changed by each edit.
+///
+/// [`Editor`]: struct.Editor.html
+pub trait SpellChecker {
+    /// Returns `true` if `word` is spelled correctly.
+    fn is_correct(&self, word: &str) -> bool;
+
+    /// Returns replacements for a misspelled `word`, with the most likely
+    /// first.
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let _ = word;
+        Vec::new()
+    }
+
+    /// Returns the ranges of the misspelled words in a paragraph of text.
+    ///
+    /// Words are found with Unicode word boundaries; words without letters,
+    /// or with digits, are not checked.
+    fn check_paragraph(&self, paragraph: &str) -> Vec<Range<usize>> {
+        paragraph
+            .split_word_bound_indices()
+            .filter(|(_, word)| {
+                word.chars().any(char::is_alphabetic)
+                    && !word.chars().any(|c| c.is_numeric())
+                    && !self.is_correct(word)
+            })
+            .map(|(start, word)| start..start + word.len())
+            .collect()
+    }
+}
+
+/// A [`SpellChecker`] that knows a list of words.
+///
+/// Words are compared ignoring case, and suggestions are the known words
+/// that are the fewest edits away from a misspelled word.
+///
+/// [`SpellChecker`]: trait.SpellChecker.html
+#[derive(Debug, Clone, Default)]
+pub struct WordList {
+    /// The known words, in lower case.
+    words: HashSet<String>,
+}
+
+impl WordList {
+    /// Create a new `WordList` that knows `words`.
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = S>) -> Self {
+        WordList {
+            words: words
+                .into_iter()
+                .map(|word| word.as_ref().trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Load a `WordList` from a file with one word on each line.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(WordList::new(contents.lines()))
+    }
+
+    /// Add a word, such as one the user chose to keep.
+    pub fn add(&mut self, word: &str) {
+        self.words.insert(word.to_lowercase());
+    }
+
+    /// Returns the number of known words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if no words are known.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl SpellChecker for WordList {
+    fn is_correct(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let len = lower.chars().count();
+        let mut found: Vec<(usize, &String)> = self
+            .words
+            .iter()
+            .filter(|known| {
+                let known_len = known.chars().count();
+                known_len.max(len) - known_len.min(len) <= MAX_SUGGESTION_DISTANCE
+            })
+            .map(|known| (edit_distance(&lower, known), known))
+            .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .collect();
+        found.sort();
+        let capitalized = word.chars().next().is_some_and(char::is_uppercase);
+        found
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, known)| {
+                if capitalized {
+                    let mut chars = known.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                } else {
+                    known.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+/// The number of characters that have to be inserted, deleted or replaced
+/// to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // the distances from the start of `a` so far to each start of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + (a_char != *b_char) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The misspelled words of each paragraph of some text, which are kept up
+/// to date as the text is edited.
+#[derive(Clone)]
+pub(crate) struct SpellCheck {
+    checker: Arc<dyn SpellChecker>,
+    paragraphs: Lines<Vec<Range<usize>>>,
+}
+
+impl SpellCheck {
+    pub fn new(checker: Arc<dyn SpellChecker>, text: &str) -> Self {
+        let paragraphs = Lines::new(text, |paragraph| checker.check_paragraph(paragraph));
+        SpellCheck {
+            checker,
+            paragraphs,
+        }
+    }
+
+    pub fn checker(&self) -> &Arc<dyn SpellChecker> {
+        &self.checker
+    }
+
+    /// Check all of `text`.
+    pub fn set_text(&mut self, text: &str) {
+        *self = SpellCheck::new(self.checker.clone(), text);
+    }
+
+    /// Update the misspelled words after `range` of the checked text was
+    /// replaced with `inserted` bytes, checking only the changed paragraphs.
+    /// `text` is the text after the edit.
+    pub fn edit<T: EditableText>(&mut self, text: &T, range: Range<usize>, inserted: usize) {
+        let checker = &self.checker;
+        self.paragraphs.edit(text, range, inserted, |paragraph| {
+            checker.check_paragraph(paragraph)
+        });
+    }
+
+    /// The ranges of the misspelled words in the paragraphs that have any
+    /// of `range`.
+    pub fn misspelled(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        self.paragraphs
+            .in_range(range)
+            .flat_map(|(offset, words)| {
+                words
+                    .iter()
+                    .map(move |word| word.start + offset..word.end + offset)
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for SpellCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SpellCheck")
+            .field("paragraphs", &self.paragraphs.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_list() {
+        let words = WordList::new(vec!["the", "quick", "brown", "fox", "quack", "box"]);
+        assert_eq!(
+            words.check_paragraph("The quikc brown fox, 2nd fxo."),
+            vec![4..9, 25..28]
+        );
+        assert_eq!(words.suggestions("Quikc"), vec!["Quick"]);
+        assert_eq!(words.suggestions("bix"), vec!["box", "fox"]);
+        assert!(words.suggestions("elephant").is_empty());
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn incremental_edits() {
+        let words: Arc<dyn SpellChecker> = Arc::new(WordList::new(vec!["one", "two", "three"]));
+        let mut text = "one twoo\nthree\nfuor".to_string();
+        let mut check = SpellCheck::new(words.clone(), &text);
+        let all = 0..usize::MAX;
+        assert_eq!(check.misspelled(all.clone()), vec![4..8, 15..19]);
+        // only the paragraphs in the range are collected
+        assert_eq!(check.misspelled(10..12), Vec::<Range<usize>>::new());
+        assert_eq!(check.misspelled(12..16), vec![15..19]);
+
+        text.edit(7..8, "\n");
+        check.edit(&text, 7..8, 1);
+        assert_eq!(text, "one two\n\nthree\nfuor");
+        assert_eq!(check.misspelled(all.clone()), vec![15..19]);
+        text.edit(15..19, "three twoo");
+        check.edit(&text, 15..19, 10);
+        assert_eq!(
+            check.misspelled(all.clone()),
+            SpellCheck::new(words, &text).misspelled(all.clone())
+        );
+        assert_eq!(check.misspelled(all), vec![21..25]);
+    }
+}
@@ -16,6 +16,7 @@
 
 use std::sync::Arc;
 
+use super::{AttributeSpans, Fonts};
 use crate::piet::{PietTextLayoutBuilder, TextStorage as PietTextStorage};
 use druid::{Data, Env};
 
@@ -23,10 +24,36 @@ use druid::{Data, Env};
 pub trait TextStorage: PietTextStorage + Data {
     /// If this TextStorage object manages style spans, it should implement
     /// this method and update the provided builder with its spans, as required.
+    ///
+    /// Font families are resolved with `fonts`, the fonts of the app.
     #[allow(unused_variables)]
-    fn add_attributes(&self, builder: PietTextLayoutBuilder, env: &Env) -> PietTextLayoutBuilder {
+    fn add_attributes(
+        &self,
+        builder: PietTextLayoutBuilder,
+        fonts: &Fonts,
+        env: &Env,
+    ) -> PietTextLayoutBuilder {
         builder
     }
+
+    /// If this TextStorage object manages style spans, it should return them
+    /// here, so that attributes piet does not support can be drawn by the
+    /// [`TextLayout`].
+    ///
+    /// [`TextLayout`]: struct.TextLayout.html
+    fn attributes(&self) -> Option<&AttributeSpans> {
+        None
+    }
+
+    /// Returns `true` if this is the same text as `other`.
+    ///
+    /// Types that build their string when it is needed, such as [`Rope`],
+    /// should compare their text without building it.
+    ///
+    /// [`Rope`]: struct.Rope.html
+    fn text_eq(&self, other: &Self) -> bool {
+        self.same(other) || self.as_str() == other.as_str()
+    }
 }
 
 /// A reference counted string slice.
@@ -14,6 +14,8 @@
 
 //! Map input to `EditAction`s
 
+use std::ops::Range;
+
 use super::Movement;
 use druid::{HotKey, KbKey, KeyEvent, Modifiers, SysMods};
 
@@ -37,6 +39,44 @@ pub enum EditAction {
     JumpBackspace(Movement),
     Insert(String),
     Paste(String),
+    Undo,
+    Redo,
+    /// Copy the selection to the clipboard.
+    Copy,
+    /// Collapse the selection to a caret at its start, or at its active end.
+    CollapseSelection {
+        to_start: bool,
+    },
+    /// Delete from the cursor to the result of the movement, adding the
+    /// deleted text to the kill ring.
+    Kill(Movement),
+    /// Delete the selection, adding it to the kill ring.
+    KillSelection,
+    /// Add the selection to the kill ring without deleting it.
+    CopyToKillRing,
+    /// Insert the most recent kill.
+    Yank,
+    /// Replace the text inserted by the previous `Yank` or `YankPop` with
+    /// the kill before it.
+    YankPop,
+    /// Add a cursor on the line above the primary cursor, which becomes
+    /// the primary cursor.
+    AddCursorAbove,
+    /// Add a cursor on the line below the primary cursor, which becomes
+    /// the primary cursor.
+    AddCursorBelow,
+    /// Select the next occurrence of the text of the primary selection,
+    /// which becomes the primary selection.
+    ///
+    /// If the primary selection is a caret, this selects the word around it.
+    AddNextOccurrence,
+    /// Remove all but the primary selection.
+    RemoveSecondaryCursors,
+    /// Replace each range with its text, as a single undoable edit, and
+    /// place the cursor after the last replacement.
+    ///
+    /// The ranges must not overlap.
+    Replace(Vec<(Range<usize>, String)>),
 }
 
 /// Extra information related to mouse actions
@@ -46,29 +86,73 @@ pub struct MouseAction {
     pub row: usize,
     pub column: usize,
     pub mods: Modifiers,
+    /// The number of consecutive clicks; 2 for a double click.
+    pub count: u8,
+}
+
+/// The shape of the text cursor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CursorShape {
+    /// A line between two characters.
+    Line,
+    /// A block covering the character after the cursor position.
+    Block,
 }
 
 /// A trait for types that map keyboard events to possible edit actions.
+///
+/// A `TextInput` can keep state between events, such as a mode or a
+/// command that is waiting for more keys.
 pub trait TextInput {
-    /// Handle a key event and return an edit action to be executed
-    /// for the key event
-    fn handle_event(&self, event: &KeyEvent) -> Option<EditAction>;
+    /// Handle a key event and return the edit actions to be executed
+    /// for the key event, in order.
+    ///
+    /// Only the last action may change the text; the editor skips actions
+    /// that follow an edit, as they would see the text from before it.
+    fn handle_event(&mut self, event: &KeyEvent) -> Vec<EditAction>;
+
+    /// Returns the shape of the cursor to draw.
+    fn cursor_shape(&self) -> CursorShape {
+        CursorShape::Line
+    }
+
+    /// Set the height of the visible text, by which page movements move.
+    ///
+    /// Widgets call this whenever their size changes.
+    #[allow(unused_variables)]
+    fn set_viewport_height(&mut self, height: f64) {}
+
+    /// Set whether there are selections other than the primary selection,
+    /// which some keys remove.
+    ///
+    /// Widgets call this before each key event.
+    #[allow(unused_variables)]
+    fn set_secondary_cursors(&mut self, secondary: bool) {}
+
+    /// Called when the user clicks in the text, which moves the selection
+    /// without going through [`handle_event`].
+    ///
+    /// [`handle_event`]: TextInput::handle_event
+    fn handle_click(&mut self) {}
 }
 
 /// Handles key events and returns actions that are applicable to
 /// single line textboxes
 #[derive(Default, Debug, Clone)]
-pub struct BasicTextInput;
+pub struct BasicTextInput {
+    viewport_height: f64,
+    /// Whether there are secondary cursors, for Escape to remove.
+    secondary_cursors: bool,
+}
 
 impl BasicTextInput {
     /// Create a new `BasicTextInput`.
     pub fn new() -> Self {
-        Self
+        Self::default()
     }
-}
 
-impl TextInput for BasicTextInput {
-    fn handle_event(&self, event: &KeyEvent) -> Option<EditAction> {
+    /// Returns the edit action for a key event, if there is one.
+    pub fn action_for_event(&self, event: &KeyEvent) -> Option<EditAction> {
         let action = match event {
             // Select left word (Shift+Ctrl+ArrowLeft || Shift+Cmd+ArrowLeft)
             k_e if (HotKey::new(SysMods::CmdShift, KbKey::ArrowLeft)).matches(k_e) => {
@@ -94,6 +178,22 @@ impl TextInput for BasicTextInput {
             k_e if (HotKey::new(SysMods::Shift, KbKey::ArrowRight)).matches(k_e) => {
                 EditAction::ModifySelection(Movement::Right)
             }
+            // Redo (Shift+Ctrl+Z || Shift+Cmd+Z)
+            k_e if (HotKey::new(SysMods::CmdShift, "Z")).matches(k_e)
+                || (HotKey::new(SysMods::CmdShift, "z")).matches(k_e) =>
+            {
+                EditAction::Redo
+            }
+            // Undo (Ctrl+Z || Cmd+Z)
+            k_e if (HotKey::new(SysMods::Cmd, "z")).matches(k_e) => EditAction::Undo,
+            // Redo (Ctrl+Y || Cmd+Y)
+            k_e if (HotKey::new(SysMods::Cmd, "y")).matches(k_e) => EditAction::Redo,
+            // Add the next occurrence of the selection (Ctrl+D || Cmd+D)
+            k_e if (HotKey::new(SysMods::Cmd, "d")).matches(k_e) => EditAction::AddNextOccurrence,
+            // Remove the secondary cursors (Escape)
+            k_e if self.secondary_cursors && (HotKey::new(None, KbKey::Escape)).matches(k_e) => {
+                EditAction::RemoveSecondaryCursors
+            }
             // Select all (Ctrl+A || Cmd+A)
             k_e if (HotKey::new(SysMods::Cmd, "a")).matches(k_e) => EditAction::SelectAll,
             // Left word (Ctrl+ArrowLeft || Cmd+ArrowLeft)
@@ -124,6 +224,46 @@ impl TextInput for BasicTextInput {
             k_e if (HotKey::new(SysMods::Shift, KbKey::ArrowDown)).matches(k_e) => {
                 EditAction::ModifySelection(Movement::Down)
             }
+            // Add a cursor above or below (Ctrl+Alt+ArrowUp/Down || Cmd+Alt+ArrowUp/Down)
+            k_e if (HotKey::new(SysMods::AltCmd, KbKey::ArrowUp)).matches(k_e) => {
+                EditAction::AddCursorAbove
+            }
+            k_e if (HotKey::new(SysMods::AltCmd, KbKey::ArrowDown)).matches(k_e) => {
+                EditAction::AddCursorBelow
+            }
+            // Paragraph start and end (Ctrl+ArrowUp/Down || Cmd+ArrowUp/Down)
+            k_e if (HotKey::new(SysMods::Cmd, KbKey::ArrowUp)).matches(k_e) => {
+                EditAction::Move(Movement::StartOfParagraph)
+            }
+            k_e if (HotKey::new(SysMods::Cmd, KbKey::ArrowDown)).matches(k_e) => {
+                EditAction::Move(Movement::EndOfParagraph)
+            }
+            k_e if (HotKey::new(SysMods::CmdShift, KbKey::ArrowUp)).matches(k_e) => {
+                EditAction::ModifySelection(Movement::StartOfParagraph)
+            }
+            k_e if (HotKey::new(SysMods::CmdShift, KbKey::ArrowDown)).matches(k_e) => {
+                EditAction::ModifySelection(Movement::EndOfParagraph)
+            }
+            // Page up and down
+            k_e if (HotKey::new(None, KbKey::PageUp)).matches(k_e) => {
+                EditAction::Move(Movement::PageUp(self.viewport_height))
+            }
+            k_e if (HotKey::new(None, KbKey::PageDown)).matches(k_e) => {
+                EditAction::Move(Movement::PageDown(self.viewport_height))
+            }
+            k_e if (HotKey::new(SysMods::Shift, KbKey::PageUp)).matches(k_e) => {
+                EditAction::ModifySelection(Movement::PageUp(self.viewport_height))
+            }
+            k_e if (HotKey::new(SysMods::Shift, KbKey::PageDown)).matches(k_e) => {
+                EditAction::ModifySelection(Movement::PageDown(self.viewport_height))
+            }
+            // Delete to the start or end of the paragraph
+            k_e if (HotKey::new(SysMods::CmdShift, KbKey::Backspace)).matches(k_e) => {
+                EditAction::JumpBackspace(Movement::StartOfParagraph)
+            }
+            k_e if (HotKey::new(SysMods::CmdShift, KbKey::Delete)).matches(k_e) => {
+                EditAction::JumpDelete(Movement::EndOfParagraph)
+            }
             // Delete left word
             k_e if (HotKey::new(SysMods::Cmd, KbKey::Backspace)).matches(k_e) => {
                 EditAction::JumpBackspace(Movement::LeftWord)
@@ -155,6 +295,20 @@ impl TextInput for BasicTextInput {
     }
 }
 
+impl TextInput for BasicTextInput {
+    fn handle_event(&mut self, event: &KeyEvent) -> Vec<EditAction> {
+        self.action_for_event(event).into_iter().collect()
+    }
+
+    fn set_viewport_height(&mut self, height: f64) {
+        self.viewport_height = height;
+    }
+
+    fn set_secondary_cursors(&mut self, secondary: bool) {
+        self.secondary_cursors = secondary;
+    }
+}
+
 /// Determine whether a keyboard event contains insertable text.
 fn string_from_key(event: &KeyEvent) -> Option<String> {
     match &event.key {
@@ -167,3 +321,22 @@ fn string_from_key(event: &KeyEvent) -> Option<String> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::Modifiers;
+
+    #[test]
+    fn escape_removes_secondary_cursors() {
+        let mut input = BasicTextInput::new();
+        let escape = KeyEvent::for_test(Modifiers::empty(), KbKey::Escape);
+        // escape is left to other widgets when there is one cursor
+        assert!(input.handle_event(&escape).is_empty());
+        input.set_secondary_cursors(true);
+        assert_eq!(
+            input.handle_event(&escape),
+            vec![EditAction::RemoveSecondaryCursors]
+        );
+    }
+}
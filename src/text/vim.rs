@@ -0,0 +1,383 @@
+//! A vim-style modal `TextInput`.
+
+use super::{BasicTextInput, CursorShape, EditAction, Movement, TextInput};
+use druid::{KbKey, KeyEvent};
+
+/// The mode of a [`VimTextInput`].
+///
+/// [`VimTextInput`]: struct.VimTextInput.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VimMode {
+    /// Keys are commands, and the cursor is a block.
+    Normal,
+    /// Keys insert text, as with a [`BasicTextInput`].
+    ///
+    /// [`BasicTextInput`]: struct.BasicTextInput.html
+    Insert,
+    /// Motions extend the selection, and operators apply to it.
+    Visual,
+}
+
+/// An operator that is waiting for a motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+}
+
+/// A [`TextInput`] with vim-style normal, insert and visual modes.
+///
+/// Normal mode supports the motions `h`, `j`, `k`, `l`, `w`, `b`, `e`, `0`,
+/// `$`, `gg`, `G`, `(`, `)`, `{` and `}`, the operators `d`, `c` and `y`
+/// followed by a motion or doubled to act on the whole line, and the
+/// commands `i`, `a`, `I`, `A`, `o`, `x`, `X`, `u`, `Ctrl+r` and `v`.
+/// Yanked text is copied to the clipboard.
+///
+/// [`TextInput`]: trait.TextInput.html
+#[derive(Debug, Clone)]
+pub struct VimTextInput {
+    mode: VimMode,
+    /// The operator waiting for a motion.
+    operator: Option<Operator>,
+    /// Whether `g` was typed, as the start of `gg`.
+    pending_g: bool,
+    viewport_height: f64,
+    basic: BasicTextInput,
+}
+
+impl VimTextInput {
+    /// Create a new `VimTextInput`, in normal mode.
+    pub fn new() -> Self {
+        VimTextInput {
+            mode: VimMode::Normal,
+            operator: None,
+            pending_g: false,
+            viewport_height: 0.0,
+            basic: BasicTextInput::new(),
+        }
+    }
+
+    /// Returns the current mode.
+    pub fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Returns the motion for a key, if it is one.
+    fn motion(&self, key: &KbKey) -> Option<Movement> {
+        let movement = match key {
+            KbKey::ArrowLeft => Movement::Left,
+            KbKey::ArrowRight => Movement::Right,
+            KbKey::ArrowUp => Movement::Up,
+            KbKey::ArrowDown => Movement::Down,
+            KbKey::Home => Movement::PrecedingLineBreak,
+            KbKey::End => Movement::NextLineBreak,
+            KbKey::PageUp => Movement::PageUp(self.viewport_height),
+            KbKey::PageDown => Movement::PageDown(self.viewport_height),
+            KbKey::Character(c) => match c.as_str() {
+                "h" => Movement::Left,
+                "l" => Movement::Right,
+                "k" => Movement::Up,
+                "j" => Movement::Down,
+                "w" => Movement::RightWordStart,
+                "b" => Movement::LeftWord,
+                "e" => Movement::RightWord,
+                "0" => Movement::PrecedingLineBreak,
+                "$" => Movement::NextLineBreak,
+                "g" if self.pending_g => Movement::StartOfDocument,
+                "G" => Movement::EndOfDocument,
+                "(" => Movement::StartOfSentence,
+                ")" => Movement::EndOfSentence,
+                "{" => Movement::StartOfParagraph,
+                "}" => Movement::EndOfParagraph,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(movement)
+    }
+
+    /// Handle a key in normal or visual mode.
+    fn handle_command(&mut self, event: &KeyEvent) -> Vec<EditAction> {
+        if let Some(movement) = self.motion(&event.key) {
+            self.pending_g = false;
+            return match (self.mode, self.operator.take()) {
+                (VimMode::Visual, _) => vec![EditAction::ModifySelection(movement)],
+                (_, None) => vec![EditAction::Move(movement)],
+                (_, Some(Operator::Delete)) => vec![EditAction::JumpDelete(movement)],
+                (_, Some(Operator::Change)) => {
+                    self.mode = VimMode::Insert;
+                    vec![EditAction::JumpDelete(movement)]
+                }
+                (_, Some(Operator::Yank)) => vec![
+                    EditAction::ModifySelection(movement),
+                    EditAction::Copy,
+                    EditAction::CollapseSelection { to_start: true },
+                ],
+            };
+        }
+        self.pending_g = false;
+
+        let key = match &event.key {
+            KbKey::Character(c) => c.as_str(),
+            KbKey::Escape => {
+                self.operator = None;
+                if self.mode == VimMode::Visual {
+                    self.mode = VimMode::Normal;
+                    return vec![EditAction::CollapseSelection { to_start: false }];
+                }
+                return Vec::new();
+            }
+            // other keys, such as backspace, behave as usual but never insert text
+            _ => {
+                self.operator = None;
+                return match self.basic.action_for_event(event) {
+                    Some(EditAction::Insert(_)) | None => Vec::new(),
+                    Some(action) => vec![action],
+                };
+            }
+        };
+
+        if key == "g" {
+            self.pending_g = true;
+            return Vec::new();
+        }
+
+        if self.mode == VimMode::Visual {
+            let (actions, mode) = match key {
+                "d" | "x" => (vec![EditAction::Delete], VimMode::Normal),
+                "c" => (vec![EditAction::Delete], VimMode::Insert),
+                "y" => (
+                    vec![
+                        EditAction::Copy,
+                        EditAction::CollapseSelection { to_start: true },
+                    ],
+                    VimMode::Normal,
+                ),
+                "v" => (
+                    vec![EditAction::CollapseSelection { to_start: false }],
+                    VimMode::Normal,
+                ),
+                _ => (Vec::new(), VimMode::Visual),
+            };
+            self.mode = mode;
+            return actions;
+        }
+
+        let operator = match key {
+            "d" => Some(Operator::Delete),
+            "c" => Some(Operator::Change),
+            "y" => Some(Operator::Yank),
+            _ => None,
+        };
+        if let Some(operator) = operator {
+            return match self.operator.take() {
+                // a doubled operator acts on the whole line
+                Some(pending) if pending == operator => self.line_operation(operator),
+                _ => {
+                    self.operator = Some(operator);
+                    Vec::new()
+                }
+            };
+        }
+        self.operator = None;
+
+        let (actions, mode) = match key {
+            "i" => (Vec::new(), VimMode::Insert),
+            "a" => (vec![EditAction::Move(Movement::Right)], VimMode::Insert),
+            "I" => (
+                vec![EditAction::Move(Movement::PrecedingLineBreak)],
+                VimMode::Insert,
+            ),
+            "A" => (
+                vec![EditAction::Move(Movement::NextLineBreak)],
+                VimMode::Insert,
+            ),
+            "o" => (
+                vec![
+                    EditAction::Move(Movement::NextLineBreak),
+                    EditAction::Insert("\n".into()),
+                ],
+                VimMode::Insert,
+            ),
+            "x" => (vec![EditAction::Delete], VimMode::Normal),
+            "X" => (vec![EditAction::Backspace], VimMode::Normal),
+            "u" => (vec![EditAction::Undo], VimMode::Normal),
+            "v" => (Vec::new(), VimMode::Visual),
+            _ => (Vec::new(), VimMode::Normal),
+        };
+        self.mode = mode;
+        actions
+    }
+
+    /// The actions for `dd`, `cc` and `yy`.
+    fn line_operation(&mut self, operator: Operator) -> Vec<EditAction> {
+        let mut actions = vec![
+            EditAction::Move(Movement::PrecedingLineBreak),
+            EditAction::ModifySelection(Movement::NextLineBreak),
+        ];
+        // the line break is kept when changing a line
+        if operator != Operator::Change {
+            actions.push(EditAction::ModifySelection(Movement::Right));
+        }
+        match operator {
+            Operator::Delete => actions.push(EditAction::Delete),
+            Operator::Change => {
+                actions.push(EditAction::Delete);
+                self.mode = VimMode::Insert;
+            }
+            Operator::Yank => {
+                actions.push(EditAction::Copy);
+                actions.push(EditAction::CollapseSelection { to_start: true });
+            }
+        }
+        actions
+    }
+}
+
+impl Default for VimTextInput {
+    fn default() -> Self {
+        VimTextInput::new()
+    }
+}
+
+impl TextInput for VimTextInput {
+    fn handle_event(&mut self, event: &KeyEvent) -> Vec<EditAction> {
+        if self.mode == VimMode::Insert {
+            if event.key == KbKey::Escape {
+                self.mode = VimMode::Normal;
+                return vec![EditAction::Move(Movement::Left)];
+            }
+            return self.basic.handle_event(event);
+        }
+        if event.mods.ctrl() || event.mods.meta() {
+            self.operator = None;
+            self.pending_g = false;
+            if event.key == KbKey::Character("r".into()) {
+                return vec![EditAction::Redo];
+            }
+            return self.basic.handle_event(event);
+        }
+        self.handle_command(event)
+    }
+
+    fn set_viewport_height(&mut self, height: f64) {
+        self.viewport_height = height;
+        self.basic.set_viewport_height(height);
+    }
+
+    fn set_secondary_cursors(&mut self, secondary: bool) {
+        self.basic.set_secondary_cursors(secondary);
+    }
+
+    fn cursor_shape(&self) -> CursorShape {
+        match self.mode {
+            VimMode::Insert => CursorShape::Line,
+            VimMode::Normal | VimMode::Visual => CursorShape::Block,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use druid::Modifiers;
+
+    fn keys(input: &mut VimTextInput, keys: &str) -> Vec<EditAction> {
+        keys.chars()
+            .flat_map(|c| {
+                input.handle_event(&KeyEvent::for_test(
+                    Modifiers::empty(),
+                    KbKey::Character(c.to_string()),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn motions_and_operators() {
+        let mut vim = VimTextInput::new();
+        assert_eq!(
+            keys(&mut vim, "wgg$"),
+            vec![
+                EditAction::Move(Movement::RightWordStart),
+                EditAction::Move(Movement::StartOfDocument),
+                EditAction::Move(Movement::NextLineBreak),
+            ]
+        );
+        assert_eq!(keys(&mut vim, "d"), vec![]);
+        assert_eq!(
+            keys(&mut vim, "b"),
+            vec![EditAction::JumpDelete(Movement::LeftWord)]
+        );
+        assert_eq!(
+            keys(&mut vim, "ye"),
+            vec![
+                EditAction::ModifySelection(Movement::RightWord),
+                EditAction::Copy,
+                EditAction::CollapseSelection { to_start: true },
+            ]
+        );
+        assert_eq!(
+            keys(&mut vim, "dd"),
+            vec![
+                EditAction::Move(Movement::PrecedingLineBreak),
+                EditAction::ModifySelection(Movement::NextLineBreak),
+                EditAction::ModifySelection(Movement::Right),
+                EditAction::Delete,
+            ]
+        );
+        assert_eq!(
+            keys(&mut vim, "dgg"),
+            vec![EditAction::JumpDelete(Movement::StartOfDocument)]
+        );
+        // an unknown key cancels a pending operator
+        assert_eq!(
+            keys(&mut vim, "dzw"),
+            vec![EditAction::Move(Movement::RightWordStart)]
+        );
+        assert_eq!(vim.mode(), VimMode::Normal);
+        assert_eq!(vim.cursor_shape(), CursorShape::Block);
+    }
+
+    #[test]
+    fn modes() {
+        let mut vim = VimTextInput::new();
+        assert_eq!(
+            keys(&mut vim, "cw"),
+            vec![EditAction::JumpDelete(Movement::RightWordStart)]
+        );
+        assert_eq!(vim.mode(), VimMode::Insert);
+        assert_eq!(vim.cursor_shape(), CursorShape::Line);
+        assert_eq!(
+            keys(&mut vim, "hi"),
+            vec![
+                EditAction::Insert("h".into()),
+                EditAction::Insert("i".into())
+            ]
+        );
+        let escape = KeyEvent::for_test(Modifiers::empty(), KbKey::Escape);
+        assert_eq!(
+            vim.handle_event(&escape),
+            vec![EditAction::Move(Movement::Left)]
+        );
+        assert_eq!(vim.mode(), VimMode::Normal);
+
+        assert_eq!(
+            keys(&mut vim, "vll"),
+            vec![
+                EditAction::ModifySelection(Movement::Right),
+                EditAction::ModifySelection(Movement::Right),
+            ]
+        );
+        assert_eq!(vim.mode(), VimMode::Visual);
+        assert_eq!(
+            keys(&mut vim, "y"),
+            vec![
+                EditAction::Copy,
+                EditAction::CollapseSelection { to_start: true },
+            ]
+        );
+        assert_eq!(vim.mode(), VimMode::Normal);
+    }
+}
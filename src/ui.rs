@@ -2,6 +2,7 @@ use crate::{
     bloom::Bloom,
     context::{ContextState, UpdateCtx},
     id::ChildCounter,
+    id::ChildId,
     key::Caller,
     object::{AnyRenderObject, Properties, RenderObject},
     tree::{Child, ChildState, Children, State},
@@ -32,6 +33,11 @@ impl<'a, 'b> Ui<'a, 'b> {
         }
     }
 
+    /// Returns `true` if the widget with the given id has keyboard focus.
+    pub fn is_focused(&self, id: ChildId) -> bool {
+        self.state.focus_widget == Some(id)
+    }
+
     pub fn state_node<T, I, N>(&mut self, caller: Caller, init: I, content: N)
     where
         T: Any,
@@ -1,15 +1,24 @@
 //! A label widget.
 
+use std::ops::Range;
+
 use crate::{
-    kurbo::Point,
+    kurbo::{Point, Vec2},
     object::prelude::*,
-    piet::{Color, TextAlignment},
-    text::{FontDescriptor, TextLayout},
+    piet::{Color, TextAlignment, TextLayout as _, TextStorage as _},
+    text::{ArcStr, Attribute, Editor, FontDescriptor, RichText, TextLayout, TextStorage},
 };
+use druid::{Cursor, HotKey, MouseButton, SysMods};
+use unicode_segmentation::UnicodeSegmentation;
 
 // added padding between the edges of the widget and the text.
 const LABEL_X_PADDING: f64 = 2.0;
 
+/// The character that replaces text removed by [`LineBreaking::Ellipsis`].
+///
+/// [`LineBreaking::Ellipsis`]: enum.LineBreaking.html#variant.Ellipsis
+const ELLIPSIS: &str = "\u{2026}";
+
 /// A widget that displays text data.
 ///
 /// This requires the `Data` to be `ArcStr`; to handle static, dynamic, or
@@ -18,12 +27,15 @@ const LABEL_X_PADDING: f64 = 2.0;
 /// [`Label`]: struct.Label.html
 #[derive(Debug, Clone)]
 pub struct Label {
+    text: String,
     layout: TextLayout<String>,
     line_break_mode: LineBreaking,
+    max_lines: Option<usize>,
+    selectable: bool,
 }
 
 impl Properties for Label {
-    type Object = Label;
+    type Object = LabelObject;
 }
 
 /// Options for handling lines that are too wide for the label.
@@ -35,14 +47,53 @@ pub enum LineBreaking {
     Clip,
     /// Lines overflow the label.
     Overflow,
+    /// Text that does not fit is removed, and replaced with an ellipsis
+    /// at the given position.
+    ///
+    /// With [`Label::max_lines`], the text is wrapped and only truncated if
+    /// it needs more lines than that.
+    ///
+    /// [`Label::max_lines`]: struct.Label.html#method.max_lines
+    Ellipsis(EllipsisPosition),
+}
+
+/// Where text is removed by [`LineBreaking::Ellipsis`].
+///
+/// [`LineBreaking::Ellipsis`]: enum.LineBreaking.html#variant.Ellipsis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EllipsisPosition {
+    /// The start of the text is removed.
+    Start,
+    /// The middle of the text is removed, keeping both ends.
+    Middle,
+    /// The end of the text is removed.
+    End,
+}
+
+/// The number of graphemes of a label's text that were kept by
+/// [`LineBreaking::Ellipsis`] for a width, so that the text doesn't have to
+/// be measured again until the text or the width changes.
+///
+/// [`LineBreaking::Ellipsis`]: enum.LineBreaking.html#variant.Ellipsis
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Ellipsized {
+    width: f64,
+    max_lines: usize,
+    position: EllipsisPosition,
+    /// The number of graphemes kept, or `None` if the whole text fits.
+    keep: Option<usize>,
 }
 
 impl Label {
     /// Create a new `Label`.
     pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
         Self {
-            layout: TextLayout::from_text(text),
+            layout: TextLayout::from_text(text.clone()),
+            text,
             line_break_mode: LineBreaking::Overflow,
+            max_lines: None,
+            selectable: false,
         }
     }
 
@@ -56,7 +107,8 @@ impl Label {
         self
     }
 
-    pub fn font(mut self, font: FontDescriptor) -> Self {
+    pub fn font(mut self, font: impl Into<FontDescriptor>) -> Self {
+        let font: FontDescriptor = font.into();
         self.layout.set_font(font);
         self
     }
@@ -71,14 +123,62 @@ impl Label {
         self
     }
 
+    /// Builder-style method to set the maximum number of lines shown with
+    /// [`LineBreaking::Ellipsis`]. Text is wrapped to fit at most this many
+    /// lines before it is truncated. The default is a single line.
+    ///
+    /// [`LineBreaking::Ellipsis`]: enum.LineBreaking.html#variant.Ellipsis
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Builder-style method to allow the text to be selected with the mouse
+    /// and copied.
+    ///
+    /// The selection applies to the text as it is displayed, so it does not
+    /// include any text that was removed by [`LineBreaking::Ellipsis`].
+    ///
+    /// [`LineBreaking::Ellipsis`]: enum.LineBreaking.html#variant.Ellipsis
+    pub fn selectable(mut self, selectable: bool) -> Self {
+        self.selectable = selectable;
+        self
+    }
+
+    /// Returns `true` if the text was truncated with an ellipsis, in which
+    /// case the full text could be shown elsewhere, such as in a tooltip.
     #[track_caller]
-    pub fn build(self, ui: &mut Ui) {
+    pub fn build(self, ui: &mut Ui) -> bool {
         let caller = Location::caller().into();
-        ui.render_object(caller, self, |_| {});
+        ui.render_object(caller, self, |_| {})
     }
 }
 
-impl Label {
+pub struct LabelObject {
+    text: String,
+    layout: TextLayout<String>,
+    line_break_mode: LineBreaking,
+    max_lines: Option<usize>,
+    selectable: bool,
+    /// Tracks the selection of a selectable label. Its layout is a copy of
+    /// `layout`, and it is never used to edit the text.
+    editor: Editor<String>,
+    /// The result of the last truncation of `text`.
+    ellipsized: Option<Ellipsized>,
+}
+
+impl LabelObject {
+    /// The full text of the label, including any text that was truncated.
+    pub fn full_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns `true` if the text that is laid out was truncated with an
+    /// ellipsis.
+    pub fn is_truncated(&self) -> bool {
+        self.layout.text().is_some_and(|shown| *shown != self.text)
+    }
+
     /// Draw this label's text at the provided `Point`, without internal padding.
     ///
     /// This is a convenience for widgets that want to use Label as a way
@@ -108,26 +208,190 @@ impl Label {
     }
 }
 
-impl RenderObject<Label> for Label {
-    type Action = ();
+/// Lay out `full`, shortened with an ellipsis at `position` so that it fits
+/// in `width` and, if it is wrapped, in at most `max_lines` lines.
+///
+/// The number of graphemes that are kept is found with a search that lays
+/// out the text many times, so it is kept in `cached`, and the layout is
+/// left alone while the width stays the same. `shorten` makes the text that
+/// keeps a number of graphemes.
+///
+/// Returns the text that is laid out, if it was set.
+#[allow(clippy::too_many_arguments)]
+fn layout_ellipsized<T: TextStorage>(
+    layout: &mut TextLayout<T>,
+    cached: &mut Option<Ellipsized>,
+    ctx: &mut LayoutCtx,
+    full: &T,
+    width: f64,
+    max_lines: usize,
+    position: EllipsisPosition,
+    shorten: impl Fn(usize) -> T,
+) -> Option<T> {
+    layout.set_wrap_width(if max_lines > 1 { width } else { f64::INFINITY });
+    let is_cached = |cached: &Ellipsized| {
+        cached.width == width && cached.max_lines == max_lines && cached.position == position
+    };
+    if cached.as_ref().is_some_and(is_cached) {
+        return None;
+    }
+    let keep = fit_ellipsized(layout, ctx, full, width, max_lines, &shorten);
+    *cached = Some(Ellipsized {
+        width,
+        max_lines,
+        position,
+        keep,
+    });
+    let shown = keep.map_or_else(|| full.clone(), shorten);
+    layout.set_text(shown.clone());
+    Some(shown)
+}
+
+/// The number of graphemes of `full` that are kept for it to fit, or `None`
+/// if it fits without being truncated.
+fn fit_ellipsized<T: TextStorage>(
+    layout: &mut TextLayout<T>,
+    ctx: &mut LayoutCtx,
+    full: &T,
+    width: f64,
+    max_lines: usize,
+    shorten: impl Fn(usize) -> T,
+) -> Option<usize> {
+    let env = ctx.env().clone();
+    let fonts = ctx.fonts();
+    let mut fits = |text: T| {
+        layout.set_text(text);
+        layout.rebuild_if_needed(ctx.text(), fonts, &env);
+        match layout.layout() {
+            Some(inner) if max_lines > 1 => {
+                inner.line_count() <= max_lines && layout.size().width <= width
+            }
+            Some(_) => layout.size().width <= width,
+            None => true,
+        }
+    };
+
+    if fits(full.clone()) {
+        return None;
+    }
+    // find the largest number of graphemes we can keep
+    let (mut low, mut high) = (0, full.as_str().graphemes(true).count().saturating_sub(1));
+    while low < high {
+        let keep = (low + high).div_ceil(2);
+        if fits(shorten(keep)) {
+            low = keep;
+        } else {
+            high = keep - 1;
+        }
+    }
+    Some(low)
+}
+
+/// The range of `text` that is replaced with an ellipsis to keep `keep` of
+/// its graphemes. Whitespace next to the ellipsis is removed too.
+fn ellipsis_range(text: &str, keep: usize, position: EllipsisPosition) -> Range<usize> {
+    let bounds: Vec<usize> = text
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .chain(Some(text.len()))
+        .collect();
+    let count = bounds.len() - 1;
+    let keep = keep.min(count);
+    let (head, tail) = match position {
+        EllipsisPosition::Start => (0, keep),
+        EllipsisPosition::Middle => (keep.div_ceil(2), keep / 2),
+        EllipsisPosition::End => (keep, 0),
+    };
+    let start = text[..bounds[head]].trim_end().len();
+    let end = text.len() - text[bounds[count - tail]..].trim_start().len();
+    start..end.max(start)
+}
+
+/// The text, keeping `keep` of its graphemes and replacing the rest with an
+/// ellipsis.
+fn ellipsize(text: &str, keep: usize, position: EllipsisPosition) -> String {
+    let mut text = text.to_owned();
+    text.replace_range(ellipsis_range(&text, keep, position), ELLIPSIS);
+    text
+}
+
+impl RenderObject<Label> for LabelObject {
+    type Action = bool;
 
     fn create(props: Label) -> Self {
-        props
+        LabelObject {
+            text: props.text,
+            layout: props.layout,
+            line_break_mode: props.line_break_mode,
+            max_lines: props.max_lines,
+            selectable: props.selectable,
+            editor: Editor::new(),
+            ellipsized: None,
+        }
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, props: Label) {
-        if self.layout.text() != props.layout.text() {
+    fn update(&mut self, ctx: &mut UpdateCtx, props: Label) -> Self::Action {
+        if self.text != props.text
+            || self.line_break_mode != props.line_break_mode
+            || self.max_lines != props.max_lines
+            || self.selectable != props.selectable
+        {
             ctx.request_layout();
+            self.text = props.text;
             self.layout = props.layout;
+            self.line_break_mode = props.line_break_mode;
+            self.max_lines = props.max_lines;
+            self.selectable = props.selectable;
+            self.ellipsized = None;
         }
         if self.layout.layout().is_none() {
             ctx.request_layout();
         }
+        self.is_truncated()
     }
 }
 
-impl RenderObjectInterface for Label {
-    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _children: &mut Children) {}
+impl RenderObjectInterface for LabelObject {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _children: &mut Children) {
+        if !self.selectable {
+            return;
+        }
+        // the editor only reads the text, but it expects to be given the data
+        let mut text = self.editor.layout().text().cloned().unwrap_or_default();
+        let offset = Vec2::new(LABEL_X_PADDING, 0.0);
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                ctx.request_focus();
+                ctx.set_active(true);
+                let mut mouse = mouse.clone();
+                mouse.pos -= offset;
+                self.editor.click(&mouse, &mut text);
+                ctx.request_paint();
+            }
+            Event::MouseMove(mouse) => {
+                ctx.set_cursor(&Cursor::IBeam);
+                if ctx.is_active() {
+                    let mut mouse = mouse.clone();
+                    mouse.pos -= offset;
+                    self.editor.drag(&mouse, &mut text);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+            }
+            Event::KeyDown(key_event) if HotKey::new(SysMods::Cmd, "c").matches(key_event) => {
+                self.editor.copy(&mut text);
+                ctx.set_handled();
+            }
+            Event::KeyDown(key_event) if HotKey::new(SysMods::Cmd, "a").matches(key_event) => {
+                self.editor.select_all(&text);
+                ctx.set_handled();
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
 
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
 
@@ -138,22 +402,28 @@ impl RenderObjectInterface for Label {
         _children: &mut Children,
     ) -> Size {
         bc.debug_check("Label");
-
-        let width = match self.line_break_mode {
-            LineBreaking::WordWrap => bc.max().width - LABEL_X_PADDING * 2.0,
-            _ => f64::INFINITY,
-        };
-
-        self.layout.set_wrap_width(width);
-        let env = ctx.env().clone();
-        self.layout.rebuild_if_needed(ctx.text(), &env);
-
-        let text_metrics = self.layout.layout_metrics();
-        ctx.set_baseline_offset(text_metrics.size.height - text_metrics.first_baseline);
-        bc.constrain(Size::new(
-            text_metrics.size.width + 2. * LABEL_X_PADDING,
-            text_metrics.size.height,
-        ))
+        if let LineBreaking::Ellipsis(position) = self.line_break_mode {
+            let width = bc.max().width - LABEL_X_PADDING * 2.0;
+            let max_lines = self.max_lines.unwrap_or(1).max(1);
+            let text = &self.text;
+            layout_ellipsized(
+                &mut self.layout,
+                &mut self.ellipsized,
+                ctx,
+                text,
+                width,
+                max_lines,
+                position,
+                |keep| ellipsize(text, keep, position),
+            );
+        }
+        let size = layout_text(&mut self.layout, self.line_break_mode, ctx, bc);
+        if self.selectable {
+            let selection = *self.editor.selection();
+            *self.editor.layout_mut() = self.layout.clone();
+            self.editor.set_selection(selection);
+        }
+        size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, _children: &mut Children) {
@@ -163,6 +433,310 @@ impl RenderObjectInterface for Label {
         if self.line_break_mode == LineBreaking::Clip {
             ctx.clip(label_size.to_rect());
         }
+        if self.selectable && ctx.is_focused() {
+            for rect in self.editor.selection_rects() {
+                let rect = rect + origin.to_vec2();
+                ctx.fill(rect.to_rounded_rect(1.0), &Color::BLUE);
+            }
+        }
         self.draw_at(ctx, origin)
     }
 }
+
+/// A widget that displays [`RichText`], with styles applied to spans of
+/// the text.
+///
+/// The text color, size and font set on the label are used for any text
+/// that does not have these set by an attribute.
+///
+/// Spans with a [`Link`] attribute are underlined while hovered, and
+/// [`build`] returns the payload of a link when it is clicked.
+///
+/// [`RichText`]: ../text/struct.RichText.html
+/// [`Link`]: ../text/enum.Attribute.html#variant.Link
+/// [`build`]: #method.build
+#[derive(Debug, Clone)]
+pub struct RichLabel {
+    text: RichText,
+    layout: TextLayout<RichText>,
+    line_break_mode: LineBreaking,
+}
+
+impl Properties for RichLabel {
+    type Object = RichLabelObject;
+}
+
+impl RichLabel {
+    /// Create a new `RichLabel`.
+    pub fn new(text: RichText) -> Self {
+        Self {
+            layout: TextLayout::from_text(text.clone()),
+            text,
+            line_break_mode: LineBreaking::Overflow,
+        }
+    }
+
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.layout.set_text_color(color);
+        self
+    }
+
+    pub fn text_size(mut self, size: f64) -> Self {
+        self.layout.set_text_size(size);
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<FontDescriptor>) -> Self {
+        let font: FontDescriptor = font.into();
+        self.layout.set_font(font);
+        self
+    }
+
+    pub fn line_break_mode(mut self, mode: LineBreaking) -> Self {
+        self.line_break_mode = mode;
+        self
+    }
+
+    pub fn text_alignment(mut self, alignment: TextAlignment) -> Self {
+        self.layout.set_text_alignment(alignment);
+        self
+    }
+
+    /// Returns the payload of the link that was clicked, if any.
+    #[track_caller]
+    pub fn build(self, ui: &mut Ui) -> Option<ArcStr> {
+        let caller = Location::caller().into();
+        ui.render_object(caller, self, |_| {})
+    }
+}
+
+pub struct RichLabelObject {
+    text: RichText,
+    /// The text that is laid out, which is shortened with an ellipsis if
+    /// it doesn't fit, and doesn't have the underline of a hovered link.
+    shown: RichText,
+    layout: TextLayout<RichText>,
+    line_break_mode: LineBreaking,
+    /// The result of the last truncation of `text`.
+    ellipsized: Option<Ellipsized>,
+    /// The range of the link under the mouse.
+    hovered_link: Option<Range<usize>>,
+    /// The range of the link the mouse was pressed on.
+    pressed_link: Option<Range<usize>>,
+    /// The payload of a link that was clicked since the last update.
+    clicked_link: Option<ArcStr>,
+}
+
+impl RichLabelObject {
+    /// Returns the range and payload of the link at the given point.
+    fn link_at(&self, point: Point) -> Option<(Range<usize>, ArcStr)> {
+        let point = point - Vec2::new(LABEL_X_PADDING, 0.0);
+        let offset = self.layout.text_position_for_point(point);
+        // a point on the trailing half of the last character of a link
+        // maps to the offset just past it.
+        let (range, payload) = self
+            .shown
+            .link_at(offset)
+            .or_else(|| self.shown.link_at(offset.checked_sub(1)?))?;
+        let rects = self.layout.rects_for_range(range.clone());
+        if rects.iter().any(|rect| rect.contains(point)) {
+            Some((range, payload.clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Set the hovered link, underlining it.
+    ///
+    /// Returns `true` if the hovered link changed.
+    fn set_hovered_link(&mut self, range: Option<Range<usize>>) -> bool {
+        if range == self.hovered_link {
+            return false;
+        }
+        let text = match &range {
+            Some(range) => self
+                .shown
+                .clone()
+                .with_attribute(range.clone(), Attribute::underline(true)),
+            None => self.shown.clone(),
+        };
+        self.layout.set_text(text);
+        self.hovered_link = range;
+        true
+    }
+}
+
+impl RenderObject<RichLabel> for RichLabelObject {
+    type Action = Option<ArcStr>;
+
+    fn create(props: RichLabel) -> Self {
+        RichLabelObject {
+            shown: props.text.clone(),
+            text: props.text,
+            layout: props.layout,
+            line_break_mode: props.line_break_mode,
+            ellipsized: None,
+            hovered_link: None,
+            pressed_link: None,
+            clicked_link: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, props: RichLabel) -> Self::Action {
+        if self.text != props.text || self.line_break_mode != props.line_break_mode {
+            ctx.request_layout();
+            self.shown = props.text.clone();
+            self.text = props.text;
+            self.layout = props.layout;
+            self.line_break_mode = props.line_break_mode;
+            self.ellipsized = None;
+            self.hovered_link = None;
+            self.pressed_link = None;
+        }
+        if self.layout.layout().is_none() {
+            ctx.request_layout();
+        }
+        self.clicked_link.take()
+    }
+}
+
+impl RenderObjectInterface for RichLabelObject {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _children: &mut Children) {
+        match event {
+            Event::MouseMove(mouse) => {
+                let link = self.link_at(mouse.pos);
+                if link.is_some() {
+                    ctx.set_cursor(&Cursor::OpenHand);
+                } else {
+                    ctx.set_cursor(&Cursor::Arrow);
+                }
+                if self.set_hovered_link(link.map(|(range, _)| range)) {
+                    ctx.request_layout();
+                }
+            }
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                self.pressed_link = self.link_at(mouse.pos).map(|(range, _)| range);
+                if self.pressed_link.is_some() {
+                    ctx.set_active(true);
+                }
+            }
+            Event::MouseUp(mouse) if ctx.is_active() && mouse.button == MouseButton::Left => {
+                ctx.set_active(false);
+                let pressed = self.pressed_link.take();
+                if let Some((range, payload)) = self.link_at(mouse.pos) {
+                    if pressed == Some(range) {
+                        self.clicked_link = Some(payload);
+                        ctx.request_update();
+                        ctx.set_handled();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _children: &mut Children,
+    ) -> Size {
+        bc.debug_check("RichLabel");
+        if let LineBreaking::Ellipsis(position) = self.line_break_mode {
+            let width = bc.max().width - LABEL_X_PADDING * 2.0;
+            let text = &self.text;
+            let shown = layout_ellipsized(
+                &mut self.layout,
+                &mut self.ellipsized,
+                ctx,
+                text,
+                width,
+                1,
+                position,
+                |keep| text.with_replaced(ellipsis_range(text.as_str(), keep, position), ELLIPSIS),
+            );
+            if let Some(shown) = shown {
+                self.shown = shown;
+                self.hovered_link = None;
+                self.pressed_link = None;
+            }
+        }
+        layout_text(&mut self.layout, self.line_break_mode, ctx, bc)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _children: &mut Children) {
+        if self.line_break_mode == LineBreaking::Clip {
+            let label_size = ctx.size();
+            ctx.clip(label_size.to_rect());
+        }
+        self.layout.draw(ctx, Point::new(LABEL_X_PADDING, 0.0));
+    }
+}
+
+/// Rebuild a label's text layout for the given constraints, returning the
+/// size of the label.
+fn layout_text<T: TextStorage>(
+    layout: &mut TextLayout<T>,
+    line_break_mode: LineBreaking,
+    ctx: &mut LayoutCtx,
+    bc: &BoxConstraints,
+) -> Size {
+    match line_break_mode {
+        LineBreaking::WordWrap => layout.set_wrap_width(bc.max().width - LABEL_X_PADDING * 2.0),
+        // the wrap width for ellipsized text is set when it is truncated
+        LineBreaking::Ellipsis(_) => (),
+        _ => layout.set_wrap_width(f64::INFINITY),
+    }
+    let env = ctx.env().clone();
+    let fonts = ctx.fonts();
+    layout.rebuild_if_needed(ctx.text(), fonts, &env);
+
+    let text_metrics = layout.layout_metrics();
+    ctx.set_baseline_offset(text_metrics.size.height - text_metrics.first_baseline);
+    bc.constrain(Size::new(
+        text_metrics.size.width + 2. * LABEL_X_PADDING,
+        text_metrics.size.height,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ellipsize_positions() {
+        let text = "hello big world";
+        assert_eq!(ellipsize(text, 7, EllipsisPosition::End), "hello b\u{2026}");
+        assert_eq!(ellipsize(text, 6, EllipsisPosition::End), "hello\u{2026}");
+        assert_eq!(ellipsize(text, 5, EllipsisPosition::Start), "\u{2026}world");
+        assert_eq!(ellipsize(text, 6, EllipsisPosition::Start), "\u{2026}world");
+        assert_eq!(
+            ellipsize(text, 9, EllipsisPosition::Middle),
+            "hello\u{2026}orld"
+        );
+        assert_eq!(ellipsize(text, 0, EllipsisPosition::Middle), "\u{2026}");
+    }
+
+    #[test]
+    fn ellipsize_keeps_graphemes_whole() {
+        let text = "ne\u{301}e";
+        assert_eq!(
+            ellipsize(text, 2, EllipsisPosition::End),
+            "ne\u{301}\u{2026}"
+        );
+    }
+
+    #[test]
+    fn ellipsize_rich_text() {
+        let text =
+            RichText::new("see the docs".into()).with_attribute(8..12, Attribute::link("docs"));
+        let range = ellipsis_range(text.as_str(), 8, EllipsisPosition::Start);
+        assert_eq!(range, 0..4);
+        let shown = text.with_replaced(range, ELLIPSIS);
+        assert_eq!(shown.as_str(), "\u{2026}the docs");
+        assert_eq!(shown.link_at(7), Some((7..11, &"docs".into())));
+        assert_eq!(shown.link_at(0), None);
+    }
+}
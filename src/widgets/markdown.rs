@@ -0,0 +1,110 @@
+//! A widget that displays markdown.
+
+use std::panic::Location;
+
+use crate::{
+    piet::{FontFamily, FontWeight},
+    text::{
+        markdown::{self, Block, BlockKind},
+        ArcStr, FontDescriptor,
+    },
+    ui::Ui,
+    widgets::{
+        flex::{CrossAxisAlignment, MainAxisAlignment},
+        label::LineBreaking,
+        Flex, Padding, RichLabel, SizedBox,
+    },
+};
+
+/// The space between blocks.
+const BLOCK_SPACING: f64 = 8.0;
+/// The indentation of each level of list items.
+const LIST_INDENT: f64 = 16.0;
+
+/// A widget that displays markdown, such as release notes or help pages.
+///
+/// The blocks produced by [`markdown::parse`] are laid out in a column,
+/// with a larger, bold font for headings and a monospace font for code.
+/// The source is only parsed again when it changes.
+///
+/// [`markdown::parse`]: ../text/markdown/fn.parse.html
+pub struct Markdown<'a> {
+    source: &'a str,
+}
+
+impl<'a> Markdown<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Markdown { source }
+    }
+
+    /// Returns the destination of a link, if one was clicked.
+    #[track_caller]
+    pub fn build(self, ui: &mut Ui) -> Option<ArcStr> {
+        let caller = Location::caller().into();
+        let mut clicked = None;
+        let init = || ParsedMarkdown {
+            source: self.source.to_owned(),
+            blocks: markdown::parse(self.source),
+        };
+        ui.state_node(caller, init, |ui, parsed: &mut ParsedMarkdown| {
+            if parsed.source != self.source {
+                parsed.source = self.source.to_owned();
+                parsed.blocks = markdown::parse(self.source);
+            }
+            clicked = build_blocks(ui, &parsed.blocks);
+        });
+        clicked
+    }
+}
+
+/// The blocks of the markdown source that was last shown.
+struct ParsedMarkdown {
+    source: String,
+    blocks: Vec<Block>,
+}
+
+/// Lay out the blocks in a column, returning the destination of a link if
+/// one was clicked.
+fn build_blocks(ui: &mut Ui, blocks: &[Block]) -> Option<ArcStr> {
+    let mut clicked = None;
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .main_axis_alignment(MainAxisAlignment::Start)
+        .build(ui, |ui| {
+            for (i, block) in blocks.iter().enumerate() {
+                if i > 0 {
+                    SizedBox::new().height(BLOCK_SPACING).empty(ui);
+                }
+                let mut label =
+                    RichLabel::new(block.text.clone()).line_break_mode(LineBreaking::WordWrap);
+                let mut indent = 0.0;
+                match block.kind {
+                    BlockKind::Paragraph => {}
+                    BlockKind::Heading(level) => label = label.font(heading_font(level)),
+                    BlockKind::ListItem(depth) => indent = LIST_INDENT * (depth + 1) as f64,
+                    BlockKind::CodeBlock => {
+                        label = label.font(FontDescriptor::new(FontFamily::MONOSPACE))
+                    }
+                }
+                Padding::new((indent, 0.0, 0.0, 0.0)).build(ui, |ui| {
+                    if let Some(link) = label.build(ui) {
+                        clicked = Some(link);
+                    }
+                });
+            }
+        });
+    clicked
+}
+
+/// The font for a heading of the given level.
+fn heading_font(level: u8) -> FontDescriptor {
+    let size = match level {
+        1 => 24.0,
+        2 => 20.0,
+        3 => 17.0,
+        _ => 15.0,
+    };
+    FontDescriptor::new(FontFamily::SYSTEM_UI)
+        .with_size(size)
+        .with_weight(FontWeight::BOLD)
+}
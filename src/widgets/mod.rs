@@ -2,7 +2,7 @@ pub mod button;
 pub use button::Button;
 
 pub mod label;
-pub use label::Label;
+pub use label::{Label, RichLabel};
 
 pub mod padding;
 pub use padding::Padding;
@@ -15,3 +15,12 @@ pub use flex::Flex;
 
 pub mod textbox;
 pub use textbox::TextBox;
+
+pub mod value_textbox;
+pub use value_textbox::ValueTextBox;
+
+pub mod textarea;
+pub use textarea::TextArea;
+
+pub mod markdown;
+pub use markdown::Markdown;
@@ -0,0 +1,1265 @@
+//! A multi-line text editing widget.
+
+use crate::{
+    object::prelude::*,
+    text::{
+        BasicTextInput, Composition, CompositionEvent, CursorShape, EditAction, EditableText,
+        Editor, Fonts, Highlighter, Rope, SearchQuery, Selection, SpellChecker, TextInput,
+        TextLayout, COMPOSE,
+    },
+};
+use druid::{
+    piet::PietText, Affine, Code, Color, Cursor, Data, HotKey, Insets, KbKey, KeyEvent, LifeCycle,
+    MouseButton, MouseEvent, Point, Rect, SysMods, TimerToken, Vec2,
+};
+use std::{any::TypeId, borrow::Cow, ops::Range, sync::Arc, time::Duration};
+
+const CURSOR_BLINK_DURATION: Duration = Duration::from_millis(500);
+const TEXT_INSETS: Insets = Insets::uniform(3.0);
+const DEFAULT_WIDTH: f64 = 300.0;
+const DEFAULT_HEIGHT: f64 = 150.0;
+const SEARCH_BAR_WIDTH: f64 = 260.0;
+const SEARCH_BAR_PADDING: f64 = 4.0;
+/// The width of each of the search option toggles.
+const SEARCH_OPTION_WIDTH: f64 = 24.0;
+const MENU_PADDING: f64 = 4.0;
+
+/// A text area for editing multiple lines of text.
+///
+/// The text is kept in a [`Rope`], so that edits to long documents only
+/// cost as much as the edit rather than the whole text.
+///
+/// Lines are soft wrapped at the available width, and the content is
+/// scrolled vertically to keep the cursor in view.
+///
+/// `Cmd+F` opens a search bar, and `Ctrl+H`, or `Cmd+Option+F` on macOS, a
+/// search bar that can also replace matches. In the search bar, `Enter` and `Shift+Enter` step
+/// through the matches, or replace the selected match in the replacement
+/// field, `Cmd+Alt+Enter` replaces all matches as a single undoable edit,
+/// and `Alt+C`, `Alt+W` and `Alt+R` toggle matching case, whole words and
+/// regular expressions. `F3` and `Shift+F3` step through the matches while
+/// editing the text, and `Escape` closes the search bar.
+///
+/// With a [`spell_checker`], misspelled words are underlined, and
+/// right-clicking one shows a menu of suggested replacements.
+///
+/// [`Rope`]: ../text/struct.Rope.html
+/// [`spell_checker`]: #method.spell_checker
+pub struct TextArea<'a> {
+    placeholder: &'a str,
+    editable: &'a mut Rope,
+    highlighter: Option<Arc<dyn Highlighter>>,
+    spell_checker: Option<Arc<dyn SpellChecker>>,
+    text_input: (TypeId, Box<dyn TextInput>),
+}
+
+impl<'a> TextArea<'a> {
+    pub fn new(text: &'a mut Rope) -> Self {
+        TextArea {
+            placeholder: "",
+            editable: text,
+            highlighter: None,
+            spell_checker: None,
+            text_input: (
+                TypeId::of::<BasicTextInput>(),
+                Box::new(BasicTextInput::new()),
+            ),
+        }
+    }
+
+    pub fn placeholder(mut self, text: &'a str) -> Self {
+        self.placeholder = text;
+        self
+    }
+
+    /// Style the text with a [`Highlighter`].
+    ///
+    /// The highlighter is compared by pointer, so the same `Arc` should be
+    /// passed on every build; a different one highlights the whole text
+    /// again.
+    ///
+    /// [`Highlighter`]: ../text/trait.Highlighter.html
+    pub fn highlighter(mut self, highlighter: Arc<dyn Highlighter>) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Underline misspelled words found by a [`SpellChecker`], such as a
+    /// [`WordList`].
+    ///
+    /// Like the highlighter, the spell checker is compared by pointer.
+    ///
+    /// [`SpellChecker`]: ../text/trait.SpellChecker.html
+    /// [`WordList`]: ../text/struct.WordList.html
+    pub fn spell_checker(mut self, checker: Arc<dyn SpellChecker>) -> Self {
+        self.spell_checker = Some(checker);
+        self
+    }
+
+    /// Builder-style method to set the [`TextInput`] that maps key events
+    /// to edits, such as a [`VimTextInput`].
+    ///
+    /// The handler keeps its state, such as the vim mode, between builds;
+    /// it is only replaced when a handler of a different type is passed.
+    ///
+    /// [`TextInput`]: ../text/trait.TextInput.html
+    /// [`VimTextInput`]: ../text/struct.VimTextInput.html
+    pub fn text_input<I: TextInput + 'static>(mut self, input: I) -> Self {
+        self.text_input = (TypeId::of::<I>(), Box::new(input));
+        self
+    }
+
+    /// Returns `true` if the text area was activated with `Cmd+Enter`.
+    #[track_caller]
+    pub fn build(self, ui: &mut Ui) -> bool {
+        let caller = Location::caller().into();
+        ui.render_object(caller, self, |_| {})
+    }
+}
+
+impl Properties for TextArea<'_> {
+    type Object = TextAreaObject;
+}
+
+pub struct TextAreaObject {
+    placeholder: TextLayout<String>,
+    /// The text being edited, which shares its chunks with the caller's
+    /// rope until it is edited.
+    text: Rope,
+    editor: Editor<Rope>,
+    activated: bool,
+
+    input_handler: Box<dyn TextInput>,
+    /// The type of `input_handler`, to tell when it is replaced.
+    input_type: TypeId,
+    vscroll_offset: f64,
+    // after SelectAll or scrolling with the mouse wheel, we don't adjust
+    // the viewport until the next edit or click.
+    suppress_adjust_vscroll: bool,
+    cursor_timer: TimerToken,
+    cursor_on: bool,
+    search: Option<SearchBar>,
+    /// Whether to select the next match once the text has been replaced.
+    search_step_pending: bool,
+    /// The suggestions for a misspelled word, while they are shown.
+    spelling_menu: Option<SpellingMenu>,
+}
+
+impl TextAreaObject {
+    /// The point, relative to the origin, where this text area draws its
+    /// [`TextLayout`], before scrolling is applied.
+    pub fn text_position(&self) -> Point {
+        Point::new(TEXT_INSETS.x0, TEXT_INSETS.y0)
+    }
+
+    /// Apply a step of composition by an input method, sent with the
+    /// [`COMPOSE`] command.
+    ///
+    /// [`COMPOSE`]: ../../text/constant.COMPOSE.html
+    fn compose(&mut self, event: &CompositionEvent) {
+        self.suppress_adjust_vscroll = false;
+        self.editor.compose(event, &mut self.text);
+    }
+}
+
+impl RenderObject<TextArea<'_>> for TextAreaObject {
+    type Action = bool;
+
+    fn create(props: TextArea<'_>) -> Self {
+        let text = props.editable.clone();
+        let mut editor = Editor::from_text(text.clone());
+        editor.set_multiline(true);
+        editor.set_highlighter(props.highlighter);
+        editor.set_spell_checker(props.spell_checker);
+        TextAreaObject {
+            placeholder: TextLayout::from_text(props.placeholder),
+            text,
+            editor,
+            activated: false,
+
+            input_type: props.text_input.0,
+            input_handler: props.text_input.1,
+            vscroll_offset: 0.0,
+            suppress_adjust_vscroll: false,
+            cursor_timer: TimerToken::INVALID,
+            cursor_on: false,
+            search: None,
+            search_step_pending: false,
+            spelling_menu: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, props: TextArea<'_>) -> Self::Action {
+        // ropes that share their chunks are compared without going through
+        // the text, so the edited rope is handed back without copying it
+        if !self.text.same(props.editable) {
+            if self.editor.layout().text() == Some(&*props.editable) {
+                *props.editable = self.text.clone();
+                self.editor.set_text(self.text.clone());
+            } else {
+                self.text = props.editable.clone();
+                self.editor.set_text(self.text.clone());
+                self.editor.clear_history();
+                self.editor.discard_preedit();
+            }
+            self.search_text_changed();
+            ctx.request_layout();
+        }
+        if props.placeholder != self.placeholder.text().unwrap() {
+            self.placeholder.set_text(props.placeholder.to_owned());
+            ctx.request_layout();
+        }
+        let same_highlighter = match (&props.highlighter, self.editor.highlighter()) {
+            (Some(new), Some(old)) => Arc::ptr_eq(new, old),
+            (new, old) => new.is_none() && old.is_none(),
+        };
+        if !same_highlighter {
+            self.editor.set_highlighter(props.highlighter);
+            ctx.request_layout();
+        }
+        let same_spell_checker = match (&props.spell_checker, self.editor.spell_checker()) {
+            (Some(new), Some(old)) => Arc::ptr_eq(new, old),
+            (new, old) => new.is_none() && old.is_none(),
+        };
+        if !same_spell_checker {
+            self.editor.set_spell_checker(props.spell_checker);
+            self.spelling_menu = None;
+            ctx.request_paint();
+        }
+
+        if props.text_input.0 != self.input_type {
+            self.input_type = props.text_input.0;
+            self.input_handler = props.text_input.1;
+            ctx.request_paint();
+        }
+
+        let was_activated = self.activated;
+        self.activated = false;
+        was_activated
+    }
+}
+
+impl RenderObjectInterface for TextAreaObject {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _children: &mut Children) {
+        match event {
+            Event::MouseDown(mouse) => {
+                ctx.request_focus();
+                if let Some(menu) = self.spelling_menu.take() {
+                    if menu.rect.contains(mouse.pos) {
+                        if let Some(replacement) = menu.item_at(mouse.pos) {
+                            self.replace_misspelling(ctx, menu.range, replacement);
+                        }
+                        ctx.request_paint();
+                        return;
+                    }
+                    ctx.request_paint();
+                }
+                if mouse.button == MouseButton::Right && self.open_spelling_menu(mouse.pos) {
+                    ctx.request_layout();
+                    ctx.request_paint();
+                    return;
+                }
+                if let Some(search) = &mut self.search {
+                    if search.rect.contains(mouse.pos) {
+                        let command = search.click(mouse);
+                        self.run_search_command(ctx, command);
+                        self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                        ctx.request_paint();
+                        return;
+                    }
+                    search.focused = false;
+                }
+                ctx.set_active(true);
+                self.suppress_adjust_vscroll = false;
+                let mut mouse = mouse.clone();
+                mouse.pos = self.text_point(mouse.pos);
+
+                if !mouse.focus {
+                    self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                    self.input_handler.handle_click();
+                    self.editor.click(&mouse, &mut self.text);
+                }
+
+                ctx.request_paint();
+            }
+            Event::MouseMove(mouse) => {
+                if let Some(menu) = &mut self.spelling_menu {
+                    let hovered = menu.index_at(mouse.pos);
+                    if hovered != menu.selected {
+                        menu.selected = hovered;
+                        ctx.request_paint();
+                    }
+                }
+                let mut mouse = mouse.clone();
+                mouse.pos = self.text_point(mouse.pos);
+                ctx.set_cursor(&Cursor::IBeam);
+                if ctx.is_active() {
+                    self.suppress_adjust_vscroll = false;
+                    self.editor.drag(&mouse, &mut self.text);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+                ctx.request_paint();
+            }
+            Event::Wheel(mouse) => {
+                let max_offset = self.max_vscroll_offset(ctx.size().height);
+                self.vscroll_offset = (self.vscroll_offset + mouse.wheel_delta.y)
+                    .min(max_offset)
+                    .max(0.0);
+                self.suppress_adjust_vscroll = true;
+                ctx.set_handled();
+                ctx.request_paint();
+            }
+            Event::Timer(id) if *id == self.cursor_timer => {
+                self.cursor_on = !self.cursor_on;
+                ctx.request_paint();
+                self.cursor_timer = ctx.request_timer(CURSOR_BLINK_DURATION);
+            }
+            Event::Command(cmd) if ctx.is_focused() && cmd.is(COMPOSE) => {
+                self.compose(cmd.get_unchecked(COMPOSE));
+                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                ctx.request_update();
+                ctx.set_handled();
+            }
+            Event::Paste(ref item) => {
+                if let Some(search) = self.search.as_mut().filter(|search| search.focused) {
+                    if let Some(string) = item.get_string() {
+                        let command = search.paste(string);
+                        self.run_search_command(ctx, command);
+                        ctx.request_layout();
+                        ctx.request_paint();
+                    }
+                } else if let Some(string) = item.get_string() {
+                    self.suppress_adjust_vscroll = false;
+                    self.editor.paste(string, &mut self.text);
+                    ctx.request_update();
+                }
+            }
+            Event::KeyDown(key_event) if self.spelling_menu.is_some() => {
+                self.spelling_menu_key(ctx, key_event);
+                ctx.request_paint();
+            }
+            Event::KeyDown(key_event) => {
+                self.suppress_adjust_vscroll = false;
+                if let Some(command) = self.search_key(key_event) {
+                    self.run_search_command(ctx, command);
+                    self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                    ctx.request_layout();
+                    ctx.request_paint();
+                    return;
+                }
+                match key_event {
+                    // Confirm or cancel text being composed by an input method
+                    k_e if self.editor.is_composing()
+                        && HotKey::new(None, KbKey::Enter).matches(k_e) =>
+                    {
+                        self.editor.commit_preedit(&mut self.text);
+                        ctx.request_update();
+                    }
+                    k_e if self.editor.is_composing()
+                        && HotKey::new(None, KbKey::Escape).matches(k_e) =>
+                    {
+                        self.editor.cancel_preedit(&mut self.text);
+                        ctx.request_update();
+                    }
+                    // Tab and shift+tab
+                    k_e if HotKey::new(None, KbKey::Tab).matches(k_e) => ctx.focus_next(),
+                    k_e if HotKey::new(SysMods::Shift, KbKey::Tab).matches(k_e) => ctx.focus_prev(),
+                    k_e if HotKey::new(SysMods::Cmd, KbKey::Enter).matches(k_e) => {
+                        self.activated = true;
+                        ctx.request_update();
+                    }
+                    k_e => {
+                        self.input_handler
+                            .set_secondary_cursors(!self.editor.secondary_selections().is_empty());
+                        let edits = self.input_handler.handle_event(k_e);
+                        if !edits.is_empty() {
+                            for edit in edits {
+                                self.suppress_adjust_vscroll =
+                                    matches!(edit, EditAction::SelectAll);
+                                self.editor.do_edit(edit, &mut self.text);
+                            }
+                            ctx.request_update();
+                        }
+                    }
+                };
+                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                ctx.request_layout();
+                ctx.request_paint();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _children: &mut Children,
+    ) -> Size {
+        let size = bc.constrain((DEFAULT_WIDTH, DEFAULT_HEIGHT));
+        let wrap_width = size.width - TEXT_INSETS.x_value();
+
+        let env = ctx.env().clone();
+        let fonts = ctx.fonts();
+        self.placeholder.set_wrap_width(wrap_width);
+        self.placeholder.rebuild_if_needed(ctx.text(), fonts, &env);
+        self.editor.set_wrap_width(wrap_width);
+        self.editor.rebuild_if_needed(ctx.text(), fonts, &env);
+        self.input_handler
+            .set_viewport_height(size.height - TEXT_INSETS.y_value());
+        if let Some(search) = &mut self.search {
+            search.layout(ctx, size.width);
+        }
+        if let Some(menu) = &mut self.spelling_menu {
+            menu.layout(ctx, size);
+        }
+
+        if !self.suppress_adjust_vscroll {
+            self.update_vscroll(size.height);
+        }
+
+        let text_metrics = if self.text.is_empty() {
+            self.placeholder.layout_metrics()
+        } else {
+            self.editor.layout().layout_metrics()
+        };
+        let baseline_off = size.height - TEXT_INSETS.y0 - text_metrics.first_baseline;
+        ctx.set_baseline_offset(baseline_off);
+
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _children: &mut Children) {
+        let size = ctx.size();
+        let background_color = Color::GRAY;
+        let selection_color = Color::BLUE;
+        let cursor_color = Color::WHITE;
+        let border_width = 1.0;
+
+        let is_focused = ctx.is_focused();
+
+        let border_color = if is_focused {
+            Color::WHITE
+        } else {
+            Color::BLACK
+        };
+
+        // Paint the background
+        let clip_rect = size
+            .to_rect()
+            .inset(-border_width / 2.0)
+            .to_rounded_rect(3.0);
+
+        ctx.fill(clip_rect, &background_color);
+
+        // Render text, selection, and cursor inside a clip
+        ctx.with_save(|rc| {
+            rc.clip(clip_rect);
+
+            // Shift everything inside the clip by the vscroll_offset
+            rc.transform(Affine::translate((0.0, -self.vscroll_offset)));
+
+            let text_pos = self.text_position();
+            // Draw selection rect
+            if !self.text.is_empty() {
+                if is_focused {
+                    for sel in self.editor.selection_rects() {
+                        let sel = sel + text_pos.to_vec2();
+                        let rounded = sel.to_rounded_rect(1.0);
+                        rc.fill(rounded, &selection_color);
+                    }
+                }
+                // the part of the text inside the clip, after scrolling
+                let scroll = Vec2::new(0.0, self.vscroll_offset) - text_pos.to_vec2();
+                let visible = size.to_rect() + scroll;
+                self.editor.draw_visible(rc, text_pos, visible);
+            } else {
+                self.placeholder.draw(rc, text_pos);
+            }
+
+            // Paint the cursor if focused, unless the search bar has focus
+            let search_focused = self.search.as_ref().is_some_and(|search| search.focused);
+            if is_focused && self.cursor_on && !search_focused {
+                match self.input_handler.cursor_shape() {
+                    CursorShape::Line => {
+                        let cursor = self.editor.cursor_line() + text_pos.to_vec2();
+                        rc.stroke(cursor, &cursor_color, 1.);
+                        if let Some(split) = self.editor.split_cursor_line() {
+                            let split_color = cursor_color.clone().with_alpha(0.5);
+                            rc.stroke(split + text_pos.to_vec2(), &split_color, 1.);
+                        }
+                    }
+                    CursorShape::Block => {
+                        let block = self.editor.cursor_block() + text_pos.to_vec2();
+                        rc.fill(block, &cursor_color.clone().with_alpha(0.5));
+                    }
+                }
+                for cursor in self.editor.secondary_cursor_lines() {
+                    rc.stroke(cursor + text_pos.to_vec2(), &cursor_color, 1.);
+                }
+            }
+        });
+
+        if let Some(search) = &self.search {
+            search.paint(ctx, is_focused && self.cursor_on);
+        }
+        if let Some(menu) = &self.spelling_menu {
+            menu.paint(ctx);
+        }
+
+        // Paint the border
+        ctx.stroke(clip_rect, &border_color, border_width);
+    }
+}
+
+impl TextAreaObject {
+    /// Set the text and force the editor to update.
+    ///
+    /// This should be rarely needed; the main use-case would be if you need
+    /// to manually set the text and then immediately do hit-testing or other
+    /// tasks that rely on having an up-to-date text layout.
+    pub fn force_rebuild(
+        &mut self,
+        text: String,
+        factory: &mut PietText,
+        fonts: &Fonts,
+        env: &druid::Env,
+    ) {
+        self.editor.set_text(text.into());
+        self.editor.rebuild_if_needed(factory, fonts, env);
+    }
+
+    /// Convert a point in our coordinate space into the coordinate space
+    /// of the text layout.
+    fn text_point(&self, point: Point) -> Point {
+        point - self.text_position().to_vec2() + Vec2::new(0.0, self.vscroll_offset)
+    }
+
+    fn max_vscroll_offset(&self, self_height: f64) -> f64 {
+        let text_height = self.editor.layout().size().height;
+        (text_height - self_height + TEXT_INSETS.y_value()).max(0.0)
+    }
+
+    /// Scroll vertically so that the cursor stays visible.
+    fn update_vscroll(&mut self, self_height: f64) {
+        let view_height = self_height - TEXT_INSETS.y_value();
+        let cursor = self.editor.cursor_line();
+        let (cursor_top, cursor_bottom) = (cursor.p0.y, cursor.p1.y);
+
+        if cursor_bottom > self.vscroll_offset + view_height {
+            // If the cursor goes past the bottom, scroll down
+            self.vscroll_offset = cursor_bottom - view_height;
+        } else if cursor_top < self.vscroll_offset {
+            // If the cursor goes past the top, scroll up
+            self.vscroll_offset = cursor_top;
+        }
+        self.vscroll_offset = self
+            .vscroll_offset
+            .min(self.max_vscroll_offset(self_height))
+            .max(0.0);
+    }
+
+    /// Show the suggestions for the misspelled word at `pos`, returning
+    /// `false` if there is no misspelled word there.
+    fn open_spelling_menu(&mut self, pos: Point) -> bool {
+        let offset = self
+            .editor
+            .layout()
+            .text_position_for_point(self.text_point(pos));
+        match self.editor.spelling_suggestions(offset) {
+            Some((range, suggestions)) => {
+                self.spelling_menu = Some(SpellingMenu::new(range, suggestions, pos));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handle a key while the spelling menu is shown: the arrow keys choose
+    /// a suggestion, and `Enter` accepts it. Any other key closes the menu.
+    fn spelling_menu_key(&mut self, ctx: &mut EventCtx, event: &KeyEvent) {
+        let menu = match &mut self.spelling_menu {
+            Some(menu) => menu,
+            None => return,
+        };
+        let count = menu.suggestions.len();
+        match event {
+            k_e if HotKey::new(None, KbKey::ArrowDown).matches(k_e) && count > 0 => {
+                menu.selected = Some(menu.selected.map_or(0, |i| (i + 1) % count));
+            }
+            k_e if HotKey::new(None, KbKey::ArrowUp).matches(k_e) && count > 0 => {
+                menu.selected = Some(menu.selected.map_or(count - 1, |i| (i + count - 1) % count));
+            }
+            k_e if HotKey::new(None, KbKey::Enter).matches(k_e) => {
+                if let Some(menu) = self.spelling_menu.take() {
+                    if let Some(index) = menu.selected {
+                        let replacement = menu.suggestions[index].clone();
+                        self.replace_misspelling(ctx, menu.range, replacement);
+                    }
+                }
+            }
+            _ => self.spelling_menu = None,
+        }
+    }
+
+    fn replace_misspelling(&mut self, ctx: &mut EventCtx, range: Range<usize>, text: String) {
+        self.editor
+            .do_edit(EditAction::Replace(vec![(range, text)]), &mut self.text);
+        self.suppress_adjust_vscroll = false;
+        ctx.request_update();
+    }
+
+    /// Returns the search command for a key event, if the key is handled by
+    /// the search bar.
+    fn search_key(&mut self, event: &KeyEvent) -> Option<SearchCommand> {
+        if HotKey::new(SysMods::Cmd, "f").matches(event) {
+            self.open_search(false);
+            return Some(SearchCommand::QueryChanged);
+        }
+        if is_replace_key(event) {
+            self.open_search(true);
+            return Some(SearchCommand::QueryChanged);
+        }
+        let search = self.search.as_mut()?;
+        if HotKey::new(None, KbKey::F3).matches(event) {
+            Some(SearchCommand::Next)
+        } else if HotKey::new(SysMods::Shift, KbKey::F3).matches(event) {
+            Some(SearchCommand::Previous)
+        } else if search.focused {
+            Some(search.handle_key(event))
+        } else {
+            None
+        }
+    }
+
+    /// Open and focus the search bar, searching for the selected text if
+    /// there is any.
+    fn open_search(&mut self, replacing: bool) {
+        let selected = self
+            .text
+            .slice(self.editor.selection().range())
+            .filter(|selected| !selected.is_empty() && !selected.contains('\n'))
+            .map(Cow::into_owned);
+        match &mut self.search {
+            Some(search) => {
+                search.focused = true;
+                search.replacing |= replacing;
+                search.replacement_focused = false;
+                if let Some(selected) = selected {
+                    search.query.set_text(selected);
+                }
+            }
+            None => {
+                let query = selected.unwrap_or_default();
+                self.search = Some(SearchBar::new(query, replacing));
+            }
+        }
+    }
+
+    fn run_search_command(&mut self, ctx: &mut EventCtx, command: SearchCommand) {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return,
+        };
+        match command {
+            SearchCommand::None => (),
+            SearchCommand::QueryChanged => {
+                self.update_search_matches();
+                self.select_match(self.editor.selection().min(), true);
+            }
+            SearchCommand::Next => self.select_match(self.editor.selection().max(), true),
+            SearchCommand::Previous => self.select_match(self.editor.selection().min(), false),
+            SearchCommand::ReplaceOne => {
+                let range = self.editor.selection().range();
+                if self.editor.search_matches().contains(&range) {
+                    let query = search.search_query();
+                    let replacement =
+                        query.expand(self.text.as_str(), range.clone(), &search.replacement.text);
+                    self.editor.do_edit(
+                        EditAction::Replace(vec![(range, replacement)]),
+                        &mut self.text,
+                    );
+                    // the next match is selected once the editor has the new text
+                    self.search_step_pending = true;
+                    ctx.request_update();
+                } else {
+                    self.select_match(range.end, true);
+                }
+            }
+            SearchCommand::ReplaceAll => {
+                let query = search.search_query();
+                let replacements = self
+                    .editor
+                    .search_matches()
+                    .iter()
+                    .map(|range| {
+                        let replacement = query.expand(
+                            self.text.as_str(),
+                            range.clone(),
+                            &search.replacement.text,
+                        );
+                        (range.clone(), replacement)
+                    })
+                    .collect();
+                self.editor
+                    .do_edit(EditAction::Replace(replacements), &mut self.text);
+                ctx.request_update();
+            }
+            SearchCommand::Close => {
+                self.search = None;
+                self.editor.set_search_matches(Vec::new());
+            }
+        }
+    }
+
+    /// Find the matches of the search bar's query again.
+    fn update_search_matches(&mut self) {
+        if let Some(search) = &mut self.search {
+            let query = search.search_query();
+            let matches = self.text.find_all(&query);
+            search.set_status(&query, matches.len(), None);
+            self.editor.set_search_matches(matches);
+        }
+    }
+
+    /// Update the search matches after the text changed.
+    fn search_text_changed(&mut self) {
+        self.update_search_matches();
+        if std::mem::replace(&mut self.search_step_pending, false) {
+            self.select_match(self.editor.selection().max(), true);
+        }
+    }
+
+    /// Select the first match that starts at or after `from`, or the last
+    /// one that starts before it, wrapping around at the end of the text.
+    fn select_match(&mut self, from: usize, forward: bool) {
+        let matches = self.editor.search_matches();
+        let index = if forward {
+            matches
+                .iter()
+                .position(|range| range.start >= from)
+                .or_else(|| (!matches.is_empty()).then_some(0))
+        } else {
+            matches
+                .iter()
+                .rposition(|range| range.start < from)
+                .or_else(|| matches.len().checked_sub(1))
+        };
+        let (index, range) = match index {
+            Some(index) => (index, matches[index].clone()),
+            None => return,
+        };
+        let count = matches.len();
+        self.editor
+            .set_selection(Selection::new(range.start, range.end));
+        self.suppress_adjust_vscroll = false;
+        if let Some(search) = &mut self.search {
+            let query = search.search_query();
+            search.set_status(&query, count, Some(index));
+        }
+    }
+
+    fn reset_cursor_blink(&mut self, token: TimerToken) {
+        self.cursor_on = true;
+        self.cursor_timer = token;
+    }
+}
+
+/// A command from the search bar to the text area that owns it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SearchCommand {
+    None,
+    /// The query or its options changed.
+    QueryChanged,
+    Next,
+    Previous,
+    ReplaceOne,
+    ReplaceAll,
+    Close,
+}
+
+/// A single-line text field of the search bar.
+struct SearchField {
+    text: String,
+    editor: Editor<String>,
+}
+
+impl SearchField {
+    fn new(text: String) -> Self {
+        let mut editor = Editor::from_text(text.clone());
+        editor.set_selection(Selection::new(0, text.len()));
+        SearchField { text, editor }
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.editor.set_text(text.clone());
+        self.editor.set_selection(Selection::new(0, text.len()));
+        self.text = text;
+    }
+
+    /// Apply edits to the field, returning `true` if its text changed.
+    ///
+    /// As in the text area, an edit that changes the text ends the edits;
+    /// the layout is rebuilt with the new text in the next layout pass.
+    fn edit(&mut self, edits: Vec<EditAction>) -> bool {
+        let old = self.text.clone();
+        for edit in edits {
+            self.editor.do_edit(edit, &mut self.text);
+        }
+        let changed = self.text != old;
+        if changed {
+            self.editor.set_text(self.text.clone());
+        }
+        changed
+    }
+
+    /// Draw the field in `rect`, with a cursor if `cursor` is true.
+    fn paint(&self, ctx: &mut PaintCtx, rect: Rect, focused: bool, cursor: bool) {
+        ctx.fill(rect, &Color::GRAY);
+        if focused {
+            ctx.stroke(rect, &Color::WHITE, 1.0);
+        }
+        ctx.with_save(|rc| {
+            rc.clip(rect);
+            let origin = rect.origin() + Vec2::new(SEARCH_BAR_PADDING / 2.0, 0.0);
+            if focused {
+                for sel in self.editor.selection_rects() {
+                    rc.fill(sel + origin.to_vec2(), &Color::BLUE);
+                }
+            }
+            self.editor.draw(rc, origin);
+            if cursor {
+                rc.stroke(
+                    self.editor.cursor_line() + origin.to_vec2(),
+                    &Color::WHITE,
+                    1.,
+                );
+            }
+        });
+    }
+}
+
+/// The find and replace bar of a [`TextArea`], drawn over its top right
+/// corner.
+///
+/// [`TextArea`]: struct.TextArea.html
+struct SearchBar {
+    query: SearchField,
+    replacement: SearchField,
+    /// Whether the replacement field is shown.
+    replacing: bool,
+    /// Whether key events go to the search bar rather than to the text.
+    focused: bool,
+    /// Whether the replacement field, rather than the query, has focus.
+    replacement_focused: bool,
+    case_insensitive: bool,
+    whole_word: bool,
+    regex: bool,
+    input: BasicTextInput,
+    /// The number of matches, or why there are none.
+    status: TextLayout<String>,
+    /// The labels of the case, whole word and regex toggles.
+    option_labels: [TextLayout<String>; 3],
+
+    // the positions of the parts of the bar, set in layout
+    rect: Rect,
+    query_rect: Rect,
+    replacement_rect: Rect,
+    status_origin: Point,
+    option_rects: [Rect; 3],
+}
+
+impl SearchBar {
+    fn new(query: String, replacing: bool) -> Self {
+        SearchBar {
+            query: SearchField::new(query),
+            replacement: SearchField::new(String::new()),
+            replacing,
+            focused: true,
+            replacement_focused: false,
+            case_insensitive: true,
+            whole_word: false,
+            regex: false,
+            input: BasicTextInput::new(),
+            status: TextLayout::from_text(""),
+            option_labels: [
+                TextLayout::from_text("Aa"),
+                TextLayout::from_text("W"),
+                TextLayout::from_text(".*"),
+            ],
+            rect: Rect::ZERO,
+            query_rect: Rect::ZERO,
+            replacement_rect: Rect::ZERO,
+            status_origin: Point::ZERO,
+            option_rects: [Rect::ZERO; 3],
+        }
+    }
+
+    fn search_query(&self) -> SearchQuery {
+        SearchQuery::new(self.query.text.as_str())
+            .case_insensitive(self.case_insensitive)
+            .whole_word(self.whole_word)
+            .regex(self.regex)
+    }
+
+    fn focused_field(&mut self) -> &mut SearchField {
+        if self.replacement_focused {
+            &mut self.replacement
+        } else {
+            &mut self.query
+        }
+    }
+
+    /// Show the number of matches, and which of them is selected.
+    fn set_status(&mut self, query: &SearchQuery, matches: usize, current: Option<usize>) {
+        let status = match (query.error(), matches, current) {
+            (Some(_), _, _) => "Invalid pattern".to_owned(),
+            (None, 0, _) => "No results".to_owned(),
+            (None, matches, Some(current)) => format!("{} of {}", current + 1, matches),
+            (None, 1, None) => "1 match".to_owned(),
+            (None, matches, None) => format!("{} matches", matches),
+        };
+        if self.status.text().map(String::as_str) != Some(status.as_str()) {
+            self.status.set_text(status);
+        }
+    }
+
+    fn toggle_option(&mut self, option: usize) {
+        match option {
+            0 => self.case_insensitive = !self.case_insensitive,
+            1 => self.whole_word = !self.whole_word,
+            _ => self.regex = !self.regex,
+        }
+    }
+
+    fn option_active(&self, option: usize) -> bool {
+        match option {
+            // the toggle is labelled for matching case
+            0 => !self.case_insensitive,
+            1 => self.whole_word,
+            _ => self.regex,
+        }
+    }
+
+    fn handle_key(&mut self, event: &KeyEvent) -> SearchCommand {
+        match event {
+            k_e if HotKey::new(None, KbKey::Escape).matches(k_e) => SearchCommand::Close,
+            k_e if HotKey::new(SysMods::AltCmd, KbKey::Enter).matches(k_e) => {
+                if self.replacing {
+                    SearchCommand::ReplaceAll
+                } else {
+                    SearchCommand::None
+                }
+            }
+            k_e if HotKey::new(SysMods::Shift, KbKey::Enter).matches(k_e) => {
+                SearchCommand::Previous
+            }
+            k_e if HotKey::new(None, KbKey::Enter).matches(k_e) => {
+                if self.replacement_focused {
+                    SearchCommand::ReplaceOne
+                } else {
+                    SearchCommand::Next
+                }
+            }
+            k_e if HotKey::new(None, KbKey::Tab).matches(k_e)
+                || HotKey::new(SysMods::Shift, KbKey::Tab).matches(k_e) =>
+            {
+                self.replacement_focused = self.replacing && !self.replacement_focused;
+                SearchCommand::None
+            }
+            k_e if HotKey::new(SysMods::Cmd, "c").matches(k_e) => {
+                let field = self.focused_field();
+                field.editor.copy(&mut field.text);
+                SearchCommand::None
+            }
+            k_e if HotKey::new(SysMods::Cmd, "x").matches(k_e) => {
+                let field = self.focused_field();
+                field.editor.cut(&mut field.text);
+                field.editor.set_text(field.text.clone());
+                self.query_changed()
+            }
+            k_e if k_e.mods.alt() && !k_e.mods.ctrl() && !k_e.mods.meta() => {
+                match option_for_key(k_e) {
+                    Some(option) => {
+                        self.toggle_option(option);
+                        SearchCommand::QueryChanged
+                    }
+                    None => SearchCommand::None,
+                }
+            }
+            k_e => {
+                let edits = self.input.handle_event(k_e);
+                if self.focused_field().edit(edits) {
+                    self.query_changed()
+                } else {
+                    SearchCommand::None
+                }
+            }
+        }
+    }
+
+    /// The command for a change to the focused field.
+    fn query_changed(&self) -> SearchCommand {
+        if self.replacement_focused {
+            SearchCommand::None
+        } else {
+            SearchCommand::QueryChanged
+        }
+    }
+
+    fn paste(&mut self, text: String) -> SearchCommand {
+        let field = self.focused_field();
+        field.editor.paste(text, &mut field.text);
+        field.editor.set_text(field.text.clone());
+        self.query_changed()
+    }
+
+    /// Handle a click at a point relative to the text area.
+    fn click(&mut self, mouse: &MouseEvent) -> SearchCommand {
+        self.focused = true;
+        if let Some(option) = self.option_rects.iter().position(|r| r.contains(mouse.pos)) {
+            self.toggle_option(option);
+            return SearchCommand::QueryChanged;
+        }
+        let rect = if self.replacing && self.replacement_rect.contains(mouse.pos) {
+            self.replacement_focused = true;
+            self.replacement_rect
+        } else if self.query_rect.contains(mouse.pos) {
+            self.replacement_focused = false;
+            self.query_rect
+        } else {
+            return SearchCommand::None;
+        };
+        let mut mouse = mouse.clone();
+        mouse.pos -= rect.origin().to_vec2() + Vec2::new(SEARCH_BAR_PADDING / 2.0, 0.0);
+        let field = self.focused_field();
+        field.editor.click(&mouse, &mut field.text);
+        SearchCommand::None
+    }
+
+    /// Lay out the bar in a text area of the given width.
+    fn layout(&mut self, ctx: &mut LayoutCtx, width: f64) {
+        let env = ctx.env().clone();
+        let fonts = ctx.fonts();
+        self.query.editor.rebuild_if_needed(ctx.text(), fonts, &env);
+        self.replacement
+            .editor
+            .rebuild_if_needed(ctx.text(), fonts, &env);
+        self.status.rebuild_if_needed(ctx.text(), fonts, &env);
+        for label in &mut self.option_labels {
+            label.rebuild_if_needed(ctx.text(), fonts, &env);
+        }
+
+        let row_height = self.status.size().height;
+        let bar_width = SEARCH_BAR_WIDTH.min(width - 2.0 * SEARCH_BAR_PADDING);
+        let x0 = width - bar_width - SEARCH_BAR_PADDING;
+        let field_width = bar_width - 2.0 * SEARCH_BAR_PADDING;
+        let mut y = 2.0 * SEARCH_BAR_PADDING;
+        self.query_rect = Rect::from_origin_size(
+            (x0 + SEARCH_BAR_PADDING, y),
+            Size::new(field_width, row_height),
+        );
+        y += row_height + SEARCH_BAR_PADDING;
+        if self.replacing {
+            self.replacement_rect = self.query_rect.with_origin((x0 + SEARCH_BAR_PADDING, y));
+            y += row_height + SEARCH_BAR_PADDING;
+        }
+        self.status_origin = Point::new(x0 + SEARCH_BAR_PADDING, y);
+        let mut option_x = x0 + bar_width - SEARCH_BAR_PADDING;
+        for rect in self.option_rects.iter_mut().rev() {
+            option_x -= SEARCH_OPTION_WIDTH;
+            *rect =
+                Rect::from_origin_size((option_x, y), Size::new(SEARCH_OPTION_WIDTH, row_height));
+        }
+        y += row_height + SEARCH_BAR_PADDING;
+        self.rect = Rect::new(x0, SEARCH_BAR_PADDING, x0 + bar_width, y);
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, cursor_on: bool) {
+        let rounded = self.rect.to_rounded_rect(3.0);
+        ctx.fill(rounded, &Color::rgb8(0x30, 0x30, 0x30));
+        ctx.stroke(rounded, &Color::BLACK, 1.0);
+
+        let query_focused = self.focused && !self.replacement_focused;
+        self.query.paint(
+            ctx,
+            self.query_rect,
+            query_focused,
+            query_focused && cursor_on,
+        );
+        if self.replacing {
+            let focused = self.focused && self.replacement_focused;
+            self.replacement
+                .paint(ctx, self.replacement_rect, focused, focused && cursor_on);
+        }
+        self.status.draw(ctx, self.status_origin);
+        for (option, (rect, label)) in self
+            .option_rects
+            .iter()
+            .zip(&self.option_labels)
+            .enumerate()
+        {
+            if self.option_active(option) {
+                ctx.fill(rect.to_rounded_rect(2.0), &Color::BLUE);
+            }
+            let offset = (rect.size() - label.size()).to_vec2() / 2.0;
+            label.draw(ctx, rect.origin() + offset);
+        }
+    }
+}
+
+/// The search option toggled by `Alt` and a key: `C` for case, `W` for
+/// whole words and `R` for regular expressions.
+/// Returns `true` if `event` opens the search bar for replacing.
+///
+/// This is `Cmd+Option+F` on macOS, where `Cmd+H` hides the app.
+fn is_replace_key(event: &KeyEvent) -> bool {
+    if cfg!(target_os = "macos") {
+        // the option key changes the character, but not the code
+        let mods = event.mods;
+        event.code == Code::KeyF && mods.meta() && mods.alt() && !mods.ctrl() && !mods.shift()
+    } else {
+        HotKey::new(SysMods::Cmd, "h").matches(event)
+    }
+}
+
+fn option_for_key(event: &KeyEvent) -> Option<usize> {
+    // on macOS the option key changes the character, but not the code
+    match event.code {
+        Code::KeyC => return Some(0),
+        Code::KeyW => return Some(1),
+        Code::KeyR => return Some(2),
+        _ => (),
+    }
+    match &event.key {
+        KbKey::Character(c) if c.eq_ignore_ascii_case("c") => Some(0),
+        KbKey::Character(c) if c.eq_ignore_ascii_case("w") => Some(1),
+        KbKey::Character(c) if c.eq_ignore_ascii_case("r") => Some(2),
+        _ => None,
+    }
+}
+
+/// The suggested replacements for a misspelled word, shown where it was
+/// right-clicked.
+struct SpellingMenu {
+    /// The range of the misspelled word.
+    range: Range<usize>,
+    suggestions: Vec<String>,
+    /// A label for each suggestion, or a single label saying that there
+    /// are none.
+    labels: Vec<TextLayout<String>>,
+    /// The index of the highlighted suggestion.
+    selected: Option<usize>,
+    /// Where the menu was opened.
+    origin: Point,
+    row_height: f64,
+    rect: Rect,
+}
+
+impl SpellingMenu {
+    fn new(range: Range<usize>, suggestions: Vec<String>, origin: Point) -> Self {
+        let labels = if suggestions.is_empty() {
+            let mut label = TextLayout::from_text("No suggestions".to_string());
+            label.set_text_color(Color::grey(0.6));
+            vec![label]
+        } else {
+            suggestions
+                .iter()
+                .map(|suggestion| TextLayout::from_text(suggestion.clone()))
+                .collect()
+        };
+        SpellingMenu {
+            range,
+            suggestions,
+            labels,
+            selected: None,
+            origin,
+            row_height: 0.0,
+            rect: Rect::ZERO,
+        }
+    }
+
+    /// Returns the index of the suggestion at `pos`, if there is one.
+    fn index_at(&self, pos: Point) -> Option<usize> {
+        if !self.rect.contains(pos) {
+            return None;
+        }
+        let index = ((pos.y - self.rect.y0 - MENU_PADDING) / self.row_height).floor();
+        (index >= 0.0)
+            .then_some(index as usize)
+            .filter(|index| *index < self.suggestions.len())
+    }
+
+    /// Returns the suggestion at `pos`, if there is one.
+    fn item_at(&self, pos: Point) -> Option<String> {
+        self.index_at(pos)
+            .map(|index| self.suggestions[index].clone())
+    }
+
+    /// Place the menu at the point where it was opened, moved to fit in
+    /// `size` if it can.
+    fn layout(&mut self, ctx: &mut LayoutCtx, size: Size) {
+        let env = ctx.env().clone();
+        let fonts = ctx.fonts();
+        let mut width: f64 = 0.0;
+        for label in &mut self.labels {
+            label.rebuild_if_needed(ctx.text(), fonts, &env);
+            width = width.max(label.size().width);
+            self.row_height = self.row_height.max(label.size().height + MENU_PADDING);
+        }
+        let menu_size = Size::new(
+            width + 4.0 * MENU_PADDING,
+            self.labels.len() as f64 * self.row_height + 2.0 * MENU_PADDING,
+        );
+        let x = self.origin.x.min(size.width - menu_size.width).max(0.0);
+        let y = self.origin.y.min(size.height - menu_size.height).max(0.0);
+        self.rect = Rect::from_origin_size((x, y), menu_size);
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx) {
+        let rounded = self.rect.to_rounded_rect(3.0);
+        ctx.fill(rounded, &Color::rgb8(0x30, 0x30, 0x30));
+        let mut y = self.rect.y0 + MENU_PADDING;
+        for (index, label) in self.labels.iter().enumerate() {
+            if self.selected == Some(index) {
+                let row = Rect::new(self.rect.x0, y, self.rect.x1, y + self.row_height);
+                ctx.fill(row, &Color::BLUE);
+            }
+            let x = self.rect.x0 + 2.0 * MENU_PADDING;
+            label.draw(ctx, (x, y + MENU_PADDING / 2.0));
+            y += self.row_height;
+        }
+        ctx.stroke(rounded, &Color::BLACK, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose_and_cancel() {
+        let mut text = Rope::from("one\ntwo");
+        let mut textarea = TextAreaObject::create(TextArea::new(&mut text));
+        textarea.editor.set_selection(Selection::new(4, 7));
+        textarea.compose(&CompositionEvent::Preedit("ka".into()));
+        textarea.editor.set_text(textarea.text.clone());
+        assert_eq!(textarea.text, "one\nka");
+
+        textarea.compose(&CompositionEvent::Cancel);
+        textarea.editor.set_text(textarea.text.clone());
+        assert_eq!(textarea.text, "one\ntwo");
+        assert_eq!(textarea.editor.selection(), &Selection::new(4, 7));
+
+        textarea.compose(&CompositionEvent::Preedit("ka".into()));
+        textarea.editor.set_text(textarea.text.clone());
+        textarea.compose(&CompositionEvent::Commit);
+        assert_eq!(textarea.text, "one\nka");
+        assert!(!textarea.editor.is_composing());
+    }
+}
@@ -1,20 +1,75 @@
 use crate::{
+    id::ChildId,
     object::prelude::*,
-    text::{BasicTextInput, EditAction, Editor, LayoutMetrics, Selection, TextInput, TextLayout},
+    text::{
+        format::{Formatter, InputMask, ValidationError},
+        BasicTextInput, CompletionSource, Composition, CompositionEvent, CursorShape, Direction,
+        EditAction, Editor, Fonts, InputFilter, LayoutMetrics, Selection, Suggestion, TextInput,
+        TextLayout, COMPOSE,
+    },
 };
 use druid::{
-    piet::PietText, Affine, Color, Cursor, HotKey, Insets, KbKey, LifeCycle, Point, SysMods,
+    kurbo::{Circle, Line},
+    piet::PietText,
+    Affine, Color, Cursor, HotKey, Insets, KbKey, KeyEvent, LifeCycle, Point, Rect, SysMods,
     TextAlignment, TimerToken, Vec2,
 };
-use std::time::Duration;
+use std::{any::TypeId, ops::Range, time::Duration};
+
+pub use style::{Style, StyleSheet};
 
 const CURSOR_BLINK_DURATION: Duration = Duration::from_millis(500);
+/// The width of the list of completions.
+const COMPLETION_WIDTH: f64 = 240.0;
+/// The number of completions shown at once.
+const MAX_VISIBLE_COMPLETIONS: usize = 8;
+const COMPLETION_PADDING: f64 = 3.0;
+/// The z-index of the list of completions, which is painted above the
+/// widgets after the text box.
+const COMPLETION_Z_INDEX: u32 = 1000;
+/// The space between the text box and an error message under it.
+const ERROR_SPACING: f64 = 2.0;
+const TOOLTIP_PADDING: f64 = 4.0;
 
-#[derive(PartialEq)]
 pub struct TextBox<'a> {
     placeholder: &'a str,
     editable: &'a mut String,
     alignment: TextAlignment,
+    secure: bool,
+    reveal_toggle: bool,
+    text_input: (TypeId, Box<dyn TextInput>),
+    input_filter: InputFilter,
+    completions: Option<Box<dyn CompletionSource>>,
+    style: Option<Box<dyn StyleSheet>>,
+    error: Option<ValidationError>,
+    error_display: ErrorDisplay,
+}
+
+/// What happened to a [`TextBox`] since it was last built.
+///
+/// [`TextBox`]: TextBox
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextBoxAction {
+    /// Whether Enter was pressed to commit the text.
+    pub activated: bool,
+    /// The id of the text box, to tell whether it is focused before it is
+    /// built again.
+    pub(crate) id: Option<ChildId>,
+}
+
+/// How a [`TextBox`] shows the message of a validation error.
+///
+/// [`TextBox`]: TextBox
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorDisplay {
+    /// Under the text box, which grows to make room for it.
+    Inline,
+    /// In a tooltip under the text box, while the mouse is over it.
+    Tooltip,
+    /// Only the [`invalid`] style shows that there is an error.
+    ///
+    /// [`invalid`]: style/trait.StyleSheet.html#method.invalid
+    Hidden,
 }
 
 impl<'a> TextBox<'a> {
@@ -23,6 +78,17 @@ impl<'a> TextBox<'a> {
             placeholder: "",
             editable: text,
             alignment: TextAlignment::Start,
+            secure: false,
+            reveal_toggle: false,
+            text_input: (
+                TypeId::of::<BasicTextInput>(),
+                Box::new(BasicTextInput::new()),
+            ),
+            input_filter: InputFilter::new(),
+            completions: None,
+            style: None,
+            error: None,
+            error_display: ErrorDisplay::Inline,
         }
     }
 
@@ -36,7 +102,126 @@ impl<'a> TextBox<'a> {
         self
     }
 
+    /// Builder-style method to mask the text, as for password entry.
+    ///
+    /// A secure text box shows a bullet for each character and does not
+    /// allow its contents to be copied or cut.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Builder-style method to show a toggle that reveals the text of a
+    /// [`secure`] text box while it is switched on.
+    ///
+    /// [`secure`]: #method.secure
+    pub fn reveal_toggle(mut self, reveal_toggle: bool) -> Self {
+        self.reveal_toggle = reveal_toggle;
+        self
+    }
+
+    /// Builder-style method to set the [`TextInput`] that maps key events
+    /// to edits, such as a [`VimTextInput`].
+    ///
+    /// The handler keeps its state, such as the vim mode, between builds;
+    /// it is only replaced when a handler of a different type is passed.
+    ///
+    /// [`TextInput`]: ../text/trait.TextInput.html
+    /// [`VimTextInput`]: ../text/struct.VimTextInput.html
+    pub fn text_input<I: TextInput + 'static>(mut self, input: I) -> Self {
+        self.text_input = (TypeId::of::<I>(), Box::new(input));
+        self
+    }
+
+    /// Builder-style method to limit the text to `max_length` grapheme
+    /// clusters.
+    ///
+    /// Typing is ignored once the limit is reached, and pasted text is
+    /// truncated to fit.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.input_filter = self.input_filter.max_length(max_length);
+        self
+    }
+
+    /// Builder-style method to only accept typed or pasted text for which
+    /// `filter` returns `true` when it is passed the edited text.
+    pub fn filter(mut self, filter: impl Fn(&str) -> bool + 'static) -> Self {
+        self.input_filter = self.input_filter.text(filter);
+        self
+    }
+
+    /// Builder-style method to only accept the characters for which
+    /// `filter` returns `true`.
+    ///
+    /// Other characters are left out of pasted text.
+    pub fn char_filter(mut self, filter: impl Fn(char) -> bool + 'static) -> Self {
+        self.input_filter = self.input_filter.chars(filter);
+        self
+    }
+
+    /// Builder-style method to restrict the text to an [`InputMask`], such
+    /// as digits only.
+    ///
+    /// [`InputMask`]: ../text/format/enum.InputMask.html
+    pub fn input_mask(mut self, mask: InputMask) -> Self {
+        self.input_filter = self.input_filter.formatter(mask);
+        self
+    }
+
+    /// Builder-style method to only accept typed or pasted text that
+    /// `formatter` finds valid with [`validate_partial_input`].
+    ///
+    /// When an edit is rejected, the message of the error is shown as for
+    /// [`validation_error`] until the next edit, and the selection is set
+    /// if the [`Validation`] has one, such as the range that is invalid.
+    ///
+    /// [`validate_partial_input`]: ../text/format/trait.Formatter.html#tymethod.validate_partial_input
+    /// [`validation_error`]: #method.validation_error
+    /// [`Validation`]: ../text/format/struct.Validation.html
+    pub fn formatter<T>(mut self, formatter: impl Formatter<T> + 'static) -> Self {
+        self.input_filter = self.input_filter.formatter(formatter);
+        self
+    }
+
+    pub fn style(mut self, style: impl Into<Box<dyn StyleSheet>>) -> Self {
+        self.style = Some(style.into());
+        self
+    }
+
+    /// Builder-style method to show that the text is invalid, using the
+    /// [`invalid`] style and showing the message of `error`.
+    ///
+    /// [`invalid`]: style/trait.StyleSheet.html#method.invalid
+    pub fn validation_error(mut self, error: Option<ValidationError>) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// Builder-style method to set how the message of a validation error is
+    /// shown. The default is [`ErrorDisplay::Inline`].
+    ///
+    /// [`ErrorDisplay::Inline`]: enum.ErrorDisplay.html#variant.Inline
+    pub fn error_display(mut self, display: ErrorDisplay) -> Self {
+        self.error_display = display;
+        self
+    }
+
+    /// Builder-style method to suggest completions from `source` as the
+    /// user types.
+    ///
+    /// The suggestions are shown in a list under the word being typed. The
+    /// arrow keys choose a suggestion, Enter or Tab replace the word with
+    /// it, and Escape dismisses the list.
+    pub fn completions(mut self, source: impl CompletionSource + 'static) -> Self {
+        self.completions = Some(Box::new(source));
+        self
+    }
+
     pub fn build(self, ui: &mut Ui) -> bool {
+        self.build_action(ui).activated
+    }
+
+    pub(crate) fn build_action(self, ui: &mut Ui) -> TextBoxAction {
         let caller = Location::caller().into();
         ui.render_object(caller, self, |_| {})
     }
@@ -52,9 +237,25 @@ pub struct TextBoxObject {
     editor: Editor<String>,
     alignment: TextAlignment,
     activated: bool,
+    secure: bool,
+    reveal_toggle: bool,
+    /// true if the reveal toggle is switched on.
+    revealed: bool,
 
-    // this can be Box<dyn TextInput> in the future
-    input_handler: BasicTextInput,
+    input_handler: Box<dyn TextInput>,
+    /// The type of `input_handler`, to tell when it is replaced.
+    input_type: TypeId,
+    completions: Option<Box<dyn CompletionSource>>,
+    /// The list of completions, while it is shown.
+    completion_list: Option<CompletionList>,
+    style: Box<dyn StyleSheet>,
+    /// The error set with `TextBox::validation_error`.
+    error: Option<ValidationError>,
+    error_display: ErrorDisplay,
+    /// The message of the error that is shown, if any.
+    error_label: TextLayout<String>,
+    /// The size of the text box without an inline error message.
+    field_size: Size,
     hscroll_offset: f64,
     // in cases like SelectAll, we don't adjust the viewport after an event.
     suppress_adjust_hscroll: bool,
@@ -81,18 +282,40 @@ impl TextBoxObject {
     pub fn text_position(&self) -> Point {
         self.text_pos
     }
+
+    /// Apply a step of composition by an input method, sent with the
+    /// [`COMPOSE`] command.
+    ///
+    /// [`COMPOSE`]: ../../text/constant.COMPOSE.html
+    fn compose(&mut self, event: &CompositionEvent) {
+        self.editor.compose(event, &mut self.text);
+    }
 }
 
 impl RenderObject<TextBox<'_>> for TextBoxObject {
-    type Action = bool;
+    type Action = TextBoxAction;
 
     fn create(props: TextBox<'_>) -> Self {
+        let mut editor = Editor::from_text(&*props.editable);
+        editor.set_masked(props.secure);
+        editor.set_input_filter(props.input_filter);
         TextBoxObject {
             placeholder: TextLayout::from_text(props.placeholder),
             text: String::from(&*props.editable),
-            editor: Editor::from_text(&*props.editable),
-            input_handler: BasicTextInput::default(),
+            editor,
+            input_type: props.text_input.0,
+            input_handler: props.text_input.1,
+            completions: props.completions,
+            completion_list: None,
+            style: props.style.unwrap_or_default(),
+            error: props.error,
+            error_display: props.error_display,
+            error_label: TextLayout::new(),
+            field_size: Size::ZERO,
             activated: false,
+            secure: props.secure,
+            reveal_toggle: props.reveal_toggle,
+            revealed: false,
 
             hscroll_offset: 0.,
             suppress_adjust_hscroll: false,
@@ -113,6 +336,8 @@ impl RenderObject<TextBox<'_>> for TextBoxObject {
             } else {
                 self.text = props.editable.to_owned();
                 self.editor.set_text(props.editable.to_owned());
+                self.editor.clear_history();
+                self.editor.discard_preedit();
             }
             ctx.request_layout();
         }
@@ -124,10 +349,50 @@ impl RenderObject<TextBox<'_>> for TextBoxObject {
             self.alignment = props.alignment;
             ctx.request_layout();
         }
+        if props.secure != self.secure || props.reveal_toggle != self.reveal_toggle {
+            self.secure = props.secure;
+            self.reveal_toggle = props.reveal_toggle;
+            self.revealed &= self.reveal_toggle;
+            self.editor.set_masked(self.secure && !self.revealed);
+            ctx.request_layout();
+        }
 
-        let was_activated = self.activated;
+        // filters can't be compared, so the editor always takes the new one
+        self.editor.set_input_filter(props.input_filter);
+        self.completions = props.completions;
+        if self.completions.is_none() && self.completion_list.is_some() {
+            self.completion_list = None;
+            ctx.request_paint_rect(self.completion_bounds(ctx.size()));
+        }
+
+        if props.text_input.0 != self.input_type {
+            self.input_type = props.text_input.0;
+            self.input_handler = props.text_input.1;
+            ctx.request_paint();
+        }
+
+        let style = props.style.unwrap_or_default();
+        if !StyleSheet::eq(style.as_ref(), self.style.as_ref()) {
+            self.style = style;
+            ctx.request_layout();
+        }
+        self.error = props.error;
+        let message = self.error().map(ToString::to_string).unwrap_or_default();
+        if props.error_display != self.error_display
+            || self.error_label.text().map(String::as_str) != Some(&message)
+        {
+            ctx.request_paint_rect(self.tooltip_bounds(ctx.size()));
+            self.error_display = props.error_display;
+            self.error_label.set_text(message);
+            ctx.request_layout();
+        }
+
+        let activated = self.activated;
         self.activated = false;
-        was_activated
+        TextBoxAction {
+            activated,
+            id: Some(ctx.child_id()),
+        }
     }
 }
 
@@ -135,24 +400,39 @@ impl RenderObjectInterface for TextBoxObject {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, _children: &mut Children) {
         self.suppress_adjust_hscroll = false;
         match event {
+            Event::MouseDown(mouse)
+                if self.reveal_toggle_rect(self.field_size).contains(mouse.pos) =>
+            {
+                self.revealed = !self.revealed;
+                self.editor.set_masked(self.secure && !self.revealed);
+                ctx.request_layout();
+                ctx.request_paint();
+            }
             Event::MouseDown(mouse) => {
                 ctx.request_focus();
                 ctx.set_active(true);
+                self.close_completions(ctx);
                 let mut mouse = mouse.clone();
                 mouse.pos += Vec2::new(self.hscroll_offset - self.alignment_offset, 0.0);
 
                 if !mouse.focus {
                     self.was_focused_from_click = true;
                     self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                    self.input_handler.handle_click();
                     self.editor.click(&mouse, &mut self.text);
                 }
 
                 ctx.request_paint();
             }
             Event::MouseMove(mouse) => {
+                let over_toggle = self.reveal_toggle_rect(self.field_size).contains(mouse.pos);
                 let mut mouse = mouse.clone();
                 mouse.pos += Vec2::new(self.hscroll_offset - self.alignment_offset, 0.0);
-                ctx.set_cursor(&Cursor::IBeam);
+                if over_toggle {
+                    ctx.set_cursor(&Cursor::Arrow);
+                } else {
+                    ctx.set_cursor(&Cursor::IBeam);
+                }
                 if ctx.is_active() {
                     self.editor.drag(&mouse, &mut self.text);
                     ctx.request_paint();
@@ -183,13 +463,46 @@ impl RenderObjectInterface for TextBoxObject {
             //     let edit = cmd.get_unchecked(TextBox::PERFORM_EDIT);
             //     self.editor.do_edit(edit.to_owned(), data);
             // }
+            Event::Command(cmd) if ctx.is_focused() && cmd.is(COMPOSE) => {
+                self.compose(cmd.get_unchecked(COMPOSE));
+                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                ctx.request_update();
+                ctx.set_handled();
+            }
             Event::Paste(ref item) => {
                 if let Some(string) = item.get_string() {
                     self.editor.paste(string, &mut self.text);
+                    self.update_completions(ctx);
+                    ctx.request_update();
                 }
             }
+            Event::KeyDown(key_event) if self.completion_key(ctx, key_event) => {
+                self.reset_cursor_blink(ctx.request_timer(CURSOR_BLINK_DURATION));
+                ctx.request_paint();
+            }
             Event::KeyDown(key_event) => {
                 match key_event {
+                    // Copy and cut; these do nothing in secure mode
+                    k_e if HotKey::new(SysMods::Cmd, "c").matches(k_e) => {
+                        self.editor.copy(&mut self.text)
+                    }
+                    k_e if HotKey::new(SysMods::Cmd, "x").matches(k_e) => {
+                        self.editor.cut(&mut self.text);
+                        ctx.request_update();
+                    }
+                    // Confirm or cancel text being composed by an input method
+                    k_e if self.editor.is_composing()
+                        && HotKey::new(None, KbKey::Enter).matches(k_e) =>
+                    {
+                        self.editor.commit_preedit(&mut self.text);
+                        ctx.request_update();
+                    }
+                    k_e if self.editor.is_composing()
+                        && HotKey::new(None, KbKey::Escape).matches(k_e) =>
+                    {
+                        self.editor.cancel_preedit(&mut self.text);
+                        ctx.request_update();
+                    }
                     // Tab and shift+tab
                     k_e if HotKey::new(None, KbKey::Tab).matches(k_e) => ctx.focus_next(),
                     k_e if HotKey::new(SysMods::Shift, KbKey::Tab).matches(k_e) => ctx.focus_prev(),
@@ -205,9 +518,30 @@ impl RenderObjectInterface for TextBoxObject {
                         ctx.request_update();
                     }
                     k_e => {
-                        if let Some(edit) = self.input_handler.handle_event(k_e) {
-                            self.suppress_adjust_hscroll = matches!(edit, EditAction::SelectAll);
-                            self.editor.do_edit(edit, &mut self.text);
+                        self.input_handler
+                            .set_secondary_cursors(!self.editor.secondary_selections().is_empty());
+                        let edits = self.input_handler.handle_event(k_e);
+                        if !edits.is_empty() {
+                            // typing shows completions, other edits such as
+                            // movement dismiss them
+                            let typed = edits.iter().all(|edit| {
+                                matches!(
+                                    edit,
+                                    EditAction::Insert(_)
+                                        | EditAction::Backspace
+                                        | EditAction::Delete
+                                )
+                            });
+                            for edit in edits {
+                                self.suppress_adjust_hscroll =
+                                    matches!(edit, EditAction::SelectAll);
+                                self.editor.do_edit(edit, &mut self.text);
+                            }
+                            if typed {
+                                self.update_completions(ctx);
+                            } else {
+                                self.close_completions(ctx);
+                            }
                             ctx.request_update();
                             ctx.request_paint();
                         }
@@ -220,7 +554,13 @@ impl RenderObjectInterface for TextBoxObject {
         }
     }
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::HotChanged(_) = event {
+            if self.error_display == ErrorDisplay::Tooltip && self.error().is_some() {
+                ctx.request_paint_rect(self.tooltip_bounds(self.field_size));
+            }
+        }
+    }
 
     fn layout(
         &mut self,
@@ -232,26 +572,41 @@ impl RenderObjectInterface for TextBoxObject {
         let text_insets = Insets::uniform(3.0);
 
         let env = ctx.env().clone();
-        self.placeholder.rebuild_if_needed(ctx.text(), &env);
+        let fonts = ctx.fonts();
+        self.placeholder.rebuild_if_needed(ctx.text(), fonts, &env);
         if self.editor.multiline() {
             self.editor
                 .set_wrap_width(bc.max().width - text_insets.x_value());
         }
-        self.editor.rebuild_if_needed(ctx.text(), &env);
+        self.editor.rebuild_if_needed(ctx.text(), fonts, &env);
+        if let Some(list) = &mut self.completion_list {
+            list.rebuild_if_needed(ctx.text(), fonts, &env);
+        }
+        let style = self.current_style(ctx.is_focused());
+        self.error_label.set_text_color(style.error_color);
+        self.error_label.rebuild_if_needed(ctx.text(), fonts, &env);
 
         let text_metrics = if self.text.is_empty() {
             self.placeholder.layout_metrics()
         } else {
-            self.editor.layout().layout_metrics()
+            self.editor.layout_metrics()
         };
 
         let height = text_metrics.size.height + text_insets.y_value();
-        let size = bc.constrain((width, height));
+        let error_height = if self.error_display == ErrorDisplay::Inline && self.error().is_some() {
+            self.error_label.size().height + ERROR_SPACING
+        } else {
+            0.0
+        };
+        let size = bc.constrain((width, height + error_height));
+        self.field_size = Size::new(size.width, (size.height - error_height).max(0.0));
+        let text_width =
+            size.width - text_insets.x_value() - self.reveal_toggle_rect(self.field_size).width();
         // if we have a non-left text-alignment, we need to manually adjust our position.
-        self.update_alignment_adjustment(size.width - text_insets.x_value(), &text_metrics);
+        self.update_alignment_adjustment(text_width, &text_metrics);
         self.text_pos = Point::new(text_insets.x0 + self.alignment_offset, text_insets.y0);
 
-        let bottom_padding = (size.height - text_metrics.size.height) / 2.0;
+        let bottom_padding = (self.field_size.height - text_metrics.size.height) / 2.0;
         let baseline_off =
             bottom_padding + (text_metrics.size.height - text_metrics.first_baseline);
         ctx.set_baseline_offset(baseline_off);
@@ -260,32 +615,31 @@ impl RenderObjectInterface for TextBoxObject {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, _children: &mut Children) {
-        let size = ctx.size();
-        let background_color = Color::GRAY;
+        let size = self.field_size;
         let selection_color = Color::BLUE;
         let cursor_color = Color::WHITE;
-        let border_width = 1.0;
         let text_insets = Insets::uniform(3.0);
 
         let is_focused = ctx.is_focused();
-
-        let border_color = if is_focused {
-            Color::WHITE
-        } else {
-            Color::BLACK
-        };
+        let style = self.current_style(is_focused);
+        let border_width = style.border_width;
 
         // Paint the background
         let clip_rect = Size::new(size.width - border_width, size.height)
             .to_rect()
             .inset(-border_width / 2.0)
-            .to_rounded_rect(3.0);
+            .to_rounded_rect(style.border_radius);
+
+        ctx.fill(clip_rect, &style.background);
 
-        ctx.fill(clip_rect, &background_color);
+        let toggle_rect = self.reveal_toggle_rect(size);
 
         // Render text, selection, and cursor inside a clip
         ctx.with_save(|rc| {
             rc.clip(clip_rect);
+            if toggle_rect.area() > 0.0 {
+                rc.clip(Rect::new(0.0, 0.0, toggle_rect.x0, size.height));
+            }
 
             // Shift everything inside the clip by the hscroll_offset
             rc.transform(Affine::translate((-self.hscroll_offset, 0.)));
@@ -321,18 +675,67 @@ impl RenderObjectInterface for TextBoxObject {
                     // (commonly when there is trailing whitespace) so we clamp it
                     // to the right edge.
                     let mut cursor = self.editor.cursor_line() + text_pos.to_vec2();
-                    let dx = size.width + self.hscroll_offset - text_insets.x0 - cursor.p0.x;
+                    let right_edge = size.width - toggle_rect.width();
+                    let dx = right_edge + self.hscroll_offset - text_insets.x0 - cursor.p0.x;
                     if dx < 0.0 {
                         cursor = cursor + Vec2::new(dx, 0.);
                     }
                     cursor
                 };
-                rc.stroke(cursor, &cursor_color, 1.);
+                match self.input_handler.cursor_shape() {
+                    CursorShape::Line => {
+                        rc.stroke(cursor, &cursor_color, 1.);
+                        // the other half of a cursor between runs of text in
+                        // different directions
+                        if let Some(split) = self.editor.split_cursor_line() {
+                            let split_color = cursor_color.clone().with_alpha(0.5);
+                            rc.stroke(split + text_pos.to_vec2(), &split_color, 1.);
+                        }
+                    }
+                    CursorShape::Block => {
+                        let block = self.editor.cursor_block();
+                        let block = block + (cursor.p0 - block.origin());
+                        rc.fill(block, &cursor_color.clone().with_alpha(0.5));
+                    }
+                }
+                for cursor in self.editor.secondary_cursor_lines() {
+                    rc.stroke(cursor + text_pos.to_vec2(), &cursor_color, 1.);
+                }
             }
         });
 
+        // Paint the reveal toggle as an eye that is crossed out while the text is hidden
+        if toggle_rect.area() > 0.0 {
+            let center = toggle_rect.center();
+            let radius = toggle_rect.height() / 4.0;
+            ctx.stroke(Circle::new(center, radius), &cursor_color, 1.0);
+            ctx.fill(Circle::new(center, radius / 2.5), &cursor_color);
+            if !self.revealed {
+                let slash = Line::new(
+                    center + Vec2::new(-radius, radius),
+                    center + Vec2::new(radius, -radius),
+                );
+                ctx.stroke(slash, &cursor_color, 1.5);
+            }
+        }
+
         // Paint the border
-        ctx.stroke(clip_rect, &border_color, border_width);
+        ctx.stroke(clip_rect, &style.border_color, border_width);
+
+        if self.error().is_some() {
+            match self.error_display {
+                ErrorDisplay::Inline => {
+                    let origin = (text_insets.x0, size.height + ERROR_SPACING);
+                    self.error_label.draw(ctx, origin);
+                }
+                ErrorDisplay::Tooltip if ctx.is_hot() => self.paint_tooltip(ctx, &style),
+                _ => (),
+            }
+        }
+
+        if is_focused {
+            self.paint_completions(ctx);
+        }
     }
 }
 
@@ -347,9 +750,15 @@ impl TextBoxObject {
     /// This should be rarely needed; the main use-case would be if you need
     /// to manually set the text and then immediately do hit-testing or other
     /// tasks that rely on having an up-to-date text layout.
-    pub fn force_rebuild(&mut self, text: String, factory: &mut PietText, env: &druid::Env) {
+    pub fn force_rebuild(
+        &mut self,
+        text: String,
+        factory: &mut PietText,
+        fonts: &Fonts,
+        env: &druid::Env,
+    ) {
         self.editor.set_text(text);
-        self.editor.rebuild_if_needed(factory, env);
+        self.editor.rebuild_if_needed(factory, fonts, env);
     }
 
     #[allow(dead_code)]
@@ -391,6 +800,154 @@ impl TextBoxObject {
         }
     }
 
+    /// The area of the reveal toggle, or an empty `Rect` if there is none.
+    fn reveal_toggle_rect(&self, size: Size) -> Rect {
+        if self.secure && self.reveal_toggle {
+            Rect::new(size.width - size.height, 0.0, size.width, size.height)
+        } else {
+            Rect::ZERO
+        }
+    }
+
+    /// Show the completions for the word at the cursor, or hide the list if
+    /// there are none.
+    fn update_completions(&mut self, ctx: &mut EventCtx) {
+        let had_list = self.completion_list.is_some();
+        self.completion_list = match &self.completions {
+            // don't give away the contents of masked text
+            Some(source) if !self.editor.is_masked() => {
+                let cursor = self.editor.selection().end;
+                let suggestions = source.suggestions(&self.text, cursor);
+                if suggestions.is_empty() {
+                    None
+                } else {
+                    let range = source.word_range(&self.text, cursor);
+                    Some(CompletionList::new(suggestions, range))
+                }
+            }
+            _ => None,
+        };
+        if had_list || self.completion_list.is_some() {
+            ctx.request_paint_rect(self.completion_bounds(ctx.size()));
+            ctx.request_layout();
+        }
+    }
+
+    fn close_completions(&mut self, ctx: &mut EventCtx) {
+        if self.completion_list.take().is_some() {
+            ctx.request_paint_rect(self.completion_bounds(ctx.size()));
+        }
+    }
+
+    /// Handle a key event for the list of completions, returning `true` if
+    /// the list used it.
+    fn completion_key(&mut self, ctx: &mut EventCtx, event: &KeyEvent) -> bool {
+        let list = match &mut self.completion_list {
+            Some(list) => list,
+            None => return false,
+        };
+        match event {
+            k_e if HotKey::new(None, KbKey::ArrowDown).matches(k_e) => list.select_next(true),
+            k_e if HotKey::new(None, KbKey::ArrowUp).matches(k_e) => list.select_next(false),
+            k_e if HotKey::new(None, KbKey::Enter).matches(k_e)
+                || HotKey::new(None, KbKey::Tab).matches(k_e) =>
+            {
+                let replacement = list.suggestions[list.selected].replacement.clone();
+                let range = list.range.clone();
+                self.editor.do_edit(
+                    EditAction::Replace(vec![(range, replacement)]),
+                    &mut self.text,
+                );
+                self.close_completions(ctx);
+                ctx.request_update();
+            }
+            k_e if HotKey::new(None, KbKey::Escape).matches(k_e) => self.close_completions(ctx),
+            _ => return false,
+        }
+        ctx.request_paint_rect(self.completion_bounds(ctx.size()));
+        true
+    }
+
+    /// The area that the list of completions may cover, wherever the word
+    /// being completed is.
+    fn completion_bounds(&self, size: Size) -> Rect {
+        let row_height = self.editor.layout_metrics().size.height + 2.0 * COMPLETION_PADDING;
+        let list_height = MAX_VISIBLE_COMPLETIONS as f64 * row_height + 2.0 * COMPLETION_PADDING;
+        Rect::new(
+            0.0,
+            0.0,
+            size.width + COMPLETION_WIDTH,
+            size.height + list_height,
+        )
+    }
+
+    /// Paint the list of completions under the start of the word being
+    /// completed, above any widgets painted after this one.
+    fn paint_completions(&self, ctx: &mut PaintCtx) {
+        let list = match &self.completion_list {
+            Some(list) => list,
+            None => return,
+        };
+        // the word is only known to fit the text once the editor is updated
+        if self.editor.layout().text() != Some(&self.text) || self.text.len() < list.range.end {
+            return;
+        }
+        let layout = self.editor.layout();
+        let x = layout.point_for_text_position(list.range.start).x;
+        let line = layout.cursor_line_for_text_position(list.range.start);
+        let text_pos = self.text_position();
+        let origin = Point::new(
+            (text_pos.x + x - self.hscroll_offset).max(0.0),
+            (text_pos.y + line.p1.y).min(self.field_size.height),
+        );
+        let list = list.clone();
+        ctx.paint_with_z_index(COMPLETION_Z_INDEX, move |ctx| list.paint(ctx, origin));
+    }
+
+    /// The error that is shown: the one set with
+    /// `TextBox::validation_error`, or else the one that rejected the last
+    /// edit.
+    fn error(&self) -> Option<&ValidationError> {
+        self.error.as_ref().or_else(|| self.editor.rejection())
+    }
+
+    fn current_style(&self, is_focused: bool) -> Style {
+        if self.error().is_some() {
+            self.style.invalid()
+        } else if is_focused {
+            self.style.focused()
+        } else {
+            self.style.enabled()
+        }
+    }
+
+    /// The area that the tooltip with the error message may cover.
+    fn tooltip_bounds(&self, size: Size) -> Rect {
+        let label = self.error_label.size();
+        Rect::new(
+            0.0,
+            0.0,
+            size.width.max(label.width + 2.0 * TOOLTIP_PADDING),
+            self.field_size.height + ERROR_SPACING + label.height + 2.0 * TOOLTIP_PADDING,
+        )
+    }
+
+    /// Paint the error message in a tooltip under the text box, above any
+    /// widgets painted after this one.
+    fn paint_tooltip(&self, ctx: &mut PaintCtx, style: &Style) {
+        let label = self.error_label.clone();
+        let origin = Point::new(0.0, self.field_size.height + ERROR_SPACING);
+        let size = label.size() + Size::new(2.0, 2.0) * TOOLTIP_PADDING;
+        let rect = Rect::from_origin_size(origin, size).to_rounded_rect(3.0);
+        let background = style.background.clone();
+        let border_color = style.border_color.clone();
+        ctx.paint_with_z_index(COMPLETION_Z_INDEX, move |ctx| {
+            ctx.fill(rect, &background);
+            ctx.stroke(rect, &border_color, 1.0);
+            label.draw(ctx, origin + Vec2::new(TOOLTIP_PADDING, TOOLTIP_PADDING));
+        });
+    }
+
     fn reset_cursor_blink(&mut self, token: TimerToken) {
         self.cursor_on = true;
         self.cursor_timer = token;
@@ -412,7 +969,14 @@ impl TextBoxObject {
             0.0
         } else {
             let extra_space = (available_width - metrics.size.width).max(0.0);
-            match self.alignment {
+            // paragraphs in different directions are aligned by the layout,
+            // within its own width
+            let alignment = match Direction::of_text(&self.text) {
+                Some(direction) => direction.resolve_alignment(self.alignment),
+                None if self.alignment == TextAlignment::Center => TextAlignment::Center,
+                None => TextAlignment::Start,
+            };
+            match alignment {
                 TextAlignment::Start | TextAlignment::Justified => 0.0,
                 TextAlignment::End => extra_space,
                 TextAlignment::Center => extra_space / 2.0,
@@ -420,3 +984,210 @@ impl TextBoxObject {
         }
     }
 }
+
+/// The list of completions shown under the word being typed.
+#[derive(Clone)]
+struct CompletionList {
+    suggestions: Vec<Suggestion>,
+    /// The range of the word that an accepted suggestion replaces.
+    range: Range<usize>,
+    /// The index of the chosen suggestion.
+    selected: usize,
+    /// The index of the first suggestion that is shown.
+    first: usize,
+    labels: Vec<TextLayout<String>>,
+    details: Vec<Option<TextLayout<String>>>,
+}
+
+impl CompletionList {
+    fn new(suggestions: Vec<Suggestion>, range: Range<usize>) -> Self {
+        let labels = suggestions
+            .iter()
+            .map(|suggestion| TextLayout::from_text(suggestion.label.clone()))
+            .collect();
+        let details = suggestions
+            .iter()
+            .map(|suggestion| {
+                suggestion.detail.clone().map(|detail| {
+                    let mut layout = TextLayout::from_text(detail);
+                    layout.set_text_color(Color::grey(0.7));
+                    layout
+                })
+            })
+            .collect();
+        CompletionList {
+            suggestions,
+            range,
+            selected: 0,
+            first: 0,
+            labels,
+            details,
+        }
+    }
+
+    /// Choose the next or previous suggestion, wrapping around at the ends,
+    /// and scroll it into view.
+    fn select_next(&mut self, forward: bool) {
+        let count = self.suggestions.len();
+        self.selected = if forward {
+            (self.selected + 1) % count
+        } else {
+            (self.selected + count - 1) % count
+        };
+        if self.selected < self.first {
+            self.first = self.selected;
+        } else if self.selected >= self.first + MAX_VISIBLE_COMPLETIONS {
+            self.first = self.selected + 1 - MAX_VISIBLE_COMPLETIONS;
+        }
+    }
+
+    fn rebuild_if_needed(&mut self, factory: &mut PietText, fonts: &Fonts, env: &druid::Env) {
+        for label in &mut self.labels {
+            label.rebuild_if_needed(factory, fonts, env);
+        }
+        for detail in self.details.iter_mut().flatten() {
+            detail.rebuild_if_needed(factory, fonts, env);
+        }
+    }
+
+    fn paint(&self, ctx: &mut PaintCtx, origin: Point) {
+        let row_height =
+            self.labels.first().map_or(0.0, |label| label.size().height) + 2.0 * COMPLETION_PADDING;
+        let visible = self.first..(self.first + MAX_VISIBLE_COMPLETIONS).min(self.labels.len());
+        let height = visible.len() as f64 * row_height + 2.0 * COMPLETION_PADDING;
+        let rect = Rect::from_origin_size(origin, Size::new(COMPLETION_WIDTH, height));
+        let rounded = rect.to_rounded_rect(3.0);
+        ctx.fill(rounded, &Color::rgb8(0x30, 0x30, 0x30));
+
+        ctx.with_save(|ctx| {
+            ctx.clip(rect);
+            let mut y = origin.y + COMPLETION_PADDING;
+            for index in visible {
+                let row = Rect::new(rect.x0, y, rect.x1, y + row_height);
+                if index == self.selected {
+                    ctx.fill(row, &Color::BLUE);
+                }
+                let text_y = y + COMPLETION_PADDING;
+                self.labels[index].draw(ctx, (rect.x0 + 2.0 * COMPLETION_PADDING, text_y));
+                if let Some(detail) = &self.details[index] {
+                    let x = rect.x1 - 2.0 * COMPLETION_PADDING - detail.size().width;
+                    detail.draw(ctx, (x, text_y));
+                }
+                y += row_height;
+            }
+        });
+        ctx.stroke(rounded, &Color::BLACK, 1.0);
+    }
+}
+
+pub mod style {
+    use crate::piet::Color;
+    use std::any::Any;
+
+    /// The appearance of a text box.
+    #[derive(Debug, Clone)]
+    pub struct Style {
+        pub background: Color,
+        pub border_width: f64,
+        pub border_radius: f64,
+        pub border_color: Color,
+        /// The color of the message of a validation error.
+        pub error_color: Color,
+    }
+
+    impl PartialEq for Box<dyn StyleSheet> {
+        fn eq(&self, other: &Self) -> bool {
+            self.as_ref().eq(other.as_ref())
+        }
+    }
+
+    /// A set of rules that dictate the style of a text box.
+    pub trait StyleSheet: Any {
+        fn eq(&self, other: &dyn StyleSheet) -> bool;
+
+        fn enabled(&self) -> Style;
+
+        fn focused(&self) -> Style {
+            self.enabled()
+        }
+
+        /// The style of a text box with a validation error.
+        fn invalid(&self) -> Style {
+            let enabled = self.enabled();
+
+            Style {
+                border_color: enabled.error_color.clone(),
+                ..enabled
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Default;
+
+    impl StyleSheet for Default {
+        fn enabled(&self) -> Style {
+            Style {
+                background: Color::GRAY,
+                border_width: 1.0,
+                border_radius: 3.0,
+                border_color: Color::BLACK,
+                error_color: Color::rgb8(0xe0, 0x30, 0x30),
+            }
+        }
+
+        fn focused(&self) -> Style {
+            Style {
+                border_color: Color::WHITE,
+                ..self.enabled()
+            }
+        }
+
+        fn eq(&self, other: &dyn StyleSheet) -> bool {
+            Any::type_id(other) == std::any::TypeId::of::<Self>()
+        }
+    }
+
+    impl std::default::Default for Box<dyn StyleSheet> {
+        fn default() -> Self {
+            Box::new(Default)
+        }
+    }
+
+    impl<T> From<T> for Box<dyn StyleSheet>
+    where
+        T: 'static + StyleSheet,
+    {
+        fn from(style: T) -> Self {
+            Box::new(style)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compose_and_commit() {
+        let mut text = "hello ".to_string();
+        let mut textbox = TextBoxObject::create(TextBox::new(&mut text));
+        textbox.editor.set_selection(Selection::caret(6));
+        // the text is passed back to the editor on update, after each event
+        for event in [
+            CompositionEvent::Preedit("n".into()),
+            CompositionEvent::Preedit("ni".into()),
+            CompositionEvent::Preedit("\u{4f60}".into()),
+        ] {
+            textbox.compose(&event);
+            textbox.editor.set_text(textbox.text.clone());
+            assert!(textbox.editor.is_composing());
+        }
+        assert_eq!(textbox.text, "hello \u{4f60}");
+
+        textbox.compose(&CompositionEvent::Commit);
+        assert_eq!(textbox.text, "hello \u{4f60}");
+        assert!(!textbox.editor.is_composing());
+        assert_eq!(textbox.editor.selection(), &Selection::caret(9));
+    }
+}
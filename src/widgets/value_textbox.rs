@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use crate::{
+    id::ChildId,
+    object::prelude::*,
+    text::format::{Formatter, ValidationError},
+    widgets::textbox::{ErrorDisplay, StyleSheet, TextBox},
+};
+
+/// A [`TextBox`] for a value that is shown and parsed with a [`Formatter`].
+///
+/// Typed and pasted text is checked with
+/// [`Formatter::validate_partial_input`], and the value is only changed when
+/// the text is committed with Enter. If the text isn't a valid value then,
+/// the text box shows the error until the text is edited.
+///
+/// The value is shown with [`Formatter::format`], or with
+/// [`Formatter::format_for_editing`] while the text box is focused.
+///
+/// [`TextBox`]: super::TextBox
+/// [`Formatter`]: crate::text::format::Formatter
+/// [`Formatter::validate_partial_input`]: crate::text::format::Formatter::validate_partial_input
+/// [`Formatter::format`]: crate::text::format::Formatter::format
+/// [`Formatter::format_for_editing`]: crate::text::format::Formatter::format_for_editing
+pub struct ValueTextBox<'a, T> {
+    value: &'a mut T,
+    formatter: Arc<dyn Formatter<T>>,
+    placeholder: &'a str,
+    style: Option<Box<dyn StyleSheet>>,
+    error_display: ErrorDisplay,
+    validate_while_editing: bool,
+}
+
+/// What happened to a [`ValueTextBox`] since it was last built.
+///
+/// [`ValueTextBox`]: ValueTextBox
+#[derive(Debug, Clone)]
+pub struct ValueTextBoxAction<T> {
+    /// The new value, if the text was committed with a valid value.
+    pub committed: Option<T>,
+    /// The error for the current text, or `None` if it is a valid value.
+    pub error: Option<ValidationError>,
+}
+
+/// The text of a [`ValueTextBox`], which is kept between builds.
+struct ValueState {
+    text: String,
+    /// The formatted value that the text was last set to, to tell when the
+    /// value is changed elsewhere.
+    formatted: String,
+    /// Whether `formatted` is formatted for editing.
+    editing: bool,
+    /// The error shown by the text box.
+    error: Option<ValidationError>,
+    /// The id of the text box, once it has been built.
+    id: Option<ChildId>,
+}
+
+impl<'a, T: Clone + 'static> ValueTextBox<'a, T> {
+    /// Create a new `ValueTextBox` for `value`, which is shown and parsed
+    /// with `formatter`.
+    pub fn new(value: &'a mut T, formatter: impl Formatter<T> + 'static) -> Self {
+        ValueTextBox {
+            value,
+            formatter: Arc::new(formatter),
+            placeholder: "",
+            style: None,
+            error_display: ErrorDisplay::Inline,
+            validate_while_editing: false,
+        }
+    }
+
+    /// Builder-style method to set the text shown while the text box is
+    /// empty.
+    pub fn placeholder(mut self, text: &'a str) -> Self {
+        self.placeholder = text;
+        self
+    }
+
+    /// Builder-style method to set the style of the text box.
+    pub fn style(mut self, style: impl Into<Box<dyn StyleSheet>>) -> Self {
+        self.style = Some(style.into());
+        self
+    }
+
+    /// Builder-style method to set how the message of a validation error is
+    /// shown.
+    pub fn error_display(mut self, display: ErrorDisplay) -> Self {
+        self.error_display = display;
+        self
+    }
+
+    /// Builder-style method to show the error for text that isn't a valid
+    /// value while it is edited, rather than only when it is committed.
+    pub fn validate_while_editing(mut self, validate: bool) -> Self {
+        self.validate_while_editing = validate;
+        self
+    }
+
+    #[track_caller]
+    pub fn build(self, ui: &mut Ui) -> ValueTextBoxAction<T> {
+        let caller = Location::caller().into();
+        let init = {
+            let formatted = self.formatter.format(self.value);
+            move || ValueState {
+                text: formatted.clone(),
+                formatted,
+                editing: false,
+                error: None,
+                id: None,
+            }
+        };
+        let mut action = ValueTextBoxAction::default();
+        ui.state_node(caller, init, |ui, state: &mut ValueState| {
+            let editing = state.id.is_some_and(|id| ui.is_focused(id));
+            let formatter = self.formatter.clone();
+            let format = |value: &T| match editing {
+                true => formatter.format_for_editing(value),
+                false => formatter.format(value),
+            };
+            let formatted = format(self.value);
+            if state.formatted != formatted {
+                // text that was edited is kept when the text box only gains
+                // or loses focus
+                let edited = state.text != state.formatted;
+                if !edited || editing == state.editing {
+                    state.text = formatted.clone();
+                    state.error = None;
+                }
+                state.formatted = formatted;
+            }
+            state.editing = editing;
+
+            let previous = state.text.clone();
+            let mut text_box = TextBox::new(&mut state.text)
+                .placeholder(self.placeholder)
+                .formatter(self.formatter.clone())
+                .validation_error(state.error.clone())
+                .error_display(self.error_display);
+            if let Some(style) = self.style {
+                text_box = text_box.style(style);
+            }
+            let text_box = text_box.build_action(ui);
+            state.id = text_box.id;
+
+            if text_box.activated {
+                match self.formatter.value(&state.text) {
+                    Ok(value) => {
+                        state.text = format(&value);
+                        state.formatted = state.text.clone();
+                        state.error = None;
+                        *self.value = value.clone();
+                        action.committed = Some(value);
+                    }
+                    Err(error) => state.error = Some(error),
+                }
+            } else if state.text != previous {
+                state.error = match self.validate_while_editing {
+                    true => self.formatter.value(&state.text).err(),
+                    false => None,
+                };
+            }
+            action.error = self.formatter.value(&state.text).err();
+        });
+        action
+    }
+}
+
+impl<T> ValueTextBoxAction<T> {
+    /// Returns `true` if the current text is a valid value.
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl<T> Default for ValueTextBoxAction<T> {
+    fn default() -> Self {
+        ValueTextBoxAction {
+            committed: None,
+            error: None,
+        }
+    }
+}